@@ -0,0 +1,42 @@
+#!/bin/sh
+# Plays every transcript of this directory against a fresh server.
+#
+# Usage: conformance/run.sh <server> <client> [port]
+
+set -u
+
+server=$1
+client=$2
+port=${3:-4242}
+team=Conformance
+status=0
+
+# Waits until the server started as $pid accepts connections on $port, for at most five
+# seconds. Fails early if the server exits.
+wait_for_server() {
+	tries=50
+	until nc -z 127.0.0.1 "$port" 2>/dev/null; do
+		tries=$((tries - 1))
+		if [ "$tries" -eq 0 ] || ! kill -0 "$pid" 2>/dev/null; then
+			return 1
+		fi
+		sleep 0.1
+	done
+}
+
+for transcript in "$(dirname "$0")"/*.txt; do
+	args=$(sed -n '1s/^# server: //p' "$transcript" | sed "s/\$TEAM/$team/g")
+	# shellcheck disable=SC2086 # The arguments are split on purpose.
+	"$server" -p "$port" $args >/dev/null 2>&1 &
+	pid=$!
+	if wait_for_server; then
+		"$client" -p "$port" -n "$team" --timeout 5 --conformance "$transcript" || status=1
+	else
+		echo "$transcript: the server did not start listening on port $port" >&2
+		status=1
+	fi
+	kill "$pid" 2>/dev/null
+	wait "$pid" 2>/dev/null
+done
+
+exit $status
@@ -1,4 +1,6 @@
 /// Module for the command line arguments.
+use std::path::PathBuf;
+
 use clap::Parser;
 
 /// A Zappy client.
@@ -14,4 +16,27 @@ pub struct Args {
     /// The hostname of the server
     #[clap(short, default_value = "localhost")]
     pub host: String,
+    /// The number of seconds to wait for a message from the server before giving up
+    /// (0 to wait forever). While playing, the player asks for its surroundings once more
+    /// before giving up
+    #[clap(long, default_value_t = 60)]
+    pub timeout: u64,
+    /// Run the commands of a script file instead of playing randomly
+    #[clap(long)]
+    pub script: Option<PathBuf>,
+    /// Check that the server answers as described in a conformance transcript
+    #[clap(long, conflicts_with = "script")]
+    pub conformance: Option<PathBuf>,
+    /// Obfuscate the broadcasts of the team with a key derived from the team name and
+    /// this secret, ignoring the broadcasts that cannot be deobfuscated
+    #[clap(long)]
+    pub secret: Option<String>,
+    /// Open this many connections at once, each playing randomly, and report aggregate
+    /// statistics every second to load-test the server
+    #[clap(long, conflicts_with_all = ["script", "conformance"])]
+    pub swarm: Option<usize>,
+    /// Play with this many players of the team from a single process, connecting a new
+    /// one whenever a player dies, as long as the team has free slots
+    #[clap(long, conflicts_with_all = ["script", "conformance", "swarm"])]
+    pub count: Option<usize>,
 }
@@ -0,0 +1,190 @@
+//! Module for the obfuscation of the broadcasts of a team.
+//!
+//! Every player hears every broadcast, enemies included. Messages are XORed with a
+//! keystream derived from the team name, a secret shared by the players of the team and
+//! a random nonce, then encoded in base64 so that they fit on a line:
+//!
+//! ```text
+//! base64(nonce (8 bytes, little endian) || (MAGIC || message) ^ keystream)
+//! ```
+//!
+//! This is not meant to resist a determined attacker, only to keep the other teams from
+//! reading the messages. Nothing authenticates them: an enemy can replay a message it
+//! heard, or flip bits of one without knowing the secret.
+
+/// The bytes prepended to every message before it is obfuscated, which tell messages of
+/// the team apart from the others once deobfuscated.
+const MAGIC: &[u8] = b"zappy:";
+
+/// The length of the nonce prepended to every obfuscated message.
+const NONCE_LEN: usize = 8;
+
+/// The characters used by base64, in the order of their value.
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Obfuscates and deobfuscates the broadcasts of a team.
+#[derive(Debug, Clone)]
+pub struct Cipher {
+    /// The key derived from the team name and the secret.
+    key: u64,
+}
+
+impl Cipher {
+    /// Creates the cipher of a team, from its name and the secret shared by its players.
+    pub fn new(team: &str, secret: &str) -> Self {
+        // FNV-1a, which gives the same key whatever the build of the client.
+        let mut key: u64 = 0xcbf2_9ce4_8422_2325;
+        for &byte in team.as_bytes().iter().chain(b"\0").chain(secret.as_bytes()) {
+            key = (key ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+        Self { key }
+    }
+
+    /// Obfuscates a message, using `nonce` to make the output differ between messages.
+    pub fn encode(&self, message: &str, nonce: u64) -> String {
+        let mut keystream = Keystream::new(self.key ^ nonce);
+        let mut bytes = nonce.to_le_bytes().to_vec();
+        bytes.extend(
+            MAGIC
+                .iter()
+                .chain(message.as_bytes())
+                .map(|&byte| byte ^ keystream.next_byte()),
+        );
+        encode_base64(&bytes)
+    }
+
+    /// Deobfuscates a message.
+    ///
+    /// Returns `None` if the message was not obfuscated with the key of the team, for
+    /// instance because it was sent by another team.
+    pub fn decode(&self, text: &str) -> Option<String> {
+        let bytes = decode_base64(text)?;
+        if bytes.len() < NONCE_LEN + MAGIC.len() {
+            return None;
+        }
+        let (nonce, payload) = bytes.split_at(NONCE_LEN);
+        let nonce = u64::from_le_bytes(nonce.try_into().ok()?);
+
+        let mut keystream = Keystream::new(self.key ^ nonce);
+        let plain: Vec<u8> = payload
+            .iter()
+            .map(|&byte| byte ^ keystream.next_byte())
+            .collect();
+        let message = plain.strip_prefix(MAGIC)?;
+        String::from_utf8(message.to_vec()).ok()
+    }
+}
+
+/// A stream of pseudo-random bytes (xorshift64*).
+struct Keystream {
+    /// The internal state of the generator. Never zero.
+    state: u64,
+}
+
+impl Keystream {
+    /// Creates a keystream from a seed.
+    fn new(seed: u64) -> Self {
+        Self { state: seed.max(1) }
+    }
+
+    /// Returns the next byte of the stream.
+    fn next_byte(&mut self) -> u8 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        (self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 56) as u8
+    }
+}
+
+/// Encodes bytes in base64, with padding.
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | u32::from(byte) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Decodes base64 with padding, returning `None` if the text is not valid base64.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.as_bytes();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(text.len() / 4 * 3);
+    let last = text.len() / 4;
+    for (index, chunk) in text.chunks(4).enumerate() {
+        // Only the last chunk may be padded.
+        let padding = chunk.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding != 0 && index + 1 != last) {
+            return None;
+        }
+        let mut n = 0u32;
+        for (i, &c) in chunk[..4 - padding].iter().enumerate() {
+            let value = BASE64.iter().position(|&b| b == c)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+        out.extend(n.to_be_bytes()[1..4 - padding].iter());
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_is_padded() {
+        assert_eq!(encode_base64(b""), "");
+        assert_eq!(encode_base64(b"f"), "Zg==");
+        assert_eq!(encode_base64(b"fo"), "Zm8=");
+        assert_eq!(encode_base64(b"foo"), "Zm9v");
+        assert_eq!(encode_base64(b"foob"), "Zm9vYg==");
+    }
+
+    #[test]
+    fn base64_round_trips() {
+        for text in [
+            "", "Zg==", "Zm8=", "Zm9v", "Zm9vYg==", "Zm9vYmE=", "Zm9vYmFy",
+        ] {
+            assert_eq!(encode_base64(&decode_base64(text).unwrap()), text);
+        }
+    }
+
+    #[test]
+    fn invalid_base64_is_rejected() {
+        assert_eq!(decode_base64("Zg="), None);
+        assert_eq!(decode_base64("Z==="), None);
+        assert_eq!(decode_base64("Zg==Zg=="), None);
+        assert_eq!(decode_base64("Z!=="), None);
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let cipher = Cipher::new("red", "secret");
+        for message in ["", "hello", "rendezvous 3 4"] {
+            let text = cipher.encode(message, 42);
+            assert_eq!(cipher.decode(&text).as_deref(), Some(message));
+        }
+    }
+
+    #[test]
+    fn other_teams_cannot_decode() {
+        let text = Cipher::new("red", "secret").encode("hello", 42);
+        assert_eq!(Cipher::new("blue", "secret").decode(&text), None);
+        assert_eq!(Cipher::new("red", "other").decode(&text), None);
+        assert_eq!(Cipher::new("red", "secret").decode("hello"), None);
+    }
+}
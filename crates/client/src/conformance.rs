@@ -0,0 +1,150 @@
+/// Module for protocol conformance transcripts.
+///
+/// A transcript describes a conversation with the server, one line per message:
+///
+/// - `> <line>`: a line sent by the client.
+/// - `< <line>`: a line the server must answer with.
+///
+/// `$TEAM` is replaced with the name of the team passed on the command-line. Empty lines
+/// and lines starting with `#` are ignored. Heartbeat pings sent to graphics monitors
+/// may arrive at any time, so they are skipped.
+///
+/// On top of checking the answers of the server, every player command is parsed and
+/// serialized again by the client, and so is every answer of the server once the
+/// handshake is done. Both must give back the exact same line, so that the parsers and
+/// serializers of the client cannot drift from the protocol either.
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::Duration;
+
+use crate::args::Args;
+use crate::server::commands::{Command, Msg};
+use crate::server::Result;
+
+/// The number of lines exchanged with the server before the first command of a player:
+/// `BIENVENUE`, the team name, the number of slots and the dimensions of the world.
+const HANDSHAKE_LINES: usize = 4;
+
+/// The team name used by graphics monitors, which do not speak the player protocol.
+const GRAPHIC: &str = "GRAPHIC";
+
+/// Plays a transcript against the server, reporting every mismatch on the standard
+/// error.
+///
+/// # Returns
+///
+/// The number of mismatches found.
+pub fn run(args: &Args, transcript: &str) -> Result<usize> {
+    let stream = TcpStream::connect((args.host.as_str(), args.port))?;
+    if args.timeout != 0 {
+        stream.set_read_timeout(Some(Duration::from_secs(args.timeout)))?;
+    }
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    let mut mismatches = 0;
+    let mut exchanged = 0;
+    let mut is_player = true;
+    let mut received = String::new();
+
+    for (index, line) in transcript.lines().enumerate() {
+        let line_number = index + 1;
+        let mut report = |message: String| {
+            eprintln!("line {}: {}", line_number, message);
+            mismatches += 1;
+        };
+
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.replace("$TEAM", &args.name);
+        let in_game = is_player && exchanged >= HANDSHAKE_LINES;
+        exchanged += 1;
+
+        if let Some(sent) = line.strip_prefix("> ") {
+            if exchanged == 2 {
+                is_player = sent != GRAPHIC;
+            }
+            if in_game {
+                check_command_reserialized(sent, &mut report);
+            }
+            writer.write_fmt(format_args!("{}\n", sent))?;
+        } else if let Some(expected) = line.strip_prefix("< ") {
+            let got = loop {
+                received.clear();
+                if reader.read_line(&mut received)? == 0 {
+                    report("the server closed the connection".into());
+                    return Ok(mismatches);
+                }
+                let got = received.trim_end_matches('\n');
+                if got != "smg ping" {
+                    break got;
+                }
+            };
+            if got != expected {
+                report(format!("expected `{}`, got `{}`", expected, got));
+            }
+            if in_game {
+                check_reserialized(expected, &mut report);
+            }
+        } else {
+            report(format!("lines must start with `> ` or `< `: `{}`", line));
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Checks that the client gives back `line` when parsing and serializing a command.
+///
+/// Commands the client does not know are sent as is: they check that the server rejects
+/// them.
+fn check_command_reserialized(line: &str, report: &mut impl FnMut(String)) {
+    if let Ok(command) = Command::parse(line) {
+        if command.to_string() != line {
+            report(format!("`{}` is serialized as `{}`", line, command));
+        }
+    }
+}
+
+/// Checks that the client gives back `line` when parsing and serializing an answer of
+/// the server.
+fn check_reserialized(line: &str, report: &mut impl FnMut(String)) {
+    let serialized = match line.parse() {
+        Ok(Msg::Notif(notif)) => notif.to_string(),
+        Ok(Msg::Response(response)) => response.to_string(),
+        Err(err) => return report(format!("`{}` cannot be parsed: {}", line, err)),
+    };
+    if serialized != line {
+        report(format!("`{}` is serialized as `{}`", line, serialized));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks, without a server, that the client gives back every line of the player
+    /// transcript past the handshake.
+    #[test]
+    fn player_transcript_is_reserialized() {
+        let transcript = include_str!("../../../conformance/player.txt");
+        let mut mismatches = Vec::new();
+        let mut report = |message| mismatches.push(message);
+
+        let lines = transcript
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'));
+        for line in lines.skip(HANDSHAKE_LINES) {
+            if let Some(sent) = line.strip_prefix("> ") {
+                check_command_reserialized(sent, &mut report);
+            } else if let Some(expected) = line.strip_prefix("< ") {
+                check_reserialized(expected, &mut report);
+            } else {
+                report(format!("lines must start with `> ` or `< `: `{}`", line));
+            }
+        }
+
+        assert!(mismatches.is_empty(), "{:#?}", mismatches);
+    }
+}
@@ -0,0 +1,220 @@
+/// Module for the coordinator role: preparing and guarding an elevation.
+///
+/// Once the player holds (or stands on) every stone needed for its next elevation, the
+/// coordinator takes over the random strategy:
+///
+/// 1. It looks at the tile, so that the elevation is never started blindly.
+/// 2. It clears the tile with `expulse` when more players than needed stand on it.
+/// 3. Once enough players are there, it weighs the risk of the elevation being
+///    interrupted: it may move somewhere quieter, call a guard or wait a little.
+/// 4. It drops the missing stones from the inventory.
+/// 5. It broadcasts a rendezvous while players are missing.
+/// 6. It starts the incantation, and gives up cleanly if the server answers `ko`.
+///
+/// Until then, it walks towards the rendezvous called by other players of the team.
+///
+/// When the team shares a secret, the rendezvous is obfuscated so that other teams can
+/// neither understand it nor fake it.
+use resources::elevation_requirement;
+
+use crate::cipher::Cipher;
+use crate::planner::{ElevationTiming, Planner, MAX_ELEVATION_DELAYS};
+use crate::server::commands::{Command, Notif, Object, Response};
+
+/// The message broadcast to call the players of the team to the tile of the elevation.
+const RENDEZVOUS: &str = "rendezvous";
+
+/// Prepares and guards the elevations of the player.
+#[derive(Debug)]
+pub struct Coordinator {
+    /// The content of the tile of the player, if it is known to be up to date.
+    tile: Option<Vec<Object>>,
+    /// Whether the rendezvous was broadcast since the tile was last looked at.
+    called: bool,
+    /// The cipher of the team, if its broadcasts are obfuscated.
+    cipher: Option<Cipher>,
+    /// The rendezvous message, as broadcast.
+    ///
+    /// It is computed once, and lives as long as the program.
+    rendezvous: &'static str,
+    /// The direction of the last rendezvous heard, if the player did not move towards it
+    /// yet.
+    heard: Option<u8>,
+    /// The precautions taken against the next elevation being interrupted.
+    precautions: Precautions,
+}
+
+/// The precautions taken against an elevation being interrupted.
+#[derive(Debug, Default)]
+struct Precautions {
+    /// Whether the risk of the elevation being interrupted was found low enough.
+    cleared: bool,
+    /// Whether a player of the team was called to stand guard during the elevation.
+    guarded: bool,
+    /// The number of times in a row the elevation was put off.
+    delays: u32,
+}
+
+impl Precautions {
+    /// Decides whether the elevation can start on `tile` given the risk of it being
+    /// interrupted, returning the command putting it off otherwise.
+    ///
+    /// A guard is called with the `rendezvous` of the team.
+    fn weigh_risk(
+        &mut self,
+        planner: &Planner,
+        tile: &[Object],
+        rendezvous: &'static str,
+    ) -> Option<Command<'static>> {
+        let timing = planner.elevation_timing(tile, self.delays);
+        if !matches!(timing, ElevationTiming::Now) {
+            self.delays += 1;
+        }
+        match timing {
+            ElevationTiming::Now => {
+                self.cleared = true;
+                None
+            }
+            ElevationTiming::Delay => Some(Command::Look),
+            ElevationTiming::Guard => {
+                println!("elevation at risk, calling a guard");
+                self.guarded = true;
+                Some(Command::Broadcast(rendezvous))
+            }
+            ElevationTiming::Relocate(command) => Some(command),
+        }
+    }
+}
+
+impl Coordinator {
+    /// Creates a coordinator, obfuscating its broadcasts with `cipher` if provided.
+    pub fn new(cipher: Option<Cipher>) -> Self {
+        let rendezvous = match &cipher {
+            Some(cipher) => cipher.encode(RENDEZVOUS, crate::rand64()).leak(),
+            None => RENDEZVOUS,
+        };
+        Self {
+            tile: None,
+            called: false,
+            cipher,
+            rendezvous,
+            heard: None,
+            precautions: Precautions::default(),
+        }
+    }
+
+    /// Returns the next command needed to perform the elevation, or `None` when the
+    /// player is not ready to coordinate one.
+    pub fn next_command(&mut self, planner: &Planner) -> Option<Command<'static>> {
+        let requirement = elevation_requirement(planner.level().into())?;
+        let known = self.tile.as_deref().unwrap_or_default();
+        if planner.must_eat() {
+            return None;
+        }
+        if !planner.missing(known).is_empty() {
+            return self.heard.take().and_then(step_towards);
+        }
+
+        let Some(tile) = &self.tile else {
+            return Some(Command::Look);
+        };
+
+        let players = tile.iter().filter(|&&o| o == Object::Player).count();
+        let needed = usize::from(requirement.players) + usize::from(self.precautions.guarded);
+        if players > needed {
+            return Some(Command::Kick);
+        }
+        // The risk is weighed before the stones are dropped, so that a player elevating
+        // alone can still take them elsewhere.
+        if players == needed && !self.precautions.cleared {
+            let put_off = self.precautions.weigh_risk(planner, tile, self.rendezvous);
+            if put_off.is_some() {
+                return put_off;
+            }
+        }
+        if let Some(stone) = planner.stone_to_drop(tile) {
+            return Some(Command::Drop(stone));
+        }
+        if players < needed {
+            // A guard that does not show up is not waited for forever.
+            if self.precautions.guarded && players + 1 == needed {
+                self.precautions.delays += 1;
+                if self.precautions.delays >= 2 * MAX_ELEVATION_DELAYS {
+                    self.precautions.guarded = false;
+                }
+            }
+            // Alternate between calling the team and looking whether it arrived.
+            self.called = !self.called;
+            return Some(if self.called {
+                Command::Broadcast(self.rendezvous)
+            } else {
+                Command::Look
+            });
+        }
+        // Everything should be in place, but the tile is checked one last time.
+        Some(if planner.ready_to_elevate(tile) {
+            Command::Incantation
+        } else {
+            Command::Look
+        })
+    }
+
+    /// Listens to the broadcasts heard while waiting for the last response.
+    ///
+    /// Messages that cannot be deobfuscated come from other teams and are ignored.
+    pub fn listen(&mut self, notifications: &[Notif]) {
+        for notif in notifications {
+            let Notif::Message(direction, text) = notif else {
+                continue;
+            };
+            let message = match &self.cipher {
+                Some(cipher) => cipher.decode(text),
+                None => Some(text.clone()),
+            };
+            if message.as_deref() == Some(RENDEZVOUS) {
+                self.heard = Some(*direction);
+            }
+        }
+    }
+
+    /// Updates the knowledge of the coordinator with the response of the server to a
+    /// command.
+    pub fn update(&mut self, command: &Command, response: &Response) {
+        match (command, response) {
+            (_, Response::Seen(seen)) => self.tile = seen.first().cloned(),
+            (Command::Drop(object), Response::Ok) => {
+                if let Some(tile) = &mut self.tile {
+                    tile.push(*object);
+                }
+            }
+            (Command::Incantation, response) => {
+                if let Response::Ko = response {
+                    println!("incantation aborted");
+                }
+                self.tile = None;
+                self.precautions = Precautions::default();
+            }
+            // The risk was weighed for the tile the player left.
+            (Command::Forward, _) => {
+                self.tile = None;
+                self.precautions.cleared = false;
+            }
+            // Expelled players, elevations and anything unexpected change the tile.
+            (Command::Broadcast(_), _) => (),
+            _ => self.tile = None,
+        }
+    }
+}
+
+/// Returns the command moving the player towards a broadcast heard from `direction`.
+///
+/// Directions are numbered counterclockwise from `1`, the front of the player, `0` being
+/// the tile of the player itself.
+fn step_towards(direction: u8) -> Option<Command<'static>> {
+    match direction {
+        1 | 2 | 8 => Some(Command::Forward),
+        3..=5 => Some(Command::Left),
+        6 | 7 => Some(Command::Right),
+        _ => None,
+    }
+}
@@ -4,10 +4,37 @@
 #![warn(missing_docs, clippy::must_use_candidate)]
 
 mod args;
+mod cipher;
+mod conformance;
+mod coordinator;
+mod orchestrator;
+mod pipeline;
+mod planner;
+mod script;
 mod server;
+mod swarm;
+mod watchdog;
 
-use server::commands::Msg;
-use server::{Command, Result, Server};
+use args::Args;
+use cipher::Cipher;
+use clap::Parser;
+use coordinator::Coordinator;
+use pipeline::Pipeline;
+use planner::Planner;
+use script::{ScriptStats, Step};
+use server::commands::{Object, Response};
+use server::{Command, Result, Server, ServerError};
+use watchdog::{Action, Watchdog};
+
+/// The exit code used when the player dies, so that orchestration scripts can tell it
+/// apart from errors and spawn a new AI.
+const EXIT_DEAD: i32 = 3;
+
+/// The exit code used when the script passed with `--script` is invalid.
+const EXIT_USAGE: i32 = 2;
+
+/// The exit code used when the server does not follow a conformance transcript.
+const EXIT_NONCONFORMANT: i32 = 4;
 
 /// Generate a random 64-bit integer.
 fn rand64() -> u64 {
@@ -16,17 +43,35 @@ fn rand64() -> u64 {
 }
 
 /// Send a random command to the server.
-fn random_command() -> Command<'static> {
+///
+/// When picking up an object, the stone the planner needs the most is preferred. When the
+/// player is about to starve, it only looks for food and avoids crowded tiles. Stones
+/// gathered by other players on the current tile are taken when possible.
+fn random_command(planner: &Planner, tile: &[Object]) -> Command<'static> {
     use Command::*;
     let random_number = rand64();
+    if planner.must_eat() {
+        if tile.contains(&Object::Food) {
+            return Take(Object::Food);
+        }
+        return match random_number % 3 {
+            0 if planner.front_is_crowded() => Left,
+            0 => Forward,
+            1 => Look,
+            _ => Inventory,
+        };
+    }
+    if let Some(stone) = planner.stone_to_steal() {
+        return Take(stone);
+    }
     match random_number % 12 {
         0 => Forward,
         1 => Right,
         2 => Left,
         3 => Look,
         4 => Inventory,
-        5 => Take(server::commands::Object::Food),
-        6 => Drop(server::commands::Object::Food),
+        5 => Take(planner.scarcest_missing(tile).unwrap_or(Object::Food)),
+        6 => Drop(Object::Food),
         7 => Kick,
         8 => Broadcast("Hello, world!"),
         9 => Incantation,
@@ -36,10 +81,195 @@ fn random_command() -> Command<'static> {
     }
 }
 
+/// Asks the server for the surroundings and the inventory of the player, so that the
+/// planner starts again from what the server knows.
+///
+/// A command may still be waiting for its response: responses are discarded until the
+/// one to `voir`, after which they match the commands again.
+///
+/// # Returns
+///
+/// `false` if the player died in the meantime.
+fn resync(server: &mut Server, planner: &mut Planner) -> Result<bool> {
+    server.send_command(Command::Look)?;
+    server.send_command(Command::Inventory)?;
+    let mut seen = false;
+    loop {
+        let Some(response) = server.receive_response()? else {
+            return Ok(false);
+        };
+        match response {
+            Response::Seen(_) => {
+                planner.update(&Command::Look, &response);
+                seen = true;
+            }
+            Response::Inventory(_) if seen => {
+                planner.update(&Command::Inventory, &response);
+                return Ok(true);
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Reports the death of the player, then exits.
+fn die(planner: &Planner, commands_sent: u64) -> ! {
+    println!(
+        "player died at level {} after {} commands",
+        planner.level(),
+        commands_sent
+    );
+    if let Some(tick) = planner.tick_duration() {
+        println!("estimated time unit: {:?}", tick);
+    }
+    if let Some(round_trip) = planner.round_trip() {
+        println!("estimated round-trip time: {:?}", round_trip);
+    }
+    std::process::exit(EXIT_DEAD);
+}
+
+/// Runs the steps of a script, then exits.
+fn script_mode(server: &mut Server, steps: &[Step]) -> Result<()> {
+    let mut stats = ScriptStats::default();
+    let alive = script::run(server, steps, &mut stats)?;
+    println!(
+        "script finished: {} commands sent, {} failed",
+        stats.sent, stats.failed
+    );
+    if !alive {
+        println!("player died while running the script");
+        std::process::exit(EXIT_DEAD);
+    }
+    Ok(())
+}
+
 fn main() -> Result<()> {
-    let mut server = Server::new()?;
+    let args = Args::parse();
+
+    if let Some(path) = &args.conformance {
+        let transcript = std::fs::read_to_string(path)?;
+        let mismatches = conformance::run(&args, &transcript)?;
+        if mismatches != 0 {
+            eprintln!("{}: {} mismatches", path.display(), mismatches);
+            std::process::exit(EXIT_NONCONFORMANT);
+        }
+        println!("{}: conformant", path.display());
+        return Ok(());
+    }
+
+    if let Some(count) = args.swarm {
+        swarm::run(&args, count);
+    }
+    if let Some(count) = args.count {
+        orchestrator::run(&args, count);
+    }
+
+    // The script is loaded before connecting, so that a typo does not take a slot.
+    let source = args
+        .script
+        .as_deref()
+        .map(std::fs::read_to_string)
+        .transpose()?;
+    let steps = match (&args.script, &source) {
+        (Some(path), Some(source)) => match script::parse(source) {
+            Ok(steps) => Some(steps),
+            Err(err) => {
+                eprintln!("{}: {}", path.display(), err);
+                std::process::exit(EXIT_USAGE);
+            }
+        },
+        _ => None,
+    };
+
+    let mut server = Server::new(&args)?;
+    if let Some(steps) = &steps {
+        return script_mode(&mut server, steps);
+    }
+
+    let (planner, commands_sent) = play(&mut server, &args)?;
+    die(&planner, commands_sent)
+}
+
+/// Plays until the player dies.
+///
+/// # Returns
+///
+/// What the player knew when it died, and the number of commands it sent.
+fn play(server: &mut Server, args: &Args) -> Result<(Planner, u64)> {
+    let mut planner = Planner::new();
+    planner.set_map_size(server.map_size());
+    let cipher = args
+        .secret
+        .as_deref()
+        .map(|secret| Cipher::new(&args.name, secret));
+    let mut coordinator = Coordinator::new(cipher);
+    let mut watchdog = Watchdog::default();
+    let mut pipeline = Pipeline::default();
+    let mut tile = Vec::new();
+    let mut commands_sent = 0u64;
     loop {
-        server.send_command(random_command())?;
-        while let Msg::Notif(_) = server.receive()? {}
+        // The commands fetching a remembered resource or leading to a place worth
+        // exploring are sent without waiting for the responses to the previous ones,
+        // anything else is sent alone.
+        if pipeline.is_empty() {
+            if let Some(command) = coordinator.next_command(&planner) {
+                pipeline.plan([command]);
+            } else {
+                match planner.fetch_wanted() {
+                    Some(commands) => pipeline.plan(commands),
+                    None => match planner.explore() {
+                        Some(commands) => pipeline.plan(commands),
+                        None => pipeline.plan([random_command(&planner, &tile)]),
+                    },
+                }
+            }
+        }
+        while let Some(command) = pipeline.next_to_send() {
+            server.send_command(command)?;
+            commands_sent += 1;
+        }
+
+        let action = match server.receive_response() {
+            Ok(Some(response)) => {
+                let Some((command, sent_at, alone)) = pipeline.complete(&response) else {
+                    continue;
+                };
+                // The content of the current tile is only known right after looking
+                // around.
+                tile = match &response {
+                    Response::Seen(seen) => seen.first().cloned().unwrap_or_default(),
+                    _ => Vec::new(),
+                };
+                if alone {
+                    planner.record_latency(&command, sent_at);
+                }
+                planner.update(&command, &response);
+                planner.listen(server.notifications());
+                coordinator.listen(server.notifications());
+                coordinator.update(&command, &response);
+                watchdog.check(&command, &response, &planner)
+            }
+            Ok(None) => return Ok((planner, commands_sent)),
+            Err(ServerError::Timeout) => {
+                println!("the server stopped answering");
+                watchdog.timed_out()
+            }
+            Err(err) => return Err(err),
+        };
+
+        match action {
+            Action::Continue => continue,
+            Action::Resync => println!("player seems stuck, resynchronizing"),
+            Action::Reset => {
+                println!("player still stuck, forgetting what it knows");
+                planner.reset();
+                watchdog.reset(&planner);
+            }
+        }
+        pipeline.clear();
+        tile.clear();
+        if !resync(server, &mut planner)? {
+            return Ok((planner, commands_sent));
+        }
     }
 }
@@ -0,0 +1,90 @@
+/// Module for the orchestrator mode, where several players of the same team play from a
+/// single process.
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::args::Args;
+use crate::server::{Server, ServerError};
+
+/// The time to wait before trying again when a player could not connect for another
+/// reason than the team being full.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// What the orchestrator knows about the players of the team.
+#[derive(Debug, Default)]
+struct Team {
+    /// The number of players currently playing.
+    alive: usize,
+    /// The number of players that died.
+    deaths: u32,
+    /// The highest level a player of the team died at.
+    best_level: u8,
+}
+
+/// Locks the team, even if a player panicked while holding it.
+fn lock(team: &Mutex<Team>) -> MutexGuard<'_, Team> {
+    team.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Runs `count` players of the team at once, each in its own thread.
+///
+/// A player that dies is replaced by a new connection, as long as the server lets it
+/// join the team. Once no player is left and the team has no free slot, the process
+/// exits as if its only player had died.
+pub fn run(args: &Args, count: usize) -> ! {
+    let team = Mutex::new(Team::default());
+    std::thread::scope(|scope| {
+        for index in 1..=count {
+            let team = &team;
+            scope.spawn(move || supervise(args, team, index));
+        }
+    });
+
+    let team = lock(&team);
+    println!(
+        "no free slot left in the team: {} players died, the best at level {}",
+        team.deaths, team.best_level
+    );
+    std::process::exit(crate::EXIT_DEAD);
+}
+
+/// Connects the `index`-th player and makes it play, connecting a new one whenever it
+/// dies, until the team has no free slot left.
+fn supervise(args: &Args, team: &Mutex<Team>, index: usize) {
+    loop {
+        let mut server = match Server::new(args) {
+            Ok(server) => server,
+            Err(ServerError::Rejected) => {
+                println!("player {index}: no free slot left in the team");
+                return;
+            }
+            Err(err) => {
+                println!("player {index}: failed to connect: {err}");
+                std::thread::sleep(RETRY_DELAY);
+                continue;
+            }
+        };
+        lock(team).alive += 1;
+
+        let outcome = crate::play(&mut server, args);
+        let mut team = lock(team);
+        team.alive -= 1;
+        match outcome {
+            Ok((planner, commands_sent)) => {
+                team.deaths += 1;
+                team.best_level = team.best_level.max(planner.level());
+                println!(
+                    "player {index} died at level {} after {} commands, {} players left",
+                    planner.level(),
+                    commands_sent,
+                    team.alive
+                );
+            }
+            Err(err) => {
+                println!("player {index}: {err}");
+                drop(team);
+                std::thread::sleep(RETRY_DELAY);
+            }
+        }
+    }
+}
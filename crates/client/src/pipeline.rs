@@ -0,0 +1,76 @@
+/// Module for sending several commands without waiting for their responses.
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use crate::server::commands::{Command, Response};
+
+/// The maximum number of commands waiting for their response at once.
+///
+/// The server keeps up to 10 commands per player, but the further ahead the player plans,
+/// the more it has to throw away when something unexpected happens.
+pub const MAX_IN_FLIGHT: usize = 4;
+
+/// The commands the player planned to send, and the ones waiting for their response.
+///
+/// Commands are only planned in advance to fetch an object: moves along a path, which
+/// cannot fail and whose outcome does not change which command comes next, then taking
+/// the object. Any other command is sent alone, once every response has been received.
+#[derive(Debug, Default)]
+pub struct Pipeline {
+    /// The commands planned but not sent yet.
+    planned: VecDeque<Command<'static>>,
+    /// The commands sent, in order, with the time at which they were sent and whether no
+    /// other command was in flight then.
+    in_flight: VecDeque<(Command<'static>, Instant, bool)>,
+}
+
+impl Pipeline {
+    /// Returns whether no command is planned or waiting for its response, in which case
+    /// the player must decide what to do next.
+    pub fn is_empty(&self) -> bool {
+        self.planned.is_empty() && self.in_flight.is_empty()
+    }
+
+    /// Plans to send the provided commands, after the ones already planned.
+    pub fn plan(&mut self, commands: impl IntoIterator<Item = Command<'static>>) {
+        self.planned.extend(commands);
+    }
+
+    /// Returns the next planned command that can be sent right away, if any.
+    ///
+    /// The command is considered in flight from now on.
+    pub fn next_to_send(&mut self) -> Option<Command<'static>> {
+        if self.in_flight.len() >= MAX_IN_FLIGHT {
+            return None;
+        }
+        let command = self.planned.pop_front()?;
+        let alone = self.in_flight.is_empty();
+        self.in_flight.push_back((command, Instant::now(), alone));
+        Some(command)
+    }
+
+    /// Returns the command the response that just arrived answers, with the time at which
+    /// it was sent and whether it was sent alone.
+    ///
+    /// The server only starts executing a command once the ones sent before it are done:
+    /// the time a command took only tells something about the server when it was sent
+    /// alone.
+    ///
+    /// When the command failed, the commands planned after it are dropped: they assumed
+    /// it would succeed. The ones already in flight complete anyway, and the planner keeps
+    /// track of where they lead as their responses arrive.
+    pub fn complete(&mut self, response: &Response) -> Option<(Command<'static>, Instant, bool)> {
+        let completed = self.in_flight.pop_front()?;
+        if let Response::Ko = response {
+            self.planned.clear();
+        }
+        Some(completed)
+    }
+
+    /// Forgets every command, when the responses to the ones in flight are going to be
+    /// discarded.
+    pub fn clear(&mut self) {
+        self.planned.clear();
+        self.in_flight.clear();
+    }
+}
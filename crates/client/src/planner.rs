@@ -0,0 +1,753 @@
+/// Module planning the gathering of resources for the next elevation.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use resources::{elevation_requirement, ObjectClass, ResourceSet};
+use server_clock::ServerClock;
+
+use crate::server::commands::{Command, Notif, Object, Response};
+
+/// The number of ticks a unit of food keeps a player alive.
+const TICKS_PER_FOOD: u32 = 126;
+
+/// The amount of food a player starts with.
+const INITIAL_FOOD: u32 = 10;
+
+/// The remaining life under which the player must stop gathering stones and look for
+/// food.
+///
+/// It is expressed in seconds rather than in ticks because the round-trip time to the
+/// server does not depend on the tick rate.
+const FOOD_SAFETY_MARGIN: Duration = Duration::from_secs(10);
+
+/// The weight of a new measurement in the estimation of the tick duration.
+const TICK_ESTIMATE_WEIGHT: f64 = 0.2;
+
+/// The number of other players on a tile above which it is considered too dangerous to
+/// walk into while food is low.
+const CROWD_THRESHOLD: usize = 3;
+
+/// The amount of time after which the confidence in what was seen on a tile is halved.
+///
+/// Other players keep picking up and dropping objects, so old observations are less and
+/// less likely to still be true.
+const OBSERVATION_HALF_LIFE: Duration = Duration::from_secs(15);
+
+/// The confidence under which an observation is forgotten.
+const MIN_CONFIDENCE: f64 = 0.05;
+
+/// The farthest the player goes to explore, in moves.
+const EXPLORATION_RADIUS: i64 = 6;
+
+/// The information under which looking around from a place is not worth the trip, as the
+/// number of tiles that were never seen.
+///
+/// Tiles seen a while ago count as a fraction of a tile, depending on the confidence in
+/// what was seen there.
+const MIN_INFORMATION_GAIN: f64 = 2.0;
+
+/// The distance, in moves, within which the other players seen are a threat to an
+/// elevation.
+const THREAT_RADIUS: i64 = 3;
+
+/// The amount of time after which a kick counts half as much in the risk of an
+/// elevation being interrupted.
+const KICK_HALF_LIFE: Duration = Duration::from_secs(30);
+
+/// The threat posed by a player seen nearby, as a rate of interruptions.
+const NEARBY_PLAYER_THREAT: f64 = 0.3;
+
+/// The threat posed by a recent kick, as a rate of interruptions.
+const KICK_THREAT: f64 = 0.5;
+
+/// The risk of being interrupted above which an elevation is not started right away.
+const RISK_TOLERANCE: f64 = 0.5;
+
+/// The number of times in a row an elevation may be put off because of the risk of it
+/// being interrupted, before it is started anyway.
+pub const MAX_ELEVATION_DELAYS: u32 = 4;
+
+/// The content of a tile, as seen by the player.
+#[derive(Debug)]
+struct Observation {
+    /// The objects seen on the tile, the player itself excluded.
+    objects: Vec<Object>,
+    /// When the tile was seen.
+    seen_at: Instant,
+}
+
+impl Observation {
+    /// Returns how likely the observation is to still be true, between 0 and 1.
+    fn confidence(&self) -> f64 {
+        decay(self.seen_at.elapsed(), OBSERVATION_HALF_LIFE)
+    }
+}
+
+/// Returns the weight of something that happened `age` ago, halved every `half_life`.
+fn decay(age: Duration, half_life: Duration) -> f64 {
+    0.5f64.powf(age.as_secs_f64() / half_life.as_secs_f64())
+}
+
+/// Returns the direction the player faces after a command, if it succeeds.
+fn turned((dx, dy): (i64, i64), command: &Command) -> (i64, i64) {
+    match command {
+        Command::Left => (dy, -dx),
+        Command::Right => (-dy, dx),
+        _ => (dx, dy),
+    }
+}
+
+/// Returns the position of the `column`-th tile of the `row`-th row seen by a player at
+/// `from` facing `(dx, dy)`.
+///
+/// Row `r` holds `2r + 1` tiles, from column `-r` on the left to column `r` on the right.
+fn tile_in_view(from: (i64, i64), (dx, dy): (i64, i64), row: i64, column: i64) -> (i64, i64) {
+    (
+        from.0 + row * dx - column * dy,
+        from.1 + row * dy + column * dx,
+    )
+}
+
+/// Returns the number of players among the provided objects.
+fn players_in(objects: &[Object]) -> usize {
+    objects
+        .iter()
+        .filter(|&&object| object == Object::Player)
+        .count()
+}
+
+/// What the player should do about an elevation it is ready to start, given the risk of
+/// it being interrupted.
+#[derive(Debug, Clone, Copy)]
+pub enum ElevationTiming {
+    /// The risk is low enough: the elevation starts right away.
+    Now,
+    /// The player looks around once more, hoping for the threat to go away.
+    Delay,
+    /// The player calls one more player of its team, to stand guard on the tile.
+    Guard,
+    /// The player needs nobody else: it moves towards the quietest place it knows of,
+    /// with the command provided.
+    Relocate(Command<'static>),
+}
+
+/// Returns the resources among the provided objects, players left out.
+fn resources_of(objects: &[Object]) -> ResourceSet {
+    objects.iter().filter_map(|object| object.class()).collect()
+}
+
+/// Keeps track of what the player knows about the game to plan its next elevation.
+#[derive(Debug)]
+pub struct Planner {
+    /// The current level of the player.
+    level: u8,
+    /// The content of the inventory, the last time it was checked, along with the objects
+    /// taken and dropped since.
+    inventory: ResourceSet,
+    /// The position of the player, relative to where it spawned, as tracked from its
+    /// own moves.
+    ///
+    /// Moves the player did not make, such as being kicked, are not taken into account:
+    /// this only matters until the observations made before them fade away.
+    position: (i64, i64),
+    /// The direction the player is facing, as a unit vector.
+    facing: (i64, i64),
+    /// The width and height of the map, once known.
+    map_size: Option<(i64, i64)>,
+    /// What the player saw on the tiles around it, by position.
+    ///
+    /// Once the size of the map is known, positions are wrapped around it, so that a tile
+    /// is remembered once whichever way the player went around the map.
+    memory: HashMap<(i64, i64), Observation>,
+    /// When the inventory was last checked.
+    food_checked_at: Instant,
+    /// The estimated duration of a tick of the server, once a command has completed.
+    tick_duration: Option<Duration>,
+    /// The estimated round-trip time to the server and phase of its ticks.
+    clock: ServerClock,
+    /// The instant from which the times given to `clock` are measured.
+    epoch: Instant,
+    /// The tiles seen by the last `voir`, only kept until the next command completes.
+    ///
+    /// The player itself is removed from the first tile.
+    seen: Vec<Vec<Object>>,
+    /// When the player was recently kicked by another one, from the oldest to the most
+    /// recent.
+    kicked_at: Vec<Instant>,
+}
+
+impl Planner {
+    /// Creates a new planner for a level 1 player with an empty inventory.
+    pub fn new() -> Self {
+        Self {
+            level: 1,
+            inventory: ResourceSet::from_counts([INITIAL_FOOD, 0, 0, 0, 0, 0, 0]),
+            position: (0, 0),
+            // The actual orientation does not matter, only turns do.
+            facing: (0, -1),
+            map_size: None,
+            memory: HashMap::new(),
+            food_checked_at: Instant::now(),
+            tick_duration: None,
+            clock: ServerClock::new(),
+            epoch: Instant::now(),
+            seen: Vec::new(),
+            kicked_at: Vec::new(),
+        }
+    }
+
+    /// Returns the current level of the player.
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Returns the position of the player, relative to where it spawned or to where it
+    /// was when the planner was last reset.
+    pub fn position(&self) -> (i64, i64) {
+        self.position
+    }
+
+    /// Records the size of the map, sent by the server when the player joined.
+    ///
+    /// The map wraps around its edges: from then on, the tiles are remembered and the
+    /// distances are computed accordingly.
+    pub fn set_map_size(&mut self, (width, height): (usize, usize)) {
+        if width == 0 || height == 0 {
+            return;
+        }
+        let size = (width as i64, height as i64);
+        self.map_size = Some(size);
+        self.memory = std::mem::take(&mut self.memory)
+            .into_iter()
+            .map(|(position, observation)| (self.wrap(position), observation))
+            .collect();
+    }
+
+    /// Returns the position of a tile wrapped around the map, if its size is known.
+    fn wrap(&self, (x, y): (i64, i64)) -> (i64, i64) {
+        match self.map_size {
+            Some((width, height)) => (x.rem_euclid(width), y.rem_euclid(height)),
+            None => (x, y),
+        }
+    }
+
+    /// Returns the shortest offset from the player to `target`, going around the map if
+    /// it is shorter.
+    fn offset_to(&self, target: (i64, i64)) -> (i64, i64) {
+        let shortest = |delta: i64, size: Option<i64>| match size {
+            Some(size) => {
+                let delta = delta.rem_euclid(size);
+                if delta > size / 2 {
+                    delta - size
+                } else {
+                    delta
+                }
+            }
+            None => delta,
+        };
+        (
+            shortest(target.0 - self.position.0, self.map_size.map(|s| s.0)),
+            shortest(target.1 - self.position.1, self.map_size.map(|s| s.1)),
+        )
+    }
+
+    /// Forgets everything the player knows about its surroundings, when it cannot be
+    /// trusted anymore.
+    ///
+    /// The level of the player, the size of the map, the estimations of the timing of the
+    /// server and the kicks recently received are kept.
+    pub fn reset(&mut self) {
+        *self = Self {
+            level: self.level,
+            map_size: self.map_size,
+            kicked_at: std::mem::take(&mut self.kicked_at),
+            tick_duration: self.tick_duration,
+            clock: self.clock,
+            epoch: self.epoch,
+            ..Self::new()
+        };
+    }
+
+    /// Updates the knowledge of the planner with the response of the server to a
+    /// command.
+    pub fn update(&mut self, command: &Command, response: &Response) {
+        let (dx, dy) = self.facing;
+        match (command, response) {
+            (Command::Forward, Response::Ok) => {
+                self.position = (self.position.0 + dx, self.position.1 + dy);
+            }
+            (Command::Left | Command::Right, Response::Ok) => {
+                self.facing = turned(self.facing, command);
+            }
+            (Command::Take(object), Response::Ok) => {
+                if let Some(class) = object.class() {
+                    self.inventory.add(class);
+                }
+                self.forget_here(*object, 1);
+            }
+            (Command::Take(object), Response::Ko) => self.forget_here(*object, usize::MAX),
+            (Command::Drop(object), Response::Ok) => {
+                if let Some(class) = object.class() {
+                    self.inventory.remove(class);
+                }
+            }
+            _ => (),
+        }
+
+        self.seen.clear();
+        match response {
+            Response::Seen(tiles) => {
+                self.seen.clone_from(tiles);
+                if let Some(here) = self.seen.first_mut() {
+                    if let Some(i) = here.iter().position(|&o| o == Object::Player) {
+                        here.remove(i);
+                    }
+                }
+                self.remember_seen();
+            }
+            Response::Inventory(inventory) => {
+                self.inventory = ResourceSet::default();
+                self.food_checked_at = Instant::now();
+                for &(object, count) in inventory {
+                    if let Some(class) = object.class() {
+                        self.inventory[class] = count.into();
+                    }
+                }
+            }
+            Response::Elevated(level) => self.level = *level,
+            _ => (),
+        }
+    }
+
+    /// Records the kicks among the notifications received while waiting for the last
+    /// response.
+    ///
+    /// Kicks old enough not to matter anymore are forgotten.
+    pub fn listen(&mut self, notifications: &[Notif]) {
+        self.kicked_at
+            .retain(|at| decay(at.elapsed(), KICK_HALF_LIFE) >= MIN_CONFIDENCE);
+        for notif in notifications {
+            if let Notif::Displaced(_) = notif {
+                self.kicked_at.push(Instant::now());
+            }
+        }
+    }
+
+    /// Forgets up to `count` objects of the provided kind on the tile the player stands on,
+    /// once they were taken or turned out not to be there.
+    fn forget_here(&mut self, object: Object, count: usize) {
+        if let Some(observation) = self.memory.get_mut(&self.wrap(self.position)) {
+            let mut forgotten = 0;
+            observation.objects.retain(|&o| {
+                let forget = o == object && forgotten < count;
+                forgotten += usize::from(forget);
+                !forget
+            });
+        }
+    }
+
+    /// Records the tiles of the last `voir` in the memory of the player, and forgets the
+    /// observations that are too old to be trusted.
+    fn remember_seen(&mut self) {
+        let seen_at = Instant::now();
+        // The tiles are seen row by row, from the closest to the farthest, and from left
+        // to right.
+        let mut row = 0i64;
+        let mut row_start = 0;
+        for (i, objects) in self.seen.iter().enumerate() {
+            if i > row_start + 2 * row as usize {
+                row_start = i;
+                row += 1;
+            }
+            let column = (i - row_start) as i64 - row;
+            let position = self.wrap(tile_in_view(self.position, self.facing, row, column));
+            self.memory.insert(
+                position,
+                Observation {
+                    objects: objects.clone(),
+                    seen_at,
+                },
+            );
+        }
+
+        self.memory
+            .retain(|_, observation| observation.confidence() >= MIN_CONFIDENCE);
+    }
+
+    /// Returns how many of each resource the player expects to still be on the tiles it
+    /// remembers, indexed by [`ObjectClass::index`], old observations weighing less.
+    fn observed(&self) -> [f64; 7] {
+        let mut observed = [0.0; 7];
+        for observation in self.memory.values() {
+            let confidence = observation.confidence();
+            for class in observation
+                .objects
+                .iter()
+                .filter_map(|object| object.class())
+            {
+                observed[class.index()] += confidence;
+            }
+        }
+        observed
+    }
+
+    /// Updates the estimations of the timing of the server with the response to a
+    /// command sent at `sent_at`, which just arrived.
+    ///
+    /// The command must have been sent while no other command was waiting for its
+    /// response, or it waited for them on the server as well.
+    ///
+    /// `connect_nbr` is answered at the next tick, which tells the round-trip time to the
+    /// server. The time the other commands took, minus that round trip, tells the
+    /// duration of a tick: commands lasting many ticks give the best estimations.
+    /// Commands whose duration is not fixed are ignored.
+    pub fn record_latency(&mut self, command: &Command, sent_at: Instant) {
+        if let Command::ConnectNbr = command {
+            self.clock.record_immediate_response(
+                sent_at.saturating_duration_since(self.epoch),
+                self.epoch.elapsed(),
+            );
+            return;
+        }
+        let Some(ticks) = command.ticks() else {
+            return;
+        };
+        let round_trip = self.clock.round_trip().unwrap_or_default();
+        let measured = sent_at.elapsed().saturating_sub(round_trip) / ticks;
+        self.tick_duration = Some(match self.tick_duration {
+            Some(estimate) => {
+                estimate.mul_f64(1.0 - TICK_ESTIMATE_WEIGHT)
+                    + measured.mul_f64(TICK_ESTIMATE_WEIGHT)
+            }
+            None => measured,
+        });
+        if let Some(tick_duration) = self.tick_duration {
+            self.clock.set_tick_period(tick_duration);
+        }
+    }
+
+    /// Returns the estimated duration of a tick of the server.
+    ///
+    /// Returns `None` until a command with a fixed duration has completed.
+    pub fn tick_duration(&self) -> Option<Duration> {
+        self.tick_duration
+    }
+
+    /// Returns the estimated round-trip time to the server.
+    ///
+    /// Returns `None` until a `connect_nbr` has been answered.
+    pub fn round_trip(&self) -> Option<Duration> {
+        self.clock.round_trip()
+    }
+
+    /// Returns the estimated amount of time the player has left to live.
+    ///
+    /// Returns `None` until the duration of a tick has been estimated.
+    pub fn remaining_life(&self) -> Option<Duration> {
+        let life = self.tick_duration? * (self.inventory[ObjectClass::Food] * TICKS_PER_FOOD);
+        Some(life.saturating_sub(self.food_checked_at.elapsed()))
+    }
+
+    /// Returns whether the player should stop gathering stones and look for food.
+    pub fn must_eat(&self) -> bool {
+        self.remaining_life()
+            .is_some_and(|life| life < FOOD_SAFETY_MARGIN)
+    }
+
+    /// Returns whether the tile in front of the player holds so many players that walking
+    /// into it is risky.
+    ///
+    /// The tiles seen by `voir` do not tell teams apart, so every player is considered an
+    /// enemy.
+    pub fn front_is_crowded(&self) -> bool {
+        // The tile in front of the player is the middle one of the first row.
+        self.seen.get(2).is_some_and(|tile| {
+            tile.iter()
+                .filter(|&&object| object == Object::Player)
+                .count()
+                > CROWD_THRESHOLD
+        })
+    }
+
+    /// Returns a stone that other players gathered on the current tile, presumably for an
+    /// incantation, and that the player could pick up.
+    ///
+    /// Missing stones are preferred, but any stone will do: taking it away delays the
+    /// elevation of the other players.
+    pub fn stone_to_steal(&self) -> Option<Object> {
+        let tile = self.seen.first()?;
+        if !tile.contains(&Object::Player) {
+            return None;
+        }
+        self.scarcest_missing(&[])
+            .filter(|stone| tile.contains(stone))
+            .or_else(|| {
+                ObjectClass::STONES
+                    .into_iter()
+                    .map(Object::from)
+                    .find(|stone| tile.contains(stone))
+            })
+    }
+
+    /// Returns the stones that are still missing to perform the next elevation, taking
+    /// into account the inventory and the content of the current tile.
+    pub fn missing(&self, tile: &[Object]) -> ResourceSet {
+        let Some(requirement) = elevation_requirement(self.level.into()) else {
+            return ResourceSet::default();
+        };
+
+        let mut available = self.inventory;
+        available.add_all(&resources_of(tile));
+        requirement.stones.saturating_sub(&available)
+    }
+
+    /// Returns whether the next elevation can be performed on the provided tile.
+    ///
+    /// The player itself is expected to be part of the tile's content.
+    pub fn ready_to_elevate(&self, tile: &[Object]) -> bool {
+        let Some(requirement) = elevation_requirement(self.level.into()) else {
+            return false;
+        };
+
+        players_in(tile) >= usize::from(requirement.players)
+            && resources_of(tile).contains(&requirement.stones)
+    }
+
+    /// Returns the estimated risk of an elevation started now being interrupted, between
+    /// 0 and 1.
+    ///
+    /// The risk grows with the other players seen within [`THREAT_RADIUS`] moves, the
+    /// tile of the player left out, and with the kicks the player recently received.
+    /// Teams cannot be told apart, so every player seen is considered an enemy. Both
+    /// fade away as the observations and the kicks get older.
+    pub fn interruption_risk(&self) -> f64 {
+        let kicks: f64 = self
+            .kicked_at
+            .iter()
+            .map(|at| decay(at.elapsed(), KICK_HALF_LIFE))
+            .sum();
+        let threat = self.players_around(|offset| {
+            let distance = offset.0.abs() + offset.1.abs();
+            (distance != 0 && distance <= THREAT_RADIUS).then_some(1.0)
+        }) * NEARBY_PLAYER_THREAT
+            + kicks * KICK_THREAT;
+        1.0 - (-threat).exp()
+    }
+
+    /// Returns the number of players remembered on the tiles around the player, each
+    /// weighted by the confidence in the observation and by `weight`, which is given the
+    /// offset of the tile and returns `None` for the tiles to leave out.
+    fn players_around(&self, weight: impl Fn((i64, i64)) -> Option<f64>) -> f64 {
+        self.memory
+            .iter()
+            .filter_map(|(&position, observation)| {
+                let weight = weight(self.offset_to(position))?;
+                Some(players_in(&observation.objects) as f64 * observation.confidence() * weight)
+            })
+            .sum()
+    }
+
+    /// Returns the move leading away from the other players: going forward, or turning
+    /// towards the side where the fewest players were seen.
+    ///
+    /// Every player counts less the farther it is.
+    fn quietest_move(&self) -> Command<'static> {
+        let crowd = |(dx, dy): (i64, i64)| {
+            self.players_around(|(x, y)| {
+                let ahead = x * dx + y * dy;
+                (ahead > 0).then(|| 1.0 / (x.abs() + y.abs()) as f64)
+            })
+        };
+        [Command::Forward, Command::Left, Command::Right]
+            .into_iter()
+            .map(|command| (crowd(turned(self.facing, &command)), command))
+            .min_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map_or(Command::Forward, |(_, command)| command)
+    }
+
+    /// Decides whether the elevation can start on `tile`, which the player is ready to
+    /// perform it on, after putting it off `delays` times in a row.
+    ///
+    /// When the risk of it being interrupted is too high, the player moves somewhere
+    /// quieter if it holds the stones and needs nobody else, calls a guard if it needs
+    /// players of its team, or waits. A guard standing on the tile, or having put off
+    /// the elevation [`MAX_ELEVATION_DELAYS`] times, is enough to start anyway.
+    pub fn elevation_timing(&self, tile: &[Object], delays: u32) -> ElevationTiming {
+        let Some(requirement) = elevation_requirement(self.level.into()) else {
+            return ElevationTiming::Now;
+        };
+        let needed = usize::from(requirement.players);
+        let guarded = needed > 1 && players_in(tile) > needed;
+
+        if guarded || delays >= MAX_ELEVATION_DELAYS || self.interruption_risk() < RISK_TOLERANCE {
+            ElevationTiming::Now
+        } else if needed == 1 && self.missing(&[]).is_empty() {
+            ElevationTiming::Relocate(self.quietest_move())
+        } else if needed > 1 {
+            ElevationTiming::Guard
+        } else {
+            ElevationTiming::Delay
+        }
+    }
+
+    /// Returns a stone that the tile lacks for the next elevation and that the player can
+    /// drop from its inventory.
+    pub fn stone_to_drop(&self, tile: &[Object]) -> Option<Object> {
+        let requirement = elevation_requirement(self.level.into())?;
+        let on_tile = resources_of(tile);
+        ObjectClass::STONES
+            .into_iter()
+            .find(|&stone| on_tile[stone] < requirement.stones[stone] && self.inventory[stone] > 0)
+            .map(Object::from)
+    }
+
+    /// Returns the object the player is looking for: food when it is about to starve, or
+    /// the scarcest missing stone.
+    fn wanted(&self) -> Option<Object> {
+        if self.must_eat() {
+            Some(Object::Food)
+        } else {
+            self.scarcest_missing(&[])
+        }
+    }
+
+    /// Returns the commands fetching the object the player is looking for: the moves
+    /// leading to the closest remembered tile holding it, then taking it.
+    ///
+    /// Returns `None` if the player is not looking for anything, or does not remember
+    /// where to find it. Stones too far away to be fetched before the player has to look
+    /// for food are not worth the trip either.
+    pub fn fetch_wanted(&self) -> Option<Vec<Command<'static>>> {
+        let wanted = self.wanted()?;
+        let (&target, _) = self
+            .memory
+            .iter()
+            .filter(|(_, observation)| observation.objects.contains(&wanted))
+            .min_by_key(|&(&target, _)| {
+                let (dx, dy) = self.offset_to(target);
+                dx.abs() + dy.abs()
+            })?;
+        let mut commands = self.path_to(target);
+        commands.push(Command::Take(wanted));
+
+        if wanted != Object::Food && !self.has_time_for(&commands) {
+            return None;
+        }
+        Some(commands)
+    }
+
+    /// Returns the commands leading to the place from which looking around is expected to
+    /// teach the player the most, followed by `voir`.
+    ///
+    /// Every place within [`EXPLORATION_RADIUS`] moves is scored by the information the
+    /// player would gain there, the tiles it never saw or saw a while ago, divided by the
+    /// number of ticks it takes to get there and look around.
+    ///
+    /// Returns `None` if no place is worth the trip, or if the player must keep its time
+    /// to gather what it knows is missing. A starving player explores anyway, in search
+    /// of food.
+    pub fn explore(&self) -> Option<Vec<Command<'static>>> {
+        let mut best = None;
+        let mut best_score = 0.0;
+        for dx in -EXPLORATION_RADIUS..=EXPLORATION_RADIUS {
+            let reach = EXPLORATION_RADIUS - dx.abs();
+            for dy in -reach..=reach {
+                let target = (self.position.0 + dx, self.position.1 + dy);
+                let mut commands = self.path_to(target);
+                let facing = commands.iter().fold(self.facing, turned);
+                commands.push(Command::Look);
+
+                let gain = self.information_gain(target, facing);
+                let ticks: u32 = commands.iter().filter_map(Command::ticks).sum();
+                let score = gain / f64::from(ticks);
+                if gain >= MIN_INFORMATION_GAIN && score > best_score {
+                    best = Some(commands);
+                    best_score = score;
+                }
+            }
+        }
+
+        let commands = best?;
+        if !self.must_eat() && !self.has_time_for(&commands) {
+            return None;
+        }
+        Some(commands)
+    }
+
+    /// Returns the information the player would gain by looking around from `from`,
+    /// facing `facing`: the number of tiles in view weighted by how little it trusts what
+    /// it remembers of them.
+    fn information_gain(&self, from: (i64, i64), facing: (i64, i64)) -> f64 {
+        let level = i64::from(self.level);
+        let mut gain = 0.0;
+        for row in 0..=level {
+            for column in -row..=row {
+                let tile = self.wrap(tile_in_view(from, facing, row, column));
+                let confidence = self.memory.get(&tile).map_or(0.0, Observation::confidence);
+                gain += 1.0 - confidence;
+            }
+        }
+        gain
+    }
+
+    /// Returns whether the player can afford to execute the provided commands before it
+    /// has to look for food.
+    ///
+    /// Anything goes until the duration of a tick has been estimated.
+    fn has_time_for(&self, commands: &[Command]) -> bool {
+        let (Some(life), Some(tick)) = (self.remaining_life(), self.tick_duration) else {
+            return true;
+        };
+        let ticks: u32 = commands.iter().filter_map(Command::ticks).sum();
+        tick * ticks + FOOD_SAFETY_MARGIN <= life
+    }
+
+    /// Returns the moves leading to `target`, going straight ahead first when possible,
+    /// and turning at most twice.
+    ///
+    /// Once the size of the map is known, the player goes around it when it is shorter.
+    fn path_to(&self, target: (i64, i64)) -> Vec<Command<'static>> {
+        let (dx, dy) = self.offset_to(target);
+        let (fx, fy) = self.facing;
+        // The distance to walk ahead, and to the right, of the player.
+        let ahead = dx * fx + dy * fy;
+        let right = -dx * fy + dy * fx;
+
+        let forward = |count: i64| (0..count.abs()).map(|_| Command::Forward);
+        let mut path = Vec::new();
+        if ahead > 0 {
+            path.extend(forward(ahead));
+        }
+        let turn = if right >= 0 {
+            Command::Right
+        } else {
+            Command::Left
+        };
+        if right != 0 {
+            path.push(turn);
+            path.extend(forward(right));
+        }
+        if ahead < 0 {
+            // Turning the same way twice faces backwards.
+            path.push(turn);
+            if right == 0 {
+                path.push(turn);
+            }
+            path.extend(forward(ahead));
+        }
+        path
+    }
+
+    /// Returns the missing stone that the player expects to be the scarcest on the tiles
+    /// it remembers.
+    ///
+    /// Returns `None` if no stone is missing for the next elevation.
+    pub fn scarcest_missing(&self, tile: &[Object]) -> Option<Object> {
+        let observed = self.observed();
+        self.missing(tile)
+            .iter()
+            .filter(|&(_, count)| count > 0)
+            .min_by(|&(a, _), &(b, _)| observed[a.index()].total_cmp(&observed[b.index()]))
+            .map(|(class, _)| Object::from(class))
+    }
+}
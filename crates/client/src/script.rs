@@ -0,0 +1,133 @@
+/// Module for scripts: sequences of commands read from a file.
+///
+/// A script contains one instruction per line:
+///
+/// - A command, written as it is sent to the server (`avance`, `prend linemate`, ...).
+/// - `wait <ms>`, which pauses the script for the given number of milliseconds.
+/// - `repeat <n>`, which repeats the following instructions `n` times, up to the
+///   matching `end`.
+///
+/// Empty lines and lines starting with `#` are ignored.
+use std::fmt::Display;
+use std::time::Duration;
+
+use crate::server::commands::Response;
+use crate::server::{Command, Result, Server};
+
+/// An instruction of a script.
+pub enum Step<'a> {
+    /// Send a command to the server and wait for its response.
+    Send(Command<'a>),
+    /// Pause the script.
+    Wait(Duration),
+    /// Run the inner steps a number of times.
+    Repeat(u32, Vec<Step<'a>>),
+}
+
+/// An error found while parsing a script.
+#[derive(Debug)]
+pub struct ScriptError {
+    /// The line at which the error was found, starting at 1.
+    pub line: usize,
+    /// A description of the error.
+    pub message: &'static str,
+}
+
+impl std::error::Error for ScriptError {}
+
+impl Display for ScriptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parses the content of a script.
+pub fn parse(source: &str) -> std::result::Result<Vec<Step<'_>>, ScriptError> {
+    // The steps of the enclosing blocks, along with their repetition count and the line
+    // at which they started.
+    let mut blocks: Vec<(Vec<Step>, u32, usize)> = Vec::new();
+    let mut steps = Vec::new();
+
+    for (index, line) in source.lines().enumerate() {
+        let line_number = index + 1;
+        let error = |message| ScriptError {
+            line: line_number,
+            message,
+        };
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (keyword, arg) = line.split_once(' ').unwrap_or((line, ""));
+        match keyword {
+            "wait" => {
+                let ms = arg.trim().parse().map_err(|_| error("invalid duration"))?;
+                steps.push(Step::Wait(Duration::from_millis(ms)));
+            }
+            "repeat" => {
+                let count = arg.trim().parse().map_err(|_| error("invalid count"))?;
+                blocks.push((std::mem::take(&mut steps), count, line_number));
+            }
+            "end" => {
+                let (outer, count, _) = blocks.pop().ok_or(error("`end` without `repeat`"))?;
+                let inner = std::mem::replace(&mut steps, outer);
+                steps.push(Step::Repeat(count, inner));
+            }
+            _ => {
+                let command = Command::parse(line)
+                    .map_err(|_| error("unknown command or invalid argument"))?;
+                steps.push(Step::Send(command));
+            }
+        }
+    }
+
+    if let Some(&(_, _, line)) = blocks.last() {
+        return Err(ScriptError {
+            line,
+            message: "`repeat` without `end`",
+        });
+    }
+
+    Ok(steps)
+}
+
+/// Statistics about the execution of a script.
+#[derive(Default)]
+pub struct ScriptStats {
+    /// The number of commands sent to the server.
+    pub sent: u64,
+    /// The number of commands that failed.
+    pub failed: u64,
+}
+
+/// Runs the steps of a script against the server.
+///
+/// # Returns
+///
+/// `false` if the player died while running the script.
+pub fn run(server: &mut Server, steps: &[Step], stats: &mut ScriptStats) -> Result<bool> {
+    for step in steps {
+        match step {
+            Step::Send(command) => {
+                server.send_command(*command)?;
+                stats.sent += 1;
+                match server.receive_response()? {
+                    Some(Response::Ko) => stats.failed += 1,
+                    Some(_) => (),
+                    None => return Ok(false),
+                }
+            }
+            Step::Wait(duration) => std::thread::sleep(*duration),
+            Step::Repeat(count, steps) => {
+                for _ in 0..*count {
+                    if !run(server, steps, stats)? {
+                        return Ok(false);
+                    }
+                }
+            }
+        }
+    }
+    Ok(true)
+}
@@ -5,14 +5,14 @@ mod errors;
 
 pub use commands::Command;
 use errors::InvalidMsg::MissingValue;
-pub use errors::Result;
+pub use errors::{Result, ServerError};
 
 use crate::args::Args;
-use clap::Parser;
 use io::{Read, Write};
+use std::time::Duration;
 use std::{io, net::TcpStream};
 
-use self::commands::Msg;
+use self::commands::{Msg, Notif, Response};
 
 /// Abstraction over the server.
 #[allow(dead_code)]
@@ -26,52 +26,111 @@ pub struct Server {
     height: usize,
     /// Read buffer.
     buf: String,
+    /// Whether the exchanges with the server are shown on the standard output.
+    verbose: bool,
+    /// The notifications received while waiting for the last response.
+    notifications: Vec<Notif>,
 }
 
 impl Server {
     /// Creates a new server instance and connects to it.
-    pub fn new() -> Result<Self> {
-        let args = Args::parse();
+    pub fn new(args: &Args) -> Result<Self> {
         let stream = TcpStream::connect((args.host.as_str(), args.port))?;
+        if args.timeout != 0 {
+            stream.set_read_timeout(Some(Duration::from_secs(args.timeout)))?;
+        }
         let mut self_ = Self {
             stream,
             width: 0,
             height: 0,
             buf: String::new(),
+            // Many players would flood the output.
+            verbose: args.swarm.is_none() && args.count.is_none(),
+            notifications: Vec::new(),
         };
 
         let _received = self_.get_line()?;
 
         self_.stream.write_fmt(format_args!("{}\n", args.name))?;
 
-        let slots: usize = self_.get_line()?.parse()?;
+        let line = self_.get_line()?;
+        if line == "ko" {
+            return Err(ServerError::Rejected);
+        }
+        let slots: usize = line.parse()?;
         let line = self_.get_line()?;
         let mut dimensions = line.split_whitespace();
         self_.width = dimensions.next().ok_or(MissingValue)?.parse()?;
         self_.height = dimensions.next().ok_or(MissingValue)?.parse()?;
-        println!(
-            "slots: {}, width: {}, height: {}",
-            slots, self_.width, self_.height
-        );
+        if self_.verbose {
+            println!(
+                "slots: {}, width: {}, height: {}",
+                slots, self_.width, self_.height
+            );
+        }
 
         Ok(self_)
     }
 
     /// Sends a command to the server.
     pub fn send_command(&mut self, command: Command) -> Result<()> {
-        print!("> {}...", command);
-        std::io::stdout().flush()?;
+        if self.verbose {
+            print!("> {}...", command);
+            std::io::stdout().flush()?;
+        }
         self.stream.write_fmt(format_args!("{}\n", command))?;
         Ok(())
     }
 
     /// Reads a message from the server.
+    ///
+    /// The server closing the connection is reported as [`Notif::Shutdown`].
     pub fn receive(&mut self) -> Result<Msg> {
-        let received = self.get_line()?.parse()?;
-        println!("in: {}", received);
+        let received = match self.get_line() {
+            Err(ServerError::Disconnected) => Msg::Notif(Notif::Shutdown),
+            line => line?.parse()?,
+        };
+        if self.verbose {
+            println!("in: {}", received);
+        }
         Ok(received)
     }
 
+    /// Waits for the response to the last command.
+    ///
+    /// Notifications received in the meantime are kept until the next call, see
+    /// [`Server::notifications`].
+    ///
+    /// # Returns
+    ///
+    /// `None` if the player died before the response arrived.
+    pub fn receive_response(&mut self) -> Result<Option<Response>> {
+        self.notifications.clear();
+        loop {
+            match self.receive()? {
+                Msg::Response(response) => return Ok(Some(response)),
+                Msg::Notif(Notif::Dead) => return Ok(None),
+                Msg::Notif(Notif::Shutdown) => return Err(ServerError::Disconnected),
+                Msg::Notif(notif) => self.notifications.push(notif),
+            }
+        }
+    }
+
+    /// Returns the width and height of the map, as sent by the server when the player
+    /// joined.
+    pub fn map_size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    /// Returns the notifications received while waiting for the last response, in the
+    /// order they arrived.
+    ///
+    /// [`Notif::Dead`] and [`Notif::Shutdown`] are never part of them: they end the game
+    /// and are reported by [`Server::receive_response`] instead.
+    pub fn notifications(&self) -> &[Notif] {
+        &self.notifications
+    }
+
     /// Returns a line read from the server.
     fn get_line(&mut self) -> Result<String> {
         let mut buf = [0; 1024];
@@ -80,7 +139,12 @@ impl Server {
             if let Some(newline) = self.buf.find('\n') {
                 break newline;
             }
-            let len = self.stream.read(&mut buf)?;
+            let len = match self.stream.read(&mut buf) {
+                Ok(0) => return Err(ServerError::Disconnected),
+                Ok(len) => len,
+                Err(err) if is_timeout(&err) => return Err(ServerError::Timeout),
+                Err(err) => return Err(err.into()),
+            };
             self.buf.push_str(&String::from_utf8_lossy(&buf[..len]));
         };
         let line = self.buf.drain(..newline).collect();
@@ -88,3 +152,147 @@ impl Server {
         Ok(line)
     }
 }
+
+/// Returns whether an IO error was caused by a read timeout.
+///
+/// Depending on the platform, timeouts are reported as either kind.
+fn is_timeout(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use std::net::{Shutdown, TcpListener};
+
+    use super::commands::Object;
+    use super::*;
+
+    /// Returns a [`Server`] connected to a socket to which `lines` were written, the
+    /// connection being closed afterwards.
+    fn server_sending(lines: &str) -> Server {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut peer, _) = listener.accept().unwrap();
+        peer.write_all(lines.as_bytes()).unwrap();
+        peer.shutdown(Shutdown::Write).unwrap();
+        Server {
+            stream,
+            width: 0,
+            height: 0,
+            buf: String::new(),
+            verbose: false,
+            notifications: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn receive_parses_notifications() {
+        let mut server = server_sending(
+            "message 3,on y va\ndeplacement 5\nelevation en cours\nmort\nmessage 0,\n",
+        );
+        let expected = [
+            Notif::Message(3, "on y va".to_string()),
+            Notif::Displaced(5),
+            Notif::Elevating,
+            Notif::Dead,
+            Notif::Message(0, String::new()),
+            Notif::Shutdown,
+        ];
+        for notif in expected {
+            match server.receive().unwrap() {
+                Msg::Notif(received) => assert_eq!(received, notif),
+                Msg::Response(response) => panic!("expected {notif}, got {response}"),
+            }
+        }
+    }
+
+    #[test]
+    fn receive_parses_responses() {
+        let mut server = server_sending(
+            "ok\nko\n3\nniveau actuel : 4\n{nourriture 9, linemate 1}\n{joueur, , sibur sibur}\n",
+        );
+        assert!(matches!(
+            server.receive().unwrap(),
+            Msg::Response(Response::Ok)
+        ));
+        assert!(matches!(
+            server.receive().unwrap(),
+            Msg::Response(Response::Ko)
+        ));
+        assert!(matches!(
+            server.receive().unwrap(),
+            Msg::Response(Response::FreeSlots(3))
+        ));
+        assert!(matches!(
+            server.receive().unwrap(),
+            Msg::Response(Response::Elevated(4))
+        ));
+        match server.receive().unwrap() {
+            Msg::Response(Response::Inventory(inventory)) => {
+                assert_eq!(inventory, [(Object::Food, 9), (Object::Linemate, 1)])
+            }
+            msg => panic!("expected an inventory, got {msg}"),
+        }
+        match server.receive().unwrap() {
+            Msg::Response(Response::Seen(seen)) => assert_eq!(
+                seen,
+                [
+                    vec![Object::Player],
+                    vec![],
+                    vec![Object::Sibur, Object::Sibur]
+                ]
+            ),
+            msg => panic!("expected the content of the tiles seen, got {msg}"),
+        }
+    }
+
+    #[test]
+    fn receive_rejects_malformed_messages() {
+        let mut server =
+            server_sending("deplacement\nmessage 2 sans virgule\nmessage x,texte\nbonjour\n");
+        for _ in 0..4 {
+            assert!(matches!(
+                server.receive(),
+                Err(ServerError::InvalidResponse(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn receive_response_keeps_notifications_in_order() {
+        let mut server = server_sending("message 1,a\ndeplacement 7\nmessage 2,b\nok\nko\n");
+        assert!(matches!(
+            server.receive_response().unwrap(),
+            Some(Response::Ok)
+        ));
+        assert_eq!(
+            server.notifications(),
+            [
+                Notif::Message(1, "a".to_string()),
+                Notif::Displaced(7),
+                Notif::Message(2, "b".to_string()),
+            ]
+        );
+        // The notifications of the previous response are forgotten.
+        assert!(matches!(
+            server.receive_response().unwrap(),
+            Some(Response::Ko)
+        ));
+        assert!(server.notifications().is_empty());
+    }
+
+    #[test]
+    fn receive_response_reports_death_and_shutdown() {
+        let mut server = server_sending("message 4,a\nmort\n");
+        assert!(server.receive_response().unwrap().is_none());
+        assert_eq!(server.notifications(), [Notif::Message(4, "a".to_string())]);
+        assert!(matches!(
+            server.receive_response(),
+            Err(ServerError::Disconnected)
+        ));
+    }
+}
@@ -2,10 +2,13 @@
 use std::fmt::Display;
 use std::str::FromStr;
 
+use resources::ObjectClass;
+
 use super::errors::InvalidMsg;
 
 #[allow(dead_code)]
 /// Enum representing a command that can be sent to the server.
+#[derive(Debug, Clone, Copy)]
 pub enum Command<'a> {
     /// Move forward.
     Forward,
@@ -52,6 +55,42 @@ impl Display for Command<'_> {
     }
 }
 
+impl<'a> Command<'a> {
+    /// Parses a command written as it is sent to the server, such as `prend linemate`.
+    pub fn parse(s: &'a str) -> Result<Self, InvalidMsg> {
+        let (name, arg) = s.split_once(' ').unwrap_or((s, ""));
+        match (name, arg) {
+            ("avance", "") => Ok(Command::Forward),
+            ("droite", "") => Ok(Command::Right),
+            ("gauche", "") => Ok(Command::Left),
+            ("voir", "") => Ok(Command::Look),
+            ("inventaire", "") => Ok(Command::Inventory),
+            ("prend", obj) => Ok(Command::Take(obj.parse()?)),
+            ("pose", obj) => Ok(Command::Drop(obj.parse()?)),
+            ("expulse", "") => Ok(Command::Kick),
+            ("broadcast", msg) => Ok(Command::Broadcast(msg)),
+            ("incantation", "") => Ok(Command::Incantation),
+            ("fork", "") => Ok(Command::Fork),
+            ("connect_nbr", "") => Ok(Command::ConnectNbr),
+            _ => Err(InvalidMsg::ParsingError),
+        }
+    }
+
+    /// Returns the number of ticks the server takes to execute the command.
+    ///
+    /// Returns `None` when the duration is not fixed: an incantation may fail right away,
+    /// and `connect_nbr` lasts no tick but is answered at the next one, whenever it was
+    /// sent.
+    pub fn ticks(&self) -> Option<u32> {
+        match self {
+            Command::Inventory => Some(1),
+            Command::Fork => Some(42),
+            Command::Incantation | Command::ConnectNbr => None,
+            _ => Some(7),
+        }
+    }
+}
+
 /// Enum representing a message from the server.
 #[derive(Debug, Clone)]
 pub enum Msg {
@@ -80,13 +119,29 @@ impl FromStr for Msg {
     }
 }
 
-/// Enum representing a notification from the server.
-#[derive(Debug, Clone)]
+/// Enum representing a notification from the server, which may arrive at any time
+/// rather than in answer to a command.
+///
+/// Directions are numbered like the tiles around the player, counterclockwise: `1` is
+/// the front of the player, `3` its left, `5` its back and `7` its right. `0` means the
+/// player's own tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Notif {
     /// Acknowledgement of an incantation.
     Elevating,
     /// The player has died.
     Dead,
+    /// The player was pushed by another player, coming from the given direction.
+    Displaced(u8),
+    /// A broadcast was heard, coming from the given direction.
+    Message(u8, String),
+    /// The server closed the connection.
+    ///
+    /// The server does not send it: it is reported by [`Server::receive`] when the
+    /// connection is closed.
+    ///
+    /// [`Server::receive`]: crate::server::Server::receive
+    Shutdown,
 }
 
 impl Display for Notif {
@@ -94,6 +149,9 @@ impl Display for Notif {
         match self {
             Notif::Elevating => write!(f, "elevation en cours"),
             Notif::Dead => write!(f, "mort"),
+            Notif::Displaced(direction) => write!(f, "deplacement {}", direction),
+            Notif::Message(direction, text) => write!(f, "message {},{}", direction, text),
+            Notif::Shutdown => write!(f, "shutdown"),
         }
     }
 }
@@ -105,6 +163,15 @@ impl FromStr for Notif {
         match s {
             "elevation en cours" => Ok(Notif::Elevating),
             "mort" => Ok(Notif::Dead),
+            s if s.starts_with("deplacement ") => {
+                Ok(Notif::Displaced(s["deplacement ".len()..].parse()?))
+            }
+            s if s.starts_with("message ") => {
+                let (direction, text) = s["message ".len()..]
+                    .split_once(',')
+                    .ok_or(InvalidMsg::MissingValue)?;
+                Ok(Notif::Message(direction.parse()?, text.to_string()))
+            }
             _ => Err(InvalidMsg::ParsingError),
         }
     }
@@ -225,7 +292,8 @@ where
     let mut seen = Vec::new();
     for row in list {
         let mut row_vec = Vec::new();
-        for obj in row.split(' ') {
+        // Empty tiles are written as nothing at all.
+        for obj in row.split(' ').filter(|obj| !obj.is_empty()) {
             row_vec.push(obj.parse()?);
         }
         seen.push(row_vec);
@@ -234,7 +302,7 @@ where
 }
 
 /// Enum representing all the objects that can be found in the game.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Object {
     /// Food.
     Food,
@@ -254,6 +322,36 @@ pub enum Object {
     Player,
 }
 
+impl Object {
+    /// Returns the class of the object, or `None` for a player.
+    pub fn class(self) -> Option<ObjectClass> {
+        match self {
+            Object::Food => Some(ObjectClass::Food),
+            Object::Linemate => Some(ObjectClass::Linemate),
+            Object::Deraumere => Some(ObjectClass::Deraumere),
+            Object::Sibur => Some(ObjectClass::Sibur),
+            Object::Mendiane => Some(ObjectClass::Mendiane),
+            Object::Phiras => Some(ObjectClass::Phiras),
+            Object::Thystame => Some(ObjectClass::Thystame),
+            Object::Player => None,
+        }
+    }
+}
+
+impl From<ObjectClass> for Object {
+    fn from(class: ObjectClass) -> Self {
+        match class {
+            ObjectClass::Food => Object::Food,
+            ObjectClass::Linemate => Object::Linemate,
+            ObjectClass::Deraumere => Object::Deraumere,
+            ObjectClass::Sibur => Object::Sibur,
+            ObjectClass::Mendiane => Object::Mendiane,
+            ObjectClass::Phiras => Object::Phiras,
+            ObjectClass::Thystame => Object::Thystame,
+        }
+    }
+}
+
 impl Display for Object {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
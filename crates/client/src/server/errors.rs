@@ -14,6 +14,12 @@ pub enum ServerError {
     Io(io::Error),
     /// An invalid response from the server.
     InvalidResponse(InvalidMsg),
+    /// The server did not send anything for too long.
+    Timeout,
+    /// The server closed the connection.
+    Disconnected,
+    /// The server refused to let the player join the team, usually because it is full.
+    Rejected,
 }
 
 impl Error for ServerError {}
@@ -23,6 +29,9 @@ impl Display for ServerError {
         match self {
             ServerError::Io(err) => write!(f, "IO error: {}", err),
             ServerError::InvalidResponse(err) => write!(f, "Invalid response: {}", err),
+            ServerError::Timeout => write!(f, "Timed out waiting for the server"),
+            ServerError::Disconnected => write!(f, "The server closed the connection"),
+            ServerError::Rejected => write!(f, "The server refused to let the player join"),
         }
     }
 }
@@ -0,0 +1,128 @@
+/// Module for the load-testing mode, where many players play at once.
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::args::Args;
+use crate::planner::Planner;
+use crate::server::commands::Response;
+use crate::server::Server;
+
+/// The period at which the statistics of the swarm are reported.
+const REPORT_PERIOD: Duration = Duration::from_secs(1);
+
+/// The time to wait before trying again when a connection fails, for instance because
+/// the team is full.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// The statistics of the swarm since the last report.
+#[derive(Debug, Default)]
+struct Stats {
+    /// The number of successful connections.
+    connects: u32,
+    /// The number of connections that could not be established.
+    failed_connects: u32,
+    /// The number of players that died.
+    deaths: u32,
+    /// The number of connections lost because of an error.
+    errors: u32,
+    /// The time the server took to answer each command.
+    latencies: Vec<Duration>,
+}
+
+/// Locks the statistics, even if a player panicked while holding them.
+fn lock(stats: &Mutex<Stats>) -> MutexGuard<'_, Stats> {
+    stats.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Runs `count` players at once, each sending random commands, and reports aggregate
+/// statistics on the standard output every second.
+///
+/// Players that die or lose their connection are replaced, so that the load stays the
+/// same. This never returns: the swarm runs until the process is interrupted.
+pub fn run(args: &Args, count: usize) -> ! {
+    let stats = Mutex::new(Stats::default());
+    std::thread::scope(|scope| {
+        for _ in 0..count {
+            scope.spawn(|| play(args, &stats));
+        }
+        report(&stats)
+    })
+}
+
+/// Connects a player to the server and makes it play randomly, again and again.
+fn play(args: &Args, stats: &Mutex<Stats>) -> ! {
+    loop {
+        let mut server = match Server::new(args) {
+            Ok(server) => server,
+            Err(_) => {
+                lock(stats).failed_connects += 1;
+                std::thread::sleep(RETRY_DELAY);
+                continue;
+            }
+        };
+        lock(stats).connects += 1;
+
+        let mut planner = Planner::new();
+        let mut tile = Vec::new();
+        loop {
+            let command = crate::random_command(&planner, &tile);
+            let sent_at = Instant::now();
+            let response = match server
+                .send_command(command)
+                .and_then(|()| server.receive_response())
+            {
+                Ok(Some(response)) => response,
+                Ok(None) => {
+                    lock(stats).deaths += 1;
+                    break;
+                }
+                Err(_) => {
+                    lock(stats).errors += 1;
+                    break;
+                }
+            };
+            let latency = sent_at.elapsed();
+            lock(stats).latencies.push(latency);
+
+            tile = match &response {
+                Response::Seen(seen) => seen.first().cloned().unwrap_or_default(),
+                _ => Vec::new(),
+            };
+            planner.record_latency(&command, sent_at);
+            planner.update(&command, &response);
+        }
+    }
+}
+
+/// Reports the statistics of the swarm every [`REPORT_PERIOD`].
+fn report(stats: &Mutex<Stats>) -> ! {
+    loop {
+        let period_start = Instant::now();
+        std::thread::sleep(REPORT_PERIOD);
+        let mut period = std::mem::take(&mut *lock(stats));
+        let seconds = period_start.elapsed().as_secs_f32();
+
+        period.latencies.sort_unstable();
+        println!(
+            "connects/s: {:.1} ({} failed), deaths: {}, errors: {}, responses/s: {:.1}, \
+             latency p50: {:?}, p90: {:?}, p99: {:?}, max: {:?}",
+            period.connects as f32 / seconds,
+            period.failed_connects,
+            period.deaths,
+            period.errors,
+            period.latencies.len() as f32 / seconds,
+            percentile(&period.latencies, 50),
+            percentile(&period.latencies, 90),
+            percentile(&period.latencies, 99),
+            period.latencies.last().copied().unwrap_or_default(),
+        );
+    }
+}
+
+/// Returns the `p`-th percentile of sorted durations, or zero if there are none.
+fn percentile(sorted: &[Duration], p: usize) -> Duration {
+    match sorted.len() {
+        0 => Duration::ZERO,
+        len => sorted[(len - 1) * p / 100],
+    }
+}
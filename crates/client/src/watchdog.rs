@@ -0,0 +1,88 @@
+/// Module for the watchdog noticing when the player is stuck.
+use crate::planner::Planner;
+use crate::server::commands::{Command, Response};
+
+/// The number of moves in a row that may not change the position of the player before it
+/// is considered stuck.
+const MAX_STALLED_MOVES: u32 = 8;
+
+/// The number of commands in a row that may fail before the player is considered stuck.
+const MAX_FAILURES: u32 = 16;
+
+/// The number of resynchronizations in a row that may not get the player moving again
+/// before the planner is reset.
+const MAX_RESYNCS: u32 = 2;
+
+/// What must be done to get a stuck player going again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// The player is fine.
+    Continue,
+    /// The surroundings and the inventory of the player must be asked to the server again.
+    Resync,
+    /// Resynchronizing did not help: what the planner knows must be forgotten, then the
+    /// player must resynchronize.
+    Reset,
+}
+
+/// Keeps an eye on the commands and their responses to notice when the player stops
+/// making progress, instead of looping forever.
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    /// The position of the player after the last move.
+    position: (i64, i64),
+    /// The number of moves in a row that did not change the position of the player.
+    stalled_moves: u32,
+    /// The number of commands in a row that the server answered with `ko`.
+    failures: u32,
+    /// The number of resynchronizations since the player last moved.
+    resyncs: u32,
+}
+
+impl Watchdog {
+    /// Checks the response to a command, once the planner has been updated with it.
+    pub fn check(&mut self, command: &Command, response: &Response, planner: &Planner) -> Action {
+        if let Command::Forward = command {
+            if planner.position() == self.position {
+                self.stalled_moves += 1;
+            } else {
+                self.position = planner.position();
+                self.stalled_moves = 0;
+                self.resyncs = 0;
+            }
+        }
+        if let Response::Ko = response {
+            self.failures += 1;
+        } else {
+            self.failures = 0;
+        }
+
+        if self.stalled_moves < MAX_STALLED_MOVES && self.failures < MAX_FAILURES {
+            return Action::Continue;
+        }
+        self.stalled_moves = 0;
+        self.failures = 0;
+        self.resync_needed()
+    }
+
+    /// Records that the server stopped answering.
+    pub fn timed_out(&mut self) -> Action {
+        self.resync_needed()
+    }
+
+    /// Returns how to resynchronize, depending on how many times it was already tried.
+    fn resync_needed(&mut self) -> Action {
+        self.resyncs += 1;
+        if self.resyncs > MAX_RESYNCS {
+            self.resyncs = 0;
+            Action::Reset
+        } else {
+            Action::Resync
+        }
+    }
+
+    /// Forgets the last known position of the player, after the planner was reset.
+    pub fn reset(&mut self, planner: &Planner) {
+        self.position = planner.position();
+    }
+}
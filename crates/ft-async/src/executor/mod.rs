@@ -6,6 +6,8 @@ use core::task::{Context, Poll, Waker};
 use self::task_list::TaskId;
 use self::task_waker::TaskWaker;
 use self::tasks::Tasks;
+
+pub use self::tasks::Priority;
 use self::waker::waker_from_task_id;
 
 mod task_list;
@@ -17,7 +19,15 @@ mod waker;
 type Mutex<T> = ft::Mutex<T, ft::sync::mutex::NoBlockMutex>;
 
 /// A boxed future, supported by the executor.
-type Task<'a> = Pin<Box<dyn 'a + Send + Future<Output = ()>>>;
+///
+/// The future itself is not required to be [`Send`]: see [`Executor::spawn_local`].
+struct Task<'a>(Pin<Box<dyn 'a + Future<Output = ()>>>);
+
+// SAFETY:
+//  The executor is shared by every thread, so its tasks must be `Send`. Tasks spawned with
+//  `Executor::spawn` are. The caller of `Executor::spawn_local` promises that the others
+//  are only polled and dropped on the thread that spawned them.
+unsafe impl Send for Task<'_> {}
 
 /// The executor keeping track of which tasks is ready to be polled.
 pub struct Executor<'a> {
@@ -37,11 +47,36 @@ impl<'a> Executor<'a> {
     }
 
     /// Spawns a new task onto the executor.
-    pub fn spawn<F>(&self, future: F)
+    ///
+    /// Whenever several tasks are ready, the ones with the highest priority are polled
+    /// first.
+    ///
+    /// The executor is a global shared by every thread, and a task is polled by whichever
+    /// thread calls [`Executor::run_one_task`]: the future must therefore be [`Send`].
+    /// Futures holding state that cannot leave the current thread may be spawned with
+    /// [`Executor::spawn_local`] instead.
+    pub fn spawn<F>(&self, priority: Priority, future: F)
     where
         F: Send + Future<Output = ()> + 'a,
     {
-        self.tasks.lock().insert(Box::pin(future));
+        self.tasks.lock().insert(Task(Box::pin(future)), priority);
+    }
+
+    /// Spawns a new task onto the executor, without requiring the future to be [`Send`].
+    ///
+    /// This allows a task to keep its state in types such as [`Rc`](alloc::rc::Rc) or
+    /// [`RefCell`](core::cell::RefCell) rather than in thread-safe wrappers.
+    ///
+    /// # Safety
+    ///
+    /// Until the task completes, the executor must only be run (with
+    /// [`Executor::run_one_task`]) and cleaned (with [`Executor::clean`]) on the current
+    /// thread. This is always the case in a single-threaded program.
+    pub unsafe fn spawn_local<F>(&self, priority: Priority, future: F)
+    where
+        F: Future<Output = ()> + 'a,
+    {
+        self.tasks.lock().insert(Task(Box::pin(future)), priority);
     }
 
     /// Registers a task to be woken up when the provided alarm expires.
@@ -58,9 +93,14 @@ impl<'a> Executor<'a> {
     ///
     /// In other words, when reading the file descriptor becomes guaranteed not
     /// to block, the task will be woken up.
+    ///
+    /// # Returns
+    ///
+    /// `ft::Errno::INVAL` if the file descriptor is too high to be waited for with
+    /// [`ft::select`] (1024 and above), in which case the task will not be woken up.
     #[inline]
-    pub fn wake_me_up_on_read(&self, fd: ft::Fd, waker: Waker) {
-        self.waker.lock().register_read(fd, waker);
+    pub fn wake_me_up_on_read(&self, fd: ft::Fd, waker: Waker) -> ft::Result<()> {
+        self.waker.lock().register_read(fd, waker)
     }
 
     /// Registers a task to be woken up when the provided file descriptor is
@@ -68,9 +108,28 @@ impl<'a> Executor<'a> {
     ///
     /// In other words, when writing to the file descriptor becomes guaranteed
     /// not to block, the task will be woken up.
+    ///
+    /// # Returns
+    ///
+    /// `ft::Errno::INVAL` if the file descriptor is too high to be waited for with
+    /// [`ft::select`] (1024 and above), in which case the task will not be woken up.
     #[inline]
-    pub fn wake_me_up_on_write(&self, fd: ft::Fd, waker: Waker) {
-        self.waker.lock().register_write(fd, waker);
+    pub fn wake_me_up_on_write(&self, fd: ft::Fd, waker: Waker) -> ft::Result<()> {
+        self.waker.lock().register_write(fd, waker)
+    }
+
+    /// Cancels a previous call to [`Executor::wake_me_up_on_read`] made with the same
+    /// file descriptor and a waker of the same task.
+    #[inline]
+    pub fn forget_read(&self, fd: ft::Fd, waker: &Waker) {
+        self.waker.lock().unregister_read(fd, waker);
+    }
+
+    /// Cancels a previous call to [`Executor::wake_me_up_on_write`] made with the same
+    /// file descriptor and a waker of the same task.
+    #[inline]
+    pub fn forget_write(&self, fd: ft::Fd, waker: &Waker) {
+        self.waker.lock().unregister_write(fd, waker);
     }
 
     /// Wakes a task up.
@@ -89,7 +148,7 @@ impl<'a> Executor<'a> {
 
         let waker = waker_from_task_id(id);
         let mut context = Context::from_waker(&waker);
-        match task.as_mut().poll(&mut context) {
+        match task.0.as_mut().poll(&mut context) {
             Poll::Ready(()) => self.tasks.lock().now_ready(),
             Poll::Pending => self.tasks.lock().now_pending(task),
         }
@@ -109,6 +168,19 @@ impl<'a> Executor<'a> {
         self.tasks.lock().is_empty()
     }
 
+    /// Returns the number of tasks managed by the executor, including the one currently
+    /// running.
+    #[inline]
+    pub fn task_count(&self) -> usize {
+        self.tasks.lock().len()
+    }
+
+    /// Returns the number of wakers waiting for `fd` to be readable and writable.
+    #[cfg(test)]
+    pub(crate) fn waiting_for(&self, fd: ft::Fd) -> (usize, usize) {
+        self.waker.lock().waiting_for(fd)
+    }
+
     /// Clears the executor of all its running tasks, resting it to its initial state.
     ///
     /// This function may additionally be used to free up the memory it uses.
@@ -45,6 +45,12 @@ impl<'a> TaskList<'a> {
         self.count == 0
     }
 
+    /// Returns the number of tasks in the list.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
     /// Attempts to find a hole in the list, starting from the current first hole,
     /// and moving right.
     fn update_hole_rightward(&mut self) {
@@ -4,6 +4,24 @@ use core::time::Duration;
 use alloc::collections::BinaryHeap;
 use alloc::vec::Vec;
 
+/// The amount of time by which alarms may be expired early.
+///
+/// Sleepers whose alarms expire within this window of each other are woken up together,
+/// which avoids going back to [`ft::select`] for each of them when many tasks sleep for
+/// about the same amount of time.
+const TIMER_SLACK: Duration = Duration::from_millis(1);
+
+/// The number of file descriptors an [`ft::fd::FdSet`] can hold, known as `FD_SETSIZE`.
+///
+/// Inserting a file descriptor past this limit would write out of the set, so tasks
+/// waiting for one are refused instead.
+const FD_SETSIZE: i32 = 1024;
+
+/// Returns whether the provided file descriptor fits in an [`ft::fd::FdSet`].
+fn fits_in_fdset(fd: ft::Fd) -> bool {
+    ft::Fd::from_raw(0) <= fd && fd < ft::Fd::from_raw(FD_SETSIZE)
+}
+
 /// An task currently blocked because of an I/O operation.
 struct BlockedByIo {
     /// The waker to `.wake()` when the operation becomes non-blocking.
@@ -74,9 +92,39 @@ impl EventSet {
 
     /// Registers a task to be woken up when the provided file descriptor becomes
     /// non-blocking.
-    #[inline]
-    pub fn register(&mut self, fd: ft::Fd, waker: Waker) {
-        self.list.push(BlockedByIo { waker, fd });
+    ///
+    /// A task waiting for the same file descriptor again replaces its previous waker,
+    /// so that it is only woken up once.
+    ///
+    /// # Returns
+    ///
+    /// `ft::Errno::INVAL` if the file descriptor is negative or does not fit in an
+    /// [`ft::fd::FdSet`], in which case nothing is registered.
+    pub fn register(&mut self, fd: ft::Fd, waker: Waker) -> ft::Result<()> {
+        if !fits_in_fdset(fd) {
+            return Err(ft::Errno::INVAL);
+        }
+        match self
+            .list
+            .iter_mut()
+            .find(|task| task.fd == fd && task.waker.will_wake(&waker))
+        {
+            Some(task) => task.waker = waker,
+            None => self.list.push(BlockedByIo { waker, fd }),
+        }
+        Ok(())
+    }
+
+    /// Stops waiting for the provided file descriptor on behalf of the task that
+    /// `waker` wakes up.
+    pub fn unregister(&mut self, fd: ft::Fd, waker: &Waker) {
+        if let Some(index) = self
+            .list
+            .iter()
+            .position(|task| task.fd == fd && task.waker.will_wake(waker))
+        {
+            self.list.swap_remove(index);
+        }
     }
 }
 
@@ -100,15 +148,27 @@ impl Select {
     /// Registers a task to be woken up when the provided file descriptor becomes
     /// non-blocking for reads.
     #[inline]
-    pub fn register_read(&mut self, fd: ft::Fd, waker: Waker) {
-        self.read.register(fd, waker);
+    pub fn register_read(&mut self, fd: ft::Fd, waker: Waker) -> ft::Result<()> {
+        self.read.register(fd, waker)
     }
 
     /// Registers a task to be woken up when the provided file descriptor becomes
     /// non-blocking for writes.
     #[inline]
-    pub fn register_write(&mut self, fd: ft::Fd, waker: Waker) {
-        self.write.register(fd, waker);
+    pub fn register_write(&mut self, fd: ft::Fd, waker: Waker) -> ft::Result<()> {
+        self.write.register(fd, waker)
+    }
+
+    /// Stops waiting for reads on the provided file descriptor on behalf of a task.
+    #[inline]
+    pub fn unregister_read(&mut self, fd: ft::Fd, waker: &Waker) {
+        self.read.unregister(fd, waker);
+    }
+
+    /// Stops waiting for writes on the provided file descriptor on behalf of a task.
+    #[inline]
+    pub fn unregister_write(&mut self, fd: ft::Fd, waker: &Waker) {
+        self.write.unregister(fd, waker);
     }
 
     /// Returns whether there are currently any tasks waiting for I/O.
@@ -202,11 +262,13 @@ impl Sleepers {
     }
 
     /// Wakes up tasks that are ready to be polled.
+    ///
+    /// Tasks whose alarm expires within [`TIMER_SLACK`] are woken up as well.
     #[allow(clippy::unwrap_used)]
     pub fn wake_up_tasks(&mut self) -> ft::Result<()> {
-        let now = ft::Clock::MONOTONIC.get();
+        let deadline = ft::Clock::MONOTONIC.get() + TIMER_SLACK;
         while let Some(sleeper) = self.list.peek() {
-            if sleeper.alarm <= now {
+            if sleeper.alarm <= deadline {
                 self.list.pop().unwrap().waker.wake();
             } else {
                 break;
@@ -237,15 +299,27 @@ impl TaskWaker {
     /// Registers a task to be woken up when the provided file descriptor becomes
     /// non-blocking for reads.
     #[inline]
-    pub fn register_read(&mut self, fd: ft::Fd, waker: Waker) {
-        self.select.register_read(fd, waker);
+    pub fn register_read(&mut self, fd: ft::Fd, waker: Waker) -> ft::Result<()> {
+        self.select.register_read(fd, waker)
     }
 
     /// Registers a task to be woken up when the provided file descriptor becomes
     /// non-blocking for writes.
     #[inline]
-    pub fn register_write(&mut self, fd: ft::Fd, waker: Waker) {
-        self.select.register_write(fd, waker);
+    pub fn register_write(&mut self, fd: ft::Fd, waker: Waker) -> ft::Result<()> {
+        self.select.register_write(fd, waker)
+    }
+
+    /// Stops waiting for reads on the provided file descriptor on behalf of a task.
+    #[inline]
+    pub fn unregister_read(&mut self, fd: ft::Fd, waker: &Waker) {
+        self.select.unregister_read(fd, waker);
+    }
+
+    /// Stops waiting for writes on the provided file descriptor on behalf of a task.
+    #[inline]
+    pub fn unregister_write(&mut self, fd: ft::Fd, waker: &Waker) {
+        self.select.unregister_write(fd, waker);
     }
 
     /// Registers a task to be woken up when the provided alarm expires.
@@ -260,7 +334,14 @@ impl TaskWaker {
         let timeout = match self.sleepers.earliest() {
             Some(earliest) => {
                 let now = ft::Clock::MONOTONIC.get();
-                Some(earliest.saturating_sub(now))
+                // The earliest alarm is close enough to be woken up right away, only
+                // poll the file descriptors.
+                let remaining = earliest.saturating_sub(now);
+                if remaining <= TIMER_SLACK {
+                    Some(Duration::ZERO)
+                } else {
+                    Some(remaining)
+                }
             }
             None => None,
         };
@@ -272,4 +353,66 @@ impl TaskWaker {
         self.sleepers.wake_up_tasks()?;
         Ok(())
     }
+
+    /// Returns the number of wakers waiting for `fd` to be readable and writable.
+    #[cfg(test)]
+    pub fn waiting_for(&self, fd: ft::Fd) -> (usize, usize) {
+        let count = |set: &EventSet| set.list.iter().filter(|task| task.fd == fd).count();
+        (count(&self.select.read), count(&self.select.write))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::super::waker::waker_from_task_id;
+    use super::*;
+
+    #[test]
+    fn registering_twice_keeps_one_waker() {
+        let mut set = EventSet::new();
+        let fd = ft::Fd::from_raw(3);
+
+        set.register(fd, waker_from_task_id(0)).unwrap();
+        set.register(fd, waker_from_task_id(0)).unwrap();
+        assert_eq!(set.list.len(), 1);
+
+        // Other tasks, or other file descriptors, are waited for separately.
+        set.register(fd, waker_from_task_id(1)).unwrap();
+        set.register(ft::Fd::from_raw(4), waker_from_task_id(0))
+            .unwrap();
+        assert_eq!(set.list.len(), 3);
+    }
+
+    #[test]
+    fn unregistering_only_forgets_the_task() {
+        let mut set = EventSet::new();
+        let fd = ft::Fd::from_raw(3);
+        set.register(fd, waker_from_task_id(0)).unwrap();
+        set.register(fd, waker_from_task_id(1)).unwrap();
+
+        set.unregister(fd, &waker_from_task_id(0));
+        assert_eq!(set.list.len(), 1);
+        assert!(set.list[0].waker.will_wake(&waker_from_task_id(1)));
+
+        // Forgetting a task that does not wait does nothing.
+        set.unregister(fd, &waker_from_task_id(0));
+        set.unregister(ft::Fd::from_raw(4), &waker_from_task_id(1));
+        assert_eq!(set.list.len(), 1);
+
+        set.unregister(fd, &waker_from_task_id(1));
+        assert!(!set.anybody_waiting());
+    }
+
+    #[test]
+    fn file_descriptors_outside_of_fdset_are_refused() {
+        let mut set = EventSet::new();
+        for fd in [-1, FD_SETSIZE] {
+            assert_eq!(
+                set.register(ft::Fd::from_raw(fd), waker_from_task_id(0)),
+                Err(ft::Errno::INVAL)
+            );
+        }
+        assert!(!set.anybody_waiting());
+    }
 }
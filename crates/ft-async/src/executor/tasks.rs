@@ -1,15 +1,43 @@
 use alloc::collections::VecDeque;
+use alloc::vec::Vec;
 
 use super::task_list::{TaskId, TaskList};
 use super::Task;
 
+/// The number of tasks that may be polled before a ready task is polled, whatever its
+/// priority.
+///
+/// This prevents a steady stream of high priority tasks from starving the others.
+const MAX_WAIT: u64 = 32;
+
+/// How urgently a task must be polled once it is ready.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// The task must run as soon as possible, such as the one simulating the game.
+    High,
+    /// The default priority.
+    Normal,
+    /// The task may wait for the others, such as background or bulk work.
+    Low,
+}
+
+impl Priority {
+    /// The number of priorities.
+    const COUNT: usize = 3;
+}
+
 /// The part of the [`Executor`] that is responsible for storing up tasks and keeping
 /// track of which ones are ready to be polled.
 pub struct Tasks<'a> {
     /// The list of all tasks managed by the executor.
     tasks: TaskList<'a>,
-    /// The list of tasks that are ready to be polled.
-    ready: VecDeque<TaskId>,
+    /// The priority of every task, indexed by [`TaskId`].
+    priorities: Vec<Priority>,
+    /// The tasks that are ready to be polled, one queue per priority, along with the
+    /// number of polls when they became ready.
+    ready: [VecDeque<(TaskId, u64)>; Priority::COUNT],
+    /// The number of tasks taken with [`take_ready`](Self::take_ready) so far.
+    polls: u64,
 }
 
 impl<'a> Tasks<'a> {
@@ -17,7 +45,9 @@ impl<'a> Tasks<'a> {
     pub const fn new() -> Self {
         Self {
             tasks: TaskList::new(),
-            ready: VecDeque::new(),
+            priorities: Vec::new(),
+            ready: [VecDeque::new(), VecDeque::new(), VecDeque::new()],
+            polls: 0,
         }
     }
 
@@ -27,10 +57,23 @@ impl<'a> Tasks<'a> {
         self.tasks.is_empty()
     }
 
+    /// Returns the number of tasks in the collection.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
     /// Returns a task that is ready to be polled, if any.
+    ///
+    /// Tasks with a higher priority go first, unless a task has been waiting for more
+    /// than [`MAX_WAIT`] polls, in which case the one waiting for the longest goes first.
     pub fn take_ready(&mut self) -> Option<(usize, Task<'a>)> {
-        while let Some(id) = self.ready.pop_front() {
+        while let Some(queue) = self.next_queue() {
+            let Some((id, _)) = self.ready[queue].pop_front() else {
+                break;
+            };
             if let Some(task) = self.tasks.remove_reserve(id) {
+                self.polls += 1;
                 return Some((id, task));
             }
         }
@@ -38,6 +81,22 @@ impl<'a> Tasks<'a> {
         None
     }
 
+    /// Returns the index of the queue the next ready task must be taken from.
+    fn next_queue(&self) -> Option<usize> {
+        let starving = self
+            .ready
+            .iter()
+            .enumerate()
+            .filter_map(|(queue, ready)| Some((queue, ready.front()?.1)))
+            .filter(|&(_, since)| self.polls - since > MAX_WAIT)
+            .min_by_key(|&(_, since)| since);
+
+        match starving {
+            Some((queue, _)) => Some(queue),
+            None => self.ready.iter().position(|ready| !ready.is_empty()),
+        }
+    }
+
     /// Marks the task taken with [`take_ready`](Self::take_ready) as pending.
     #[inline]
     pub fn now_pending(&mut self, task: Task<'a>) {
@@ -54,15 +113,81 @@ impl<'a> Tasks<'a> {
     /// Adds a task to the list and returns its ID.
     ///
     /// New tasks are always scheduled to be polled.
-    pub fn insert(&mut self, task: Task<'a>) -> TaskId {
+    pub fn insert(&mut self, task: Task<'a>, priority: Priority) -> TaskId {
         let id = self.tasks.insert(task);
-        self.ready.push_back(id);
+        if id >= self.priorities.len() {
+            self.priorities.resize(id + 1, Priority::Normal);
+        }
+        self.priorities[id] = priority;
+        self.set_ready(id);
         id
     }
 
     /// Sets a task as ready to be polled.
     #[inline]
     pub fn set_ready(&mut self, id: TaskId) {
-        self.ready.push_back(id);
+        let priority = self.priorities.get(id).copied().unwrap_or(Priority::Normal);
+        self.ready[priority as usize].push_back((id, self.polls));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::boxed::Box;
+
+    use super::*;
+
+    /// Returns a task that does nothing.
+    fn task() -> Task<'static> {
+        Task(Box::pin(async {}))
+    }
+
+    /// Takes the next ready task and puts it back as pending, returning its ID.
+    fn poll_next(tasks: &mut Tasks) -> Option<TaskId> {
+        let (id, task) = tasks.take_ready()?;
+        tasks.now_pending(task);
+        Some(id)
+    }
+
+    #[test]
+    fn higher_priorities_go_first() {
+        let mut tasks = Tasks::new();
+        let low = tasks.insert(task(), Priority::Low);
+        let normal = tasks.insert(task(), Priority::Normal);
+        let high = tasks.insert(task(), Priority::High);
+        let other_normal = tasks.insert(task(), Priority::Normal);
+
+        assert_eq!(poll_next(&mut tasks), Some(high));
+        assert_eq!(poll_next(&mut tasks), Some(normal));
+        assert_eq!(poll_next(&mut tasks), Some(other_normal));
+        assert_eq!(poll_next(&mut tasks), Some(low));
+        assert_eq!(poll_next(&mut tasks), None);
+    }
+
+    #[test]
+    fn waiting_tasks_are_not_starved() {
+        let mut tasks = Tasks::new();
+        let low = tasks.insert(task(), Priority::Low);
+        let high = tasks.insert(task(), Priority::High);
+
+        // The high priority task is always ready again after being polled.
+        for _ in 0..=MAX_WAIT {
+            assert_eq!(poll_next(&mut tasks), Some(high));
+            tasks.set_ready(high);
+        }
+        assert_eq!(poll_next(&mut tasks), Some(low));
+        assert_eq!(poll_next(&mut tasks), Some(high));
+    }
+
+    #[test]
+    fn finished_tasks_are_removed() {
+        let mut tasks = Tasks::new();
+        tasks.insert(task(), Priority::High);
+        tasks.insert(task(), Priority::Low);
+
+        while tasks.take_ready().is_some() {
+            tasks.now_ready();
+        }
+        assert!(tasks.is_empty());
     }
 }
@@ -2,29 +2,31 @@ use core::future::Future;
 use core::pin::Pin;
 use core::task::{Context, Poll};
 
-use crate::EXECUTOR;
+use super::registration::{Readiness, Registration};
 
 /// Creates a [`Future`] that completes when the provided file descriptor is ready to
 /// be read.
 ///
-/// When this future completes, the file descriptor is guaranteed not to block when
-/// reading from it.
+/// When this future completes successfully, the file descriptor is guaranteed not to
+/// block when reading from it. It fails right away if the file descriptor cannot be
+/// waited for, see [`Executor::wake_me_up_on_read`](crate::Executor::wake_me_up_on_read).
 pub fn ready_for_reading(fd: ft::Fd) -> ReadyForReading {
     ReadyForReading {
         fd,
-        waker_registered: false,
+        registration: Registration::new(Readiness::Read),
     }
 }
 
 /// Creates a [`Future`] that completes when the provided file descriptor is ready to
 /// be written.
 ///
-/// When this future completes, the file descriptor is guaranteed not to block when
-/// writing to it.
+/// When this future completes successfully, the file descriptor is guaranteed not to
+/// block when writing to it. It fails right away if the file descriptor cannot be
+/// waited for, see [`Executor::wake_me_up_on_write`](crate::Executor::wake_me_up_on_write).
 pub fn ready_for_writing(fd: ft::Fd) -> ReadyForWriting {
     ReadyForWriting {
         fd,
-        waker_registered: false,
+        registration: Registration::new(Readiness::Write),
     }
 }
 
@@ -32,18 +34,18 @@ pub fn ready_for_writing(fd: ft::Fd) -> ReadyForWriting {
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 pub struct ReadyForReading {
     fd: ft::Fd,
-    waker_registered: bool,
+    registration: Registration,
 }
 
 impl Future for ReadyForReading {
-    type Output = ();
+    type Output = ft::Result<()>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        if self.waker_registered {
-            Poll::Ready(())
+        if self.registration.is_registered() {
+            Poll::Ready(Ok(()))
         } else {
-            self.waker_registered = true;
-            EXECUTOR.wake_me_up_on_read(self.fd, cx.waker().clone());
+            let fd = self.fd;
+            self.registration.register(fd, cx.waker())?;
             Poll::Pending
         }
     }
@@ -53,18 +55,18 @@ impl Future for ReadyForReading {
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 pub struct ReadyForWriting {
     fd: ft::Fd,
-    waker_registered: bool,
+    registration: Registration,
 }
 
 impl Future for ReadyForWriting {
-    type Output = ();
+    type Output = ft::Result<()>;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
-        if self.waker_registered {
-            Poll::Ready(())
+        if self.registration.is_registered() {
+            Poll::Ready(Ok(()))
         } else {
-            self.waker_registered = true;
-            EXECUTOR.wake_me_up_on_write(self.fd, cx.waker().clone());
+            let fd = self.fd;
+            self.registration.register(fd, cx.waker())?;
             Poll::Pending
         }
     }
@@ -4,12 +4,16 @@ use core::task::{Context, Poll};
 
 use ft::collections::ReadBuffer;
 
-use crate::EXECUTOR;
+use super::registration::{Readiness, Registration};
 
 /// Returns a future that completes when the `buf` has been completely written to the
 /// provided file descriptor.
-pub fn write_all(fd: ft::Fd, buf: &[u8]) -> WriteAll {
-    WriteAll { fd, buf }
+pub fn write_all(fd: ft::Fd, buf: &[u8]) -> WriteAll<'_> {
+    WriteAll {
+        fd,
+        buf,
+        registration: Registration::new(Readiness::Write),
+    }
 }
 
 /// Returns a future that completes when a complete line (delimited by `\n`) has been read
@@ -23,8 +27,12 @@ pub fn write_all(fd: ft::Fd, buf: &[u8]) -> WriteAll {
 /// # Returns
 ///
 /// An error, or the line without the final delimiter.
-pub fn read_line(fd: ft::Fd, buf: &mut ReadBuffer) -> ReadLine {
-    ReadLine { fd, buf }
+pub fn read_line(fd: ft::Fd, buf: &mut ReadBuffer) -> ReadLine<'_> {
+    ReadLine {
+        fd,
+        buf,
+        registration: Registration::new(Readiness::Read),
+    }
 }
 
 /// See [`write_all`].
@@ -32,6 +40,7 @@ pub fn read_line(fd: ft::Fd, buf: &mut ReadBuffer) -> ReadLine {
 pub struct WriteAll<'a> {
     fd: ft::Fd,
     buf: &'a [u8],
+    registration: Registration,
 }
 
 impl Future for WriteAll<'_> {
@@ -40,6 +49,8 @@ impl Future for WriteAll<'_> {
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
         let n = match self.fd.write(self.buf) {
             Ok(n) => n,
+            // Non-blocking file descriptors may be full.
+            Err(ft::Errno::AGAIN) => 0,
             Err(err) => return Poll::Ready(Err(err)),
         };
 
@@ -48,7 +59,8 @@ impl Future for WriteAll<'_> {
         if self.buf.is_empty() {
             Poll::Ready(Ok(()))
         } else {
-            EXECUTOR.wake_me_up_on_write(self.fd, cx.waker().clone());
+            let fd = self.fd;
+            self.registration.register(fd, cx.waker())?;
             Poll::Pending
         }
     }
@@ -58,6 +70,7 @@ impl Future for WriteAll<'_> {
 pub struct ReadLine<'a> {
     fd: ft::Fd,
     buf: &'a mut ReadBuffer,
+    registration: Registration,
 }
 
 impl<'a> ReadLine<'a> {
@@ -111,16 +124,175 @@ impl<'a> Future for ReadLine<'a> {
         // Try to read from the file descriptor.
         match self.buf.fill_with_fd(fd) {
             Ok(0) => return Poll::Ready(Err(ft::Errno::CONNRESET)),
-            Ok(_) => (),
+            // Non-blocking file descriptors may have nothing to read yet.
+            Ok(_) | Err(ft::Errno::AGAIN) => (),
             Err(err) => return Poll::Ready(Err(err)),
         };
 
         match self.check_line() {
             Some(line) => Poll::Ready(Ok(line)),
             None => {
-                EXECUTOR.wake_me_up_on_read(fd, cx.waker().clone());
+                self.registration.register(fd, cx.waker())?;
                 Poll::Pending
             }
         }
     }
 }
+
+/// Returns a future that completes once some bytes have been read from the provided file
+/// descriptor into `buf`.
+///
+/// The task first waits for the file descriptor to become readable, then performs a
+/// single read.
+///
+/// # Cancellation safety
+///
+/// This future is cancellation-safe: bytes are only read during the call to `poll` that
+/// returns them, so dropping the future before it completes never loses data. Buffered
+/// readers may be built on top of it.
+///
+/// # Returns
+///
+/// An error, or the number of bytes read. `0` means that the end of file was reached.
+pub fn read(fd: ft::Fd, buf: &mut [u8]) -> Read<'_> {
+    Read {
+        fd,
+        buf,
+        registration: Registration::new(Readiness::Read),
+    }
+}
+
+/// Returns a future that completes once some bytes of `buf` have been written to the
+/// provided file descriptor.
+///
+/// The task first waits for the file descriptor to become writable, then performs a
+/// single write.
+///
+/// # Cancellation safety
+///
+/// This future is cancellation-safe: bytes are only written during the call to `poll`
+/// that reports them, so dropping the future before it completes never writes data the
+/// caller does not know about. Buffered writers may be built on top of it.
+///
+/// # Returns
+///
+/// An error, or the number of bytes written, which may be less than `buf.len()`.
+pub fn write(fd: ft::Fd, buf: &[u8]) -> Write<'_> {
+    Write {
+        fd,
+        buf,
+        registration: Registration::new(Readiness::Write),
+    }
+}
+
+/// See [`read`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Read<'a> {
+    fd: ft::Fd,
+    buf: &'a mut [u8],
+    registration: Registration,
+}
+
+impl Future for Read<'_> {
+    type Output = ft::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let fd = self.fd;
+
+        if !self.registration.is_registered() {
+            self.registration.register(fd, cx.waker())?;
+            return Poll::Pending;
+        }
+
+        match fd.read(self.buf) {
+            // Non-blocking file descriptors may still have nothing to read after a
+            // spurious wake up.
+            Err(ft::Errno::AGAIN) => {
+                self.registration.register(fd, cx.waker())?;
+                Poll::Pending
+            }
+            result => Poll::Ready(result),
+        }
+    }
+}
+
+/// See [`write`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Write<'a> {
+    fd: ft::Fd,
+    buf: &'a [u8],
+    registration: Registration,
+}
+
+impl Future for Write<'_> {
+    type Output = ft::Result<usize>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let fd = self.fd;
+
+        if !self.registration.is_registered() {
+            self.registration.register(fd, cx.waker())?;
+            return Poll::Pending;
+        }
+
+        match fd.write(self.buf) {
+            // Non-blocking file descriptors may still be full after a spurious wake up.
+            Err(ft::Errno::AGAIN) => {
+                self.registration.register(fd, cx.waker())?;
+                Poll::Pending
+            }
+            result => Poll::Ready(result),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use core::pin::pin;
+    use core::task::Waker;
+
+    use super::*;
+    use crate::EXECUTOR;
+
+    /// A file descriptor too high to be waited for with `select`.
+    const FD_PAST_FDSET: i32 = 1024;
+
+    #[test]
+    fn read_dropped_before_completing_forgets_its_waker() {
+        let fd = ft::Fd::from_raw(1010);
+        let mut buf = [0; 16];
+        let mut cx = Context::from_waker(Waker::noop());
+        {
+            let mut future = pin!(read(fd, &mut buf));
+            assert!(future.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(EXECUTOR.waiting_for(fd), (1, 0));
+        }
+        assert_eq!(EXECUTOR.waiting_for(fd), (0, 0));
+    }
+
+    #[test]
+    fn write_dropped_before_completing_forgets_its_waker() {
+        let fd = ft::Fd::from_raw(1011);
+        let mut cx = Context::from_waker(Waker::noop());
+        {
+            let mut future = pin!(write(fd, b"hello"));
+            assert!(future.as_mut().poll(&mut cx).is_pending());
+            assert_eq!(EXECUTOR.waiting_for(fd), (0, 1));
+        }
+        assert_eq!(EXECUTOR.waiting_for(fd), (0, 0));
+    }
+
+    #[test]
+    fn futures_waiting_for_refused_file_descriptors_fail() {
+        let fd = ft::Fd::from_raw(FD_PAST_FDSET);
+        let mut buf = [0; 16];
+        let mut cx = Context::from_waker(Waker::noop());
+
+        let result = pin!(read(fd, &mut buf)).poll(&mut cx);
+        assert!(matches!(result, Poll::Ready(Err(ft::Errno::INVAL))));
+        let result = pin!(write(fd, b"hello")).poll(&mut cx);
+        assert!(matches!(result, Poll::Ready(Err(ft::Errno::INVAL))));
+        assert_eq!(EXECUTOR.waiting_for(fd), (0, 0));
+    }
+}
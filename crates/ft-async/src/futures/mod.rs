@@ -1,10 +1,18 @@
 //! A collection of futures and utilities to work with them.
 
+mod registration;
+
 mod basic;
 pub use self::basic::*;
 
 mod io;
 pub use self::io::*;
 
+mod net;
+pub use self::net::*;
+
 mod time;
 pub use self::time::*;
+
+mod yielding;
+pub use self::yielding::*;
@@ -0,0 +1,66 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use super::registration::{Readiness, Registration};
+
+/// Returns a future that completes when a TCP connection to the provided address has
+/// been established.
+///
+/// The connection is performed in non-blocking mode: the task is woken up when the socket
+/// becomes writable, which is when the connection either succeeded or failed.
+///
+/// # Returns
+///
+/// An error, or the connected socket.
+pub fn connect(addr: ft::net::SocketAddr) -> Connect {
+    Connect {
+        addr,
+        socket: None,
+        registration: Registration::new(Readiness::Write),
+    }
+}
+
+/// See [`connect`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Connect {
+    /// The address to connect to.
+    addr: ft::net::SocketAddr,
+    /// The socket, once the connection has been initiated.
+    socket: Option<ft::File>,
+    /// The waker registered while the connection is in progress.
+    registration: Registration,
+}
+
+/// Creates a non-blocking TCP socket able to connect to the provided address.
+fn nonblocking_socket(addr: &ft::net::SocketAddr) -> ft::Result<ft::File> {
+    let socket = ft::File::socket(addr.family(), ft::net::SocketType::Stream)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket)
+}
+
+impl Future for Connect {
+    type Output = ft::Result<ft::File>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let socket = match self.socket.take() {
+            Some(socket) => socket,
+            None => match nonblocking_socket(&self.addr) {
+                Ok(socket) => socket,
+                Err(err) => return Poll::Ready(Err(err)),
+            },
+        };
+
+        // The first call initiates the connection. Calling `connect` again on a socket
+        // whose connection is in progress reports the outcome of the connection.
+        match socket.connect(&self.addr) {
+            Ok(()) | Err(ft::Errno::ISCONN) => Poll::Ready(Ok(socket)),
+            Err(ft::Errno::INPROGRESS | ft::Errno::ALREADY) => {
+                self.registration.register(*socket, cx.waker())?;
+                self.socket = Some(socket);
+                Poll::Pending
+            }
+            Err(err) => Poll::Ready(Err(err)),
+        }
+    }
+}
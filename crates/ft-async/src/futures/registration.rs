@@ -0,0 +1,113 @@
+use core::task::Waker;
+
+use crate::EXECUTOR;
+
+/// What a future waits for on a file descriptor.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Readiness {
+    /// The file descriptor can be read without blocking.
+    Read,
+    /// The file descriptor can be written without blocking.
+    Write,
+}
+
+/// Keeps track of the waker a future registered with the executor, and removes it when
+/// the future is dropped.
+///
+/// Without this, a future dropped before its file descriptor becomes ready would leave
+/// its waker behind, waking its task up for nothing and keeping the file descriptor in
+/// the `select` set even after it has been closed.
+pub(crate) struct Registration {
+    /// What the future waits for.
+    readiness: Readiness,
+    /// The file descriptor and the waker last registered, if any.
+    registered: Option<(ft::Fd, Waker)>,
+}
+
+impl Registration {
+    /// Creates a [`Registration`] that has not registered anything yet.
+    pub const fn new(readiness: Readiness) -> Self {
+        Self {
+            readiness,
+            registered: None,
+        }
+    }
+
+    /// Registers the task of `waker` to be woken up when `fd` is ready.
+    ///
+    /// # Returns
+    ///
+    /// An error if the executor cannot wait for `fd`, which the future should complete
+    /// with rather than wait forever.
+    pub fn register(&mut self, fd: ft::Fd, waker: &Waker) -> ft::Result<()> {
+        match self.readiness {
+            Readiness::Read => EXECUTOR.wake_me_up_on_read(fd, waker.clone())?,
+            Readiness::Write => EXECUTOR.wake_me_up_on_write(fd, waker.clone())?,
+        }
+        self.registered = Some((fd, waker.clone()));
+        Ok(())
+    }
+
+    /// Returns whether a waker has been registered.
+    #[inline]
+    pub fn is_registered(&self) -> bool {
+        self.registered.is_some()
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        // The waker is already gone if the file descriptor became ready, in which case
+        // this does nothing.
+        if let Some((fd, waker)) = self.registered.take() {
+            match self.readiness {
+                Readiness::Read => EXECUTOR.forget_read(fd, &waker),
+                Readiness::Write => EXECUTOR.forget_write(fd, &waker),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use core::task::Waker;
+
+    use super::*;
+
+    #[test]
+    fn registering_again_replaces_the_waker() {
+        let fd = ft::Fd::from_raw(1000);
+        let mut registration = Registration::new(Readiness::Read);
+        assert!(!registration.is_registered());
+
+        registration.register(fd, Waker::noop()).unwrap();
+        registration.register(fd, Waker::noop()).unwrap();
+        assert!(registration.is_registered());
+        assert_eq!(EXECUTOR.waiting_for(fd), (1, 0));
+
+        drop(registration);
+        assert_eq!(EXECUTOR.waiting_for(fd), (0, 0));
+    }
+
+    #[test]
+    fn dropping_forgets_the_waker() {
+        let fd = ft::Fd::from_raw(1001);
+        let mut registration = Registration::new(Readiness::Write);
+        registration.register(fd, Waker::noop()).unwrap();
+        assert_eq!(EXECUTOR.waiting_for(fd), (0, 1));
+
+        drop(registration);
+        assert_eq!(EXECUTOR.waiting_for(fd), (0, 0));
+    }
+
+    #[test]
+    fn refused_file_descriptors_are_not_registered() {
+        let mut registration = Registration::new(Readiness::Read);
+        assert_eq!(
+            registration.register(ft::Fd::from_raw(-1), Waker::noop()),
+            Err(ft::Errno::INVAL)
+        );
+        assert!(!registration.is_registered());
+    }
+}
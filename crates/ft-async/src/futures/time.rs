@@ -1,6 +1,7 @@
 use core::future::Future;
 use core::pin::Pin;
 use core::task::{Context, Poll};
+use core::time::Duration;
 
 use crate::EXECUTOR;
 
@@ -9,6 +10,11 @@ pub fn sleep(alarm: ft::Instant) -> Sleep {
     Sleep { alarm: Some(alarm) }
 }
 
+/// A future that completes once `duration` has elapsed.
+pub fn sleep_for(duration: Duration) -> Sleep {
+    sleep(ft::Clock::MONOTONIC.get() + duration)
+}
+
 /// See [`sleep`].
 #[must_use = "futures do nothing unless you `.await` or poll them"]
 pub struct Sleep {
@@ -28,3 +34,149 @@ impl Future for Sleep {
         }
     }
 }
+
+/// Creates an [`Interval`] ticking every `period`, starting right away.
+#[must_use]
+pub fn interval(period: Duration) -> Interval {
+    Interval {
+        start: ft::Clock::MONOTONIC.get(),
+        previous: None,
+        period,
+    }
+}
+
+/// A timer ticking at a fixed period. See [`interval`].
+///
+/// Every tick is due one period after the previous one was due, rather than after it was
+/// observed: a task that is woken up late does not push the following ticks back.
+pub struct Interval {
+    /// When the first tick is due.
+    start: ft::Instant,
+    /// When the last tick was due, if any.
+    previous: Option<ft::Instant>,
+    /// The time between two ticks.
+    period: Duration,
+}
+
+impl Interval {
+    /// Returns a future that completes when the next tick is due, with the instant at
+    /// which it was due.
+    pub fn tick(&mut self) -> Tick {
+        let due = self.next_due();
+        self.previous = Some(due);
+        Tick {
+            due,
+            sleep: sleep(due),
+        }
+    }
+
+    /// Returns the time between two ticks.
+    #[must_use]
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Changes the time between two ticks, starting with the next one.
+    pub fn set_period(&mut self, period: Duration) {
+        self.period = period;
+    }
+
+    /// Skips the ticks that are due more than a whole period before `now`.
+    ///
+    /// # Returns
+    ///
+    /// The number of ticks skipped.
+    pub fn skip_missed(&mut self, now: ft::Instant) -> u32 {
+        let Some(previous) = self.previous else {
+            return 0;
+        };
+        let next = previous + self.period;
+        if now <= next || self.period.is_zero() {
+            return 0;
+        }
+
+        let skipped = (now.saturating_sub(next).as_secs_f64() / self.period.as_secs_f64()) as u32;
+        self.previous = Some(previous + self.period * skipped);
+        skipped
+    }
+
+    /// Returns when the next tick is due.
+    fn next_due(&self) -> ft::Instant {
+        match self.previous {
+            Some(previous) => previous + self.period,
+            None => self.start,
+        }
+    }
+}
+
+/// See [`Interval::tick`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Tick {
+    /// When the tick is due.
+    due: ft::Instant,
+    /// The sleep until then.
+    sleep: Sleep,
+}
+
+impl Future for Tick {
+    type Output = ft::Instant;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let due = self.due;
+        Pin::new(&mut self.sleep).poll(cx).map(|()| due)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERIOD: Duration = Duration::from_millis(10);
+
+    #[test]
+    fn ticks_are_due_one_period_apart() {
+        let mut interval = interval(PERIOD);
+        let start = interval.start;
+
+        assert_eq!(interval.tick().due, start);
+        assert_eq!(interval.tick().due, start + PERIOD);
+        assert_eq!(interval.tick().due, start + PERIOD * 2);
+    }
+
+    #[test]
+    fn new_period_applies_from_the_next_tick() {
+        let mut interval = interval(PERIOD);
+        let start = interval.start;
+        assert_eq!(interval.tick().due, start);
+        assert_eq!(interval.tick().due, start + PERIOD);
+
+        interval.set_period(PERIOD * 3);
+        assert_eq!(interval.period(), PERIOD * 3);
+        assert_eq!(interval.tick().due, start + PERIOD * 4);
+        assert_eq!(interval.tick().due, start + PERIOD * 7);
+    }
+
+    #[test]
+    fn only_whole_missed_periods_are_skipped() {
+        let mut interval = interval(PERIOD);
+        let start = interval.start;
+        assert_eq!(interval.skip_missed(start + PERIOD * 5), 0);
+        assert_eq!(interval.tick().due, start);
+
+        // The next tick is not late yet.
+        assert_eq!(interval.skip_missed(start + PERIOD), 0);
+        // Two periods and a half late, two ticks are skipped.
+        assert_eq!(interval.skip_missed(start + PERIOD * 7 / 2), 2);
+        assert_eq!(interval.tick().due, start + PERIOD * 3);
+        assert_eq!(interval.skip_missed(start + PERIOD * 3), 0);
+    }
+
+    #[test]
+    fn zero_period_never_skips() {
+        let mut interval = interval(Duration::ZERO);
+        let start = interval.start;
+        assert_eq!(interval.tick().due, start);
+        assert_eq!(interval.skip_missed(start + PERIOD), 0);
+        assert_eq!(interval.tick().due, start);
+    }
+}
@@ -0,0 +1,88 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+/// Returns a future that yields back to the executor once before completing.
+///
+/// The current task is immediately re-scheduled, but only after the tasks that are
+/// already ready to be polled. This allows long-running computations to let other tasks
+/// make progress.
+pub fn yield_now() -> YieldNow {
+    YieldNow { yielded: false }
+}
+
+/// See [`yield_now`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct YieldNow {
+    /// Whether the future already yielded once.
+    yielded: bool,
+}
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        if self.yielded {
+            Poll::Ready(())
+        } else {
+            self.yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// A poll budget, limiting the amount of work a task performs before yielding back to
+/// the executor.
+///
+/// The executor cannot preempt a task: a task that does not reach an `.await` point
+/// delays every other task, including the one simulating ticks. Tasks performing work
+/// whose size depends on user input (such as dumping the whole map) should spend a unit
+/// of budget for each step of that work.
+///
+/// # Examples
+///
+/// ```ignore
+/// let mut budget = Budget::new(Budget::DEFAULT);
+/// for cell in cells {
+///     write_cell(&mut buf, cell);
+///     budget.spend().await;
+/// }
+/// ```
+pub struct Budget {
+    /// The number of units available each time the task is polled.
+    per_poll: u32,
+    /// The number of units remaining before the task yields.
+    remaining: u32,
+}
+
+impl Budget {
+    /// A reasonable number of units for cheap steps, such as formatting a line.
+    pub const DEFAULT: u32 = 256;
+
+    /// Creates a new [`Budget`] allowing `per_poll` units of work between two yields.
+    #[must_use]
+    pub fn new(per_poll: u32) -> Self {
+        Self {
+            per_poll,
+            remaining: per_poll,
+        }
+    }
+
+    /// Spends a unit of budget.
+    ///
+    /// The returned future yields back to the executor when the budget is exhausted, and
+    /// completes immediately otherwise.
+    pub fn spend(&mut self) -> YieldNow {
+        match self.remaining.checked_sub(1) {
+            Some(remaining) if remaining > 0 => {
+                self.remaining = remaining;
+                YieldNow { yielded: true }
+            }
+            _ => {
+                self.remaining = self.per_poll;
+                yield_now()
+            }
+        }
+    }
+}
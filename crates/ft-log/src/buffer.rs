@@ -0,0 +1,147 @@
+use core::fmt::{self, Write};
+use core::sync::atomic::AtomicU64;
+use core::sync::atomic::Ordering::Relaxed;
+
+/// The number of bytes the log buffer can hold.
+pub const BUFFER_SIZE: usize = 16 * 1024;
+
+/// The maximum number of bytes written to the standard output at once by [`flush`].
+///
+/// Writing at most this many bytes to a pipe that was reported writable never blocks.
+const MAX_WRITE: usize = 4096;
+
+/// A ring buffer holding the messages waiting to be written to the standard output.
+struct RingBuffer {
+    /// The bytes of the buffer.
+    bytes: [u8; BUFFER_SIZE],
+    /// The index of the first byte waiting to be written.
+    start: usize,
+    /// The number of bytes waiting to be written.
+    len: usize,
+}
+
+impl RingBuffer {
+    /// Creates an empty [`RingBuffer`].
+    const fn new() -> Self {
+        Self {
+            bytes: [0; BUFFER_SIZE],
+            start: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the bytes waiting to be written that are contiguous in memory.
+    fn first_chunk(&self) -> &[u8] {
+        let end = usize::min(self.start + self.len, BUFFER_SIZE);
+        &self.bytes[self.start..end]
+    }
+
+    /// Forgets the first `count` bytes waiting to be written.
+    fn consume(&mut self, count: usize) {
+        self.start = (self.start + count) % BUFFER_SIZE;
+        self.len -= count;
+    }
+}
+
+impl Write for RingBuffer {
+    /// Appends `s` to the buffer, failing if there is not enough room for all of it.
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if s.len() > BUFFER_SIZE - self.len {
+            return Err(fmt::Error);
+        }
+
+        for &byte in s.as_bytes() {
+            self.bytes[(self.start + self.len) % BUFFER_SIZE] = byte;
+            self.len += 1;
+        }
+        Ok(())
+    }
+}
+
+/// The messages waiting to be written to the standard output.
+static BUFFER: ft::Mutex<RingBuffer, ft::sync::mutex::NoBlockMutex> =
+    ft::Mutex::new(RingBuffer::new());
+
+/// The number of messages dropped because the buffer was full.
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// Appends a formatted message to the buffer.
+///
+/// The message is dropped as a whole when it does not fit, or when the buffer is already
+/// being used (for example because formatting the message logged another one).
+pub fn push(args: fmt::Arguments) {
+    let Some(mut buffer) = BUFFER.try_lock() else {
+        DROPPED.fetch_add(1, Relaxed);
+        return;
+    };
+
+    let len = buffer.len;
+    if buffer.write_fmt(args).is_err() {
+        buffer.len = len;
+        DROPPED.fetch_add(1, Relaxed);
+    }
+}
+
+/// Returns whether some buffered messages are waiting to be written.
+#[must_use]
+pub fn has_pending() -> bool {
+    BUFFER.lock().len != 0
+}
+
+/// Returns the number of messages dropped so far because the log buffer was full.
+#[must_use]
+pub fn dropped_messages() -> u64 {
+    DROPPED.load(Relaxed)
+}
+
+/// Writes some of the buffered messages to the standard output.
+///
+/// A single `write` of at most 4 KiB is performed, so this does not block once the
+/// standard output is known to be writable. Bytes that could not be written are kept
+/// for the next call.
+///
+/// # Returns
+///
+/// The number of bytes written. When the standard output cannot be written to anymore,
+/// the buffered messages are discarded and the error is returned.
+pub fn flush() -> ft::Result<usize> {
+    let mut buffer = BUFFER.lock();
+
+    let chunk = buffer.first_chunk();
+    let chunk = &chunk[..usize::min(chunk.len(), MAX_WRITE)];
+    if chunk.is_empty() {
+        return Ok(0);
+    }
+
+    match ft::Fd::STDOUT.write(chunk) {
+        Ok(written) => {
+            buffer.consume(written);
+            Ok(written)
+        }
+        Err(ft::Errno::AGAIN | ft::Errno::INTR) => Ok(0),
+        Err(err) => {
+            let len = buffer.len;
+            buffer.consume(len);
+            Err(err)
+        }
+    }
+}
+
+/// Writes all the buffered messages to the standard output, blocking if needed.
+///
+/// This is meant to be called once the executor stopped, before exiting.
+pub fn flush_all() -> ft::Result<()> {
+    let mut buffer = BUFFER.lock();
+
+    while buffer.len != 0 {
+        let chunk = buffer.first_chunk();
+        let len = chunk.len();
+        if let Err(err) = ft::Fd::STDOUT.write_all(chunk) {
+            let len = buffer.len;
+            buffer.consume(len);
+            return Err(err);
+        }
+        buffer.consume(len);
+    }
+    Ok(())
+}
@@ -5,8 +5,12 @@
 #![warn(missing_docs, clippy::must_use_candidate)]
 
 use core::fmt::Arguments;
-use core::sync::atomic::AtomicU8;
 use core::sync::atomic::Ordering::Relaxed;
+use core::sync::atomic::{AtomicBool, AtomicU8};
+
+mod buffer;
+
+pub use self::buffer::{dropped_messages, flush, flush_all, has_pending, BUFFER_SIZE};
 
 /// A logging verbosity level.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -58,10 +62,110 @@ impl VerbosityFilter {
     }
 }
 
+/// The maximum number of modules that may have their own verbosity level.
+pub const MAX_MODULE_FILTERS: usize = 16;
+
+/// A module and the minimum verbosity level of the messages it may log.
+type ModuleLevel = (&'static str, Verbosity);
+
+/// Per-module verbosity levels, applied on top of [`VERBOSITY`].
+pub struct ModuleFilters {
+    /// The highest level set for any module, or `0` when none has its own level.
+    ///
+    /// Messages at or above this level are allowed without taking the lock, which is
+    /// always the case when the list is empty.
+    strictest: AtomicU8,
+    /// The modules that have their own level.
+    levels: ft::Mutex<[Option<ModuleLevel>; MAX_MODULE_FILTERS], ft::sync::mutex::NoBlockMutex>,
+}
+
+impl ModuleFilters {
+    /// Creates a new [`ModuleFilters`] that does not filter any module.
+    const fn new() -> Self {
+        Self {
+            strictest: AtomicU8::new(0),
+            levels: ft::Mutex::new([None; MAX_MODULE_FILTERS]),
+        }
+    }
+
+    /// Sets the minimum verbosity level of the messages logged by `module` and its
+    /// submodules, such as `"server::gfx_connection"`.
+    ///
+    /// When several filters apply to a module, the most specific one wins.
+    ///
+    /// # Returns
+    ///
+    /// `false` if [`MAX_MODULE_FILTERS`] modules already have their own level.
+    pub fn set(&self, module: &'static str, level: Verbosity) -> bool {
+        let mut levels = self.levels.lock();
+
+        let slot = match levels
+            .iter()
+            .position(|e| e.is_some_and(|(m, _)| m == module))
+        {
+            Some(index) => index,
+            None => match levels.iter().position(Option::is_none) {
+                Some(index) => index,
+                None => return false,
+            },
+        };
+
+        levels[slot] = Some((module, level));
+        self.strictest.store(strictest(&*levels), Relaxed);
+        true
+    }
+
+    /// Removes the level of `module`, set with [`ModuleFilters::set`].
+    pub fn clear(&self, module: &str) {
+        let mut levels = self.levels.lock();
+        for entry in levels.iter_mut() {
+            if entry.is_some_and(|(m, _)| m == module) {
+                *entry = None;
+            }
+        }
+        self.strictest.store(strictest(&*levels), Relaxed);
+    }
+
+    /// Returns whether `module` may log messages at the provided verbosity level.
+    pub fn allows(&self, module: &str, verbosity: Verbosity) -> bool {
+        if verbosity as u8 >= self.strictest.load(Relaxed) {
+            return true;
+        }
+
+        self.levels
+            .lock()
+            .iter()
+            .flatten()
+            .filter(|(m, _)| is_within(module, m))
+            .max_by_key(|(m, _)| m.len())
+            .is_none_or(|&(_, level)| verbosity >= level)
+    }
+}
+
+/// Returns the highest of the provided levels, or `0` when there are none.
+fn strictest(levels: &[Option<ModuleLevel>]) -> u8 {
+    levels
+        .iter()
+        .flatten()
+        .map(|&(_, level)| level as u8)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Returns whether `module` is `parent` or one of its submodules.
+fn is_within(module: &str, parent: &str) -> bool {
+    match module.strip_prefix(parent) {
+        Some(rest) => rest.is_empty() || rest.starts_with("::"),
+        None => false,
+    }
+}
+
 /// A message to log to the console.
 pub struct Message<'a> {
     /// The verbosity level of the message to log.
     pub verbosity: Verbosity,
+    /// The path of the module that logged the message.
+    pub module: &'static str,
     /// The message to write.
     pub message: Arguments<'a>,
 }
@@ -75,6 +179,7 @@ macro_rules! message {
     ($v:expr, $($args:tt)*) => {
         $crate::Message {
             verbosity: $v,
+            module: ::core::module_path!(),
             message: ::core::format_args!($($args)*),
         }
     };
@@ -83,10 +188,39 @@ macro_rules! message {
 /// The verbosity level filter for all messages.
 pub static VERBOSITY: VerbosityFilter = VerbosityFilter::new();
 
+/// The per-module verbosity levels.
+pub static MODULE_FILTERS: ModuleFilters = ModuleFilters::new();
+
+/// Whether messages are buffered rather than written right away, see [`set_buffered`].
+static BUFFERED: AtomicBool = AtomicBool::new(false);
+
+/// Sets whether messages are buffered rather than written to the standard output right
+/// away.
+///
+/// Writing to the standard output blocks when it is a pipe that nobody reads fast enough.
+/// Buffered messages are kept in a buffer of [`BUFFER_SIZE`] bytes until [`flush`] is
+/// called, and are dropped when it is full, so that logging never blocks. The number of
+/// dropped messages is returned by [`dropped_messages`].
+#[inline]
+pub fn set_buffered(yes: bool) {
+    BUFFERED.store(yes, Relaxed);
+}
+
+/// Sets the minimum verbosity level of the messages logged by `module` and its
+/// submodules.
+///
+/// See [`ModuleFilters::set`].
+#[inline]
+pub fn set_module_level(module: &'static str, level: Verbosity) -> bool {
+    MODULE_FILTERS.set(module, level)
+}
+
 /// Logs the provided message.
 #[inline]
 pub fn log(message: &Message) {
-    if VERBOSITY.contains(message.verbosity) {
+    if VERBOSITY.contains(message.verbosity)
+        && MODULE_FILTERS.allows(message.module, message.verbosity)
+    {
         log_unchecked(message);
     }
 }
@@ -94,7 +228,9 @@ pub fn log(message: &Message) {
 /// Logs the provided message without checking whether the global verbosity level
 /// allows it.
 fn log_unchecked(message: &Message) {
-    let Message { message, verbosity } = message;
+    let Message {
+        message, verbosity, ..
+    } = message;
 
     let (prefix, suffix) = match verbosity {
         Verbosity::Trace => ("   \x1B[1;2mtrace\x1B[0m\x1B[2m  ", "\x1B[0m"),
@@ -104,7 +240,11 @@ fn log_unchecked(message: &Message) {
         Verbosity::Error => ("   \x1B[1;31merror\x1B[0m\x1B[91m  ", "\x1B[0m"),
     };
 
-    ft::printf!("{prefix}{message}{suffix}\n");
+    if BUFFERED.load(Relaxed) {
+        buffer::push(format_args!("{prefix}{message}{suffix}\n"));
+    } else {
+        ft::printf!("{prefix}{message}{suffix}\n");
+    }
 }
 
 /// Logs a message with the [`Trace`] verbosity level.
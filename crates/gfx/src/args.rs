@@ -0,0 +1,100 @@
+/// Module for the command line arguments.
+use std::fmt::Display;
+
+use clap::Parser;
+
+use crate::heatmap::Resource;
+use crate::i18n::Lang;
+
+/// A Zappy graphical monitor.
+#[derive(Parser, Debug)]
+#[clap(disable_help_flag = true, arg_required_else_help = true)]
+pub struct Args {
+    /// The port of the server
+    #[clap(short, required_unless_present = "servers", conflicts_with = "servers")]
+    pub port: Option<u16>,
+    /// The hostname of the server
+    #[clap(short, default_value = "localhost")]
+    pub host: String,
+    /// A server to observe, as `host:port`. Can be repeated to observe several servers at
+    /// once, in which case every line is prefixed with the server it comes from
+    #[clap(long = "server", value_parser = parse_endpoint)]
+    pub servers: Vec<Endpoint>,
+    /// The language of the texts shown by the monitor
+    #[clap(long, value_enum, default_value_t = Lang::En)]
+    pub lang: Lang,
+    /// Show the food and the stones of the players at a glance under every `pin` message
+    #[clap(long)]
+    pub bars: bool,
+    /// Ask the server for the number of commands queued by every player, and show it as a
+    /// stack whenever it changes
+    #[clap(long)]
+    pub queues: bool,
+    /// Show the last positions of a player, up to the provided number, whenever it moves.
+    /// Trails are colored by team when the output is a terminal
+    #[clap(long, value_name = "LENGTH")]
+    pub trails: Option<usize>,
+    /// Show the number of eggs of every team whenever it changes, colored by team when the
+    /// output is a terminal
+    #[clap(long)]
+    pub eggs: bool,
+    /// Draw how the provided resource is spread over the map, every few seconds, once the
+    /// map has been received
+    #[clap(long, value_enum)]
+    pub heatmap: Option<Resource>,
+    /// The width of the terminal, in characters. Maps wider than that are scaled down to
+    /// fit. Defaults to the `COLUMNS` environment variable, or 80
+    #[clap(long)]
+    pub columns: Option<usize>,
+    /// Report the number of messages received and the time spent displaying them, every
+    /// second
+    #[clap(long)]
+    pub stats: bool,
+    /// The number of times to try connecting to a server again after failing to connect or
+    /// losing the connection, waiting twice as long each time. Unlimited by default
+    #[clap(long, value_name = "COUNT")]
+    pub retries: Option<u32>,
+}
+
+impl Args {
+    /// Returns the servers to observe, in the order they were given.
+    pub fn endpoints(&self) -> Vec<Endpoint> {
+        self.port
+            .map(|port| Endpoint {
+                host: self.host.clone(),
+                port,
+            })
+            .into_iter()
+            .chain(self.servers.iter().cloned())
+            .collect()
+    }
+}
+
+/// The address of a server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Endpoint {
+    /// The hostname of the server.
+    pub host: String,
+    /// The port of the server.
+    pub port: u16,
+}
+
+impl Display for Endpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+/// Parses an [`Endpoint`] written as `host:port`.
+pub fn parse_endpoint(s: &str) -> Result<Endpoint, String> {
+    let (host, port) = s
+        .rsplit_once(':')
+        .ok_or_else(|| format!("expected `host:port`, got `{}`", s))?;
+    let port = port
+        .parse()
+        .map_err(|err| format!("invalid port: {}", err))?;
+    Ok(Endpoint {
+        host: host.to_string(),
+        port,
+    })
+}
@@ -0,0 +1,49 @@
+/// Module for the inventory bars shown under the `pin` messages with `--bars`.
+use std::fmt::Display;
+
+use resources::ObjectClass;
+
+use crate::i18n::{Lang, Text};
+use crate::server::messages::PlayerInventory;
+
+/// The amount of food shown as a full bar, which is what players spawn with.
+const FULL_FOOD: u32 = 10;
+
+/// The width of the food bar, in characters.
+const BAR_WIDTH: u32 = 10;
+
+/// The letters standing for the stones, from linemate to thystame.
+const STONE_LETTERS: [char; 6] = ['L', 'D', 'S', 'M', 'P', 'T'];
+
+/// The inventory of a player at a glance: a bar showing how much food they have left,
+/// followed by the stones they carry.
+///
+/// For instance, `#3 food [######----] 6 L2 S1`.
+#[derive(Debug)]
+pub struct InventoryBar<'a> {
+    /// The inventory to show.
+    pub inventory: &'a PlayerInventory,
+    /// The language of the bar.
+    pub lang: Lang,
+}
+
+impl Display for InventoryBar<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let food = self.inventory.resources[ObjectClass::Food];
+        let filled = food.min(FULL_FOOD) * BAR_WIDTH / FULL_FOOD;
+
+        write!(f, "#{} {} [", self.inventory.id, Text::Food.get(self.lang))?;
+        for i in 0..BAR_WIDTH {
+            f.write_str(if i < filled { "#" } else { "-" })?;
+        }
+        write!(f, "] {}", food)?;
+
+        for (letter, stone) in STONE_LETTERS.iter().zip(ObjectClass::STONES) {
+            let count = self.inventory.resources[stone];
+            if count != 0 {
+                write!(f, " {}{}", letter, count)?;
+            }
+        }
+        Ok(())
+    }
+}
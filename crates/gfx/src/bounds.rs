@@ -0,0 +1,166 @@
+/// Module for checking the messages of the server against the size of the map.
+use std::collections::HashSet;
+
+use crate::i18n::{Lang, Text};
+use crate::server::messages::ServerMessage;
+
+/// The largest width or height of the map the monitor accepts.
+///
+/// The heatmap keeps one counter per cell, which a bogus `msz` would otherwise make
+/// arbitrarily large.
+const MAX_MAP_SIDE: u32 = 4096;
+
+/// The maximum number of players the monitor keeps track of at once.
+///
+/// `pnw` messages announcing more players are ignored.
+const MAX_PLAYERS: usize = 4096;
+
+/// Something invalid in a message of the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Anomaly {
+    /// The width or height of the map is zero or larger than [`MAX_MAP_SIDE`].
+    MapSize,
+    /// A position is out of the map.
+    OutOfMap,
+    /// An orientation is not between 1 and 4.
+    Orientation,
+    /// A player was announced while [`MAX_PLAYERS`] players are known.
+    TooManyPlayers,
+}
+
+impl Anomaly {
+    /// Returns the text describing the anomaly and how it was handled.
+    fn text(self) -> Text {
+        match self {
+            Anomaly::MapSize => Text::MapSizeClamped,
+            Anomaly::OutOfMap => Text::OutOfMap,
+            Anomaly::Orientation => Text::InvalidOrientation,
+            Anomaly::TooManyPlayers => Text::TooManyPlayers,
+        }
+    }
+}
+
+/// Checks the coordinates, orientations and players sent by a server, so that a buggy or
+/// malicious server cannot place entities out of the map or make the monitor track an
+/// unbounded number of them.
+///
+/// Positions are wrapped around the map, as players do when they walk off an edge.
+/// Every kind of anomaly is reported once per connection, on the error output.
+#[derive(Debug)]
+pub struct Bounds {
+    /// The size of the map, once received with `msz`.
+    map: Option<(u32, u32)>,
+    /// The IDs of the players announced and still alive.
+    players: HashSet<u64>,
+    /// The anomalies already reported.
+    reported: Vec<Anomaly>,
+    /// The language of the warnings.
+    lang: Lang,
+}
+
+impl Bounds {
+    /// Starts checking the messages of a new connection.
+    pub fn new(lang: Lang) -> Self {
+        Self {
+            map: None,
+            players: HashSet::new(),
+            reported: Vec::new(),
+            lang,
+        }
+    }
+
+    /// Checks a message received from the server, correcting it in place.
+    ///
+    /// `label` is written before the warning, if any.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the message must be ignored.
+    pub fn check(&mut self, msg: &mut ServerMessage, label: &str) -> bool {
+        let (anomaly, keep) = self.fix(msg);
+        if let Some(anomaly) = anomaly {
+            self.report(anomaly, msg.tag(), label);
+        }
+        keep
+    }
+
+    /// Corrects a message, returning the anomaly found, if any, and whether the message
+    /// must be kept.
+    fn fix(&mut self, msg: &mut ServerMessage) -> (Option<Anomaly>, bool) {
+        let mut anomaly = None;
+        match msg {
+            ServerMessage::MapSize(width, height) => {
+                for side in [&mut *width, &mut *height] {
+                    if !(1..=MAX_MAP_SIDE).contains(side) {
+                        *side = (*side).clamp(1, MAX_MAP_SIDE);
+                        anomaly = Some(Anomaly::MapSize);
+                    }
+                }
+                self.map = Some((*width, *height));
+            }
+            ServerMessage::TileContent(tile) => anomaly = self.wrap(&mut tile.x, &mut tile.y),
+            ServerMessage::PlayerNew(player) => {
+                if !self.players.contains(&player.id) && self.players.len() >= MAX_PLAYERS {
+                    return (Some(Anomaly::TooManyPlayers), false);
+                }
+                self.players.insert(player.id);
+                anomaly = self
+                    .wrap(&mut player.x, &mut player.y)
+                    .or(clamp_orientation(&mut player.orientation));
+            }
+            ServerMessage::PlayerDeath(id) => {
+                self.players.remove(id);
+            }
+            ServerMessage::PlayerPosition(pos) => {
+                anomaly = self
+                    .wrap(&mut pos.x, &mut pos.y)
+                    .or(clamp_orientation(&mut pos.orientation));
+            }
+            ServerMessage::PlayerInventory(inv) => anomaly = self.wrap(&mut inv.x, &mut inv.y),
+            ServerMessage::IncantationStart(start) => {
+                anomaly = self.wrap(&mut start.x, &mut start.y);
+            }
+            ServerMessage::IncantationEnd(x, y, _) => anomaly = self.wrap(x, y),
+            ServerMessage::EggNew(egg) => anomaly = self.wrap(&mut egg.x, &mut egg.y),
+            _ => (),
+        }
+        (anomaly, true)
+    }
+
+    /// Wraps a position around the map, if its size is known.
+    fn wrap(&self, x: &mut u32, y: &mut u32) -> Option<Anomaly> {
+        let (width, height) = self.map?;
+        if *x < width && *y < height {
+            return None;
+        }
+        *x %= width;
+        *y %= height;
+        Some(Anomaly::OutOfMap)
+    }
+
+    /// Shows a warning about an anomaly found in a message with the provided tag, unless
+    /// one was already shown for this kind of anomaly.
+    fn report(&mut self, anomaly: Anomaly, tag: &str, label: &str) {
+        if self.reported.contains(&anomaly) {
+            return;
+        }
+        self.reported.push(anomaly);
+        eprintln!(
+            "{}{} ({}): {} ({})",
+            label,
+            Text::Warning.get(self.lang),
+            tag,
+            anomaly.text().get(self.lang),
+            Text::NotShownAgain.get(self.lang),
+        );
+    }
+}
+
+/// Clamps an orientation between 1 (north) and 4 (west).
+fn clamp_orientation(orientation: &mut u8) -> Option<Anomaly> {
+    if (1..=4).contains(orientation) {
+        return None;
+    }
+    *orientation = (*orientation).clamp(1, 4);
+    Some(Anomaly::Orientation)
+}
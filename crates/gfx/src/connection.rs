@@ -0,0 +1,127 @@
+/// Module for the connection to a server, re-established with an exponential backoff.
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::args::Endpoint;
+use crate::server::{Requests, Result};
+
+/// The time to wait before the first attempt to reconnect.
+const INITIAL_DELAY: Duration = Duration::from_secs(1);
+
+/// The maximum time to wait between two attempts to connect.
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// The time to wait between attempts to connect to a server, doubled after every failure.
+#[derive(Debug)]
+pub struct Backoff {
+    /// The time to wait before the next attempt.
+    delay: Duration,
+    /// The number of failed attempts since the last successful connection.
+    attempts: u32,
+}
+
+impl Backoff {
+    /// Creates a new [`Backoff`], for a server that was never connected to.
+    pub fn new() -> Self {
+        Self {
+            delay: INITIAL_DELAY,
+            attempts: 0,
+        }
+    }
+
+    /// Returns the time to wait before the next attempt, and doubles it for the one after.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.delay;
+        self.delay = (self.delay * 2).min(MAX_DELAY);
+        self.attempts += 1;
+        delay
+    }
+
+    /// Returns the number of failed attempts since the last successful connection.
+    pub fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    /// Starts over, after a successful connection.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// The state of the connection to a server shared with the thread reading the standard
+/// input, which sends the requests of the user and may change the address of the server.
+#[derive(Debug, Clone)]
+pub struct Link(Arc<Mutex<LinkState>>);
+
+/// See [`Link`].
+#[derive(Debug)]
+struct LinkState {
+    /// The address of the server.
+    endpoint: Endpoint,
+    /// The handle used to send requests, while connected.
+    requests: Option<Requests>,
+    /// Whether the address changed since the last connection attempt.
+    moved: bool,
+}
+
+impl Link {
+    /// Creates the link to the server at `endpoint`, not connected yet.
+    pub fn new(endpoint: Endpoint) -> Self {
+        Self(Arc::new(Mutex::new(LinkState {
+            endpoint,
+            requests: None,
+            moved: false,
+        })))
+    }
+
+    /// Locks the shared state.
+    fn lock(&self) -> MutexGuard<'_, LinkState> {
+        self.0.lock().unwrap_or_else(|err| err.into_inner())
+    }
+
+    /// Returns the address of the server.
+    pub fn endpoint(&self) -> Endpoint {
+        self.lock().endpoint.clone()
+    }
+
+    /// Changes the address of the server.
+    ///
+    /// The current connection, if any, is closed so that the monitor connects to the new
+    /// address right away.
+    pub fn set_endpoint(&self, endpoint: Endpoint) {
+        let mut state = self.lock();
+        state.endpoint = endpoint;
+        state.moved = true;
+        if let Some(requests) = state.requests.take() {
+            requests.close();
+        }
+    }
+
+    /// Returns whether the address changed since the last call.
+    pub fn take_moved(&self) -> bool {
+        std::mem::take(&mut self.lock().moved)
+    }
+
+    /// Records that the monitor is connected: requests are sent with `requests` from now
+    /// on.
+    pub fn connected(&self, requests: Requests) {
+        self.lock().requests = Some(requests);
+    }
+
+    /// Records that the monitor is not connected anymore.
+    pub fn disconnected(&self) {
+        self.lock().requests = None;
+    }
+
+    /// Sends a request to the server.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the monitor is not connected to the server.
+    pub fn send(&self, request: &str) -> Result<bool> {
+        match &mut self.lock().requests {
+            Some(requests) => requests.send(request).map(|()| true),
+            None => Ok(false),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+/// Module for the eggs laid by every team, counted and colored by the team of the player
+/// that laid them.
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::i18n::{Lang, Text};
+use crate::server::messages::ServerMessage;
+use crate::trails::{team_index, TEAM_COLORS};
+
+/// The maximum number of eggs kept track of at once.
+///
+/// `enw` messages announcing more eggs are not counted.
+const MAX_EGGS: usize = 65536;
+
+/// Keeps track of the eggs that have not hatched yet, from the `enw`, `eht` and `edi`
+/// messages, and of the team of every player, from the `tna`, `pnw` and `pdi` ones.
+#[derive(Debug)]
+pub struct Eggs {
+    /// Whether the scoreboard is colored by team.
+    colored: bool,
+    /// The names of the teams seen so far, in the order of the `tna` messages.
+    teams: Vec<String>,
+    /// The team of every player, by ID, as an index in `teams`.
+    players: HashMap<u64, usize>,
+    /// The team of every egg that has not hatched yet, by ID, or `None` if the player
+    /// that laid it was not known.
+    eggs: HashMap<u64, Option<usize>>,
+}
+
+impl Eggs {
+    /// Starts keeping track of the eggs.
+    pub fn new(colored: bool) -> Self {
+        Self {
+            colored,
+            teams: Vec::new(),
+            players: HashMap::new(),
+            eggs: HashMap::new(),
+        }
+    }
+
+    /// Records a message received from the server.
+    ///
+    /// # Returns
+    ///
+    /// The number of eggs of every team, if it changed.
+    pub fn record(&mut self, msg: &ServerMessage, lang: Lang) -> Option<Scoreboard<'_>> {
+        match msg {
+            ServerMessage::TeamName(name) => {
+                team_index(&mut self.teams, name);
+                return None;
+            }
+            ServerMessage::PlayerNew(player) => {
+                let team = team_index(&mut self.teams, &player.team);
+                self.players.insert(player.id, team);
+                return None;
+            }
+            ServerMessage::PlayerDeath(id) => {
+                self.players.remove(id);
+                return None;
+            }
+            ServerMessage::EggNew(egg) => {
+                if self.eggs.len() >= MAX_EGGS {
+                    return None;
+                }
+                let team = self.players.get(&egg.parent).copied();
+                self.eggs.insert(egg.id, team);
+            }
+            ServerMessage::EggHatched(id) | ServerMessage::EggDeath(id) => {
+                self.eggs.remove(id)?;
+            }
+            _ => return None,
+        }
+
+        Some(Scoreboard { eggs: self, lang })
+    }
+}
+
+/// The number of eggs of every team, in the color of the team.
+///
+/// For instance, `eggs: red 2, blue 0`.
+#[derive(Debug)]
+pub struct Scoreboard<'a> {
+    /// The eggs to count.
+    eggs: &'a Eggs,
+    /// The language of the scoreboard.
+    lang: Lang,
+}
+
+impl Display for Scoreboard<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut counts = vec![0; self.eggs.teams.len()];
+        let mut unknown = 0;
+        for team in self.eggs.eggs.values() {
+            match team {
+                Some(team) => counts[*team] += 1,
+                None => unknown += 1,
+            }
+        }
+
+        write!(f, "{}:", Text::Eggs.get(self.lang))?;
+        for (i, (name, count)) in self.eggs.teams.iter().zip(counts).enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            if self.eggs.colored {
+                let color = TEAM_COLORS[i % TEAM_COLORS.len()];
+                write!(f, "{}\x1b[{}m{} {}\x1b[0m", separator, color, name, count)?;
+            } else {
+                write!(f, "{}{} {}", separator, name, count)?;
+            }
+        }
+        if unknown != 0 {
+            write!(f, " ({} {})", unknown, Text::UnknownTeam.get(self.lang))?;
+        }
+        Ok(())
+    }
+}
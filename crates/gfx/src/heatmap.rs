@@ -0,0 +1,184 @@
+/// Module for the heatmap showing how a resource is spread over the map.
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use resources::ObjectClass;
+
+use crate::i18n::{Lang, Text};
+use crate::server::messages::ServerMessage;
+
+/// The minimum amount of time between two heatmaps.
+const PERIOD: Duration = Duration::from_secs(5);
+
+/// The characters used to draw the cells, from the emptiest to the densest.
+const SHADES: &[u8] = b" .:-=+*#%@";
+
+/// The width of the terminal assumed when it is neither provided nor found in the
+/// environment.
+const DEFAULT_COLUMNS: usize = 80;
+
+/// Returns the width of the terminal, in characters.
+///
+/// The `COLUMNS` environment variable is read every time, so that a new value exported
+/// by the shell is picked up.
+fn terminal_columns(columns: Option<usize>) -> usize {
+    columns
+        .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+        .unwrap_or(DEFAULT_COLUMNS)
+}
+
+/// A resource that can lie on the ground.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Resource {
+    /// Food.
+    Food,
+    /// Linemate.
+    Linemate,
+    /// Deraumere.
+    Deraumere,
+    /// Sibur.
+    Sibur,
+    /// Mendiane.
+    Mendiane,
+    /// Phiras.
+    Phiras,
+    /// Thystame.
+    Thystame,
+}
+
+impl Resource {
+    /// Returns the class of the objects making up the resource.
+    pub fn class(self) -> ObjectClass {
+        match self {
+            Resource::Food => ObjectClass::Food,
+            Resource::Linemate => ObjectClass::Linemate,
+            Resource::Deraumere => ObjectClass::Deraumere,
+            Resource::Sibur => ObjectClass::Sibur,
+            Resource::Mendiane => ObjectClass::Mendiane,
+            Resource::Phiras => ObjectClass::Phiras,
+            Resource::Thystame => ObjectClass::Thystame,
+        }
+    }
+}
+
+/// Keeps track of the amount of a resource on every cell, from the `bct` messages, and
+/// periodically draws it on the error output.
+#[derive(Debug)]
+pub struct Heatmap {
+    /// The resource shown.
+    resource: Resource,
+    /// The width of the terminal, in characters, if it was provided.
+    columns: Option<usize>,
+    /// The width of the map.
+    width: u32,
+    /// The amount of the resource on every cell, stored row by row.
+    counts: Vec<u32>,
+    /// Whether the amount of the resource changed on a cell since the map was last drawn.
+    changed: bool,
+    /// When the map was last drawn.
+    last_draw: Option<Instant>,
+}
+
+impl Heatmap {
+    /// Starts tracking the provided resource.
+    ///
+    /// The heatmap is scaled down to fit in `columns` characters, or in the width of the
+    /// terminal if `None`.
+    pub fn new(resource: Resource, columns: Option<usize>) -> Self {
+        Self {
+            resource,
+            columns,
+            width: 0,
+            counts: Vec::new(),
+            changed: false,
+            last_draw: None,
+        }
+    }
+
+    /// Records a message received from the server.
+    pub fn record(&mut self, msg: &ServerMessage) {
+        match msg {
+            ServerMessage::MapSize(width, height) => {
+                self.width = *width;
+                self.counts = vec![0; *width as usize * *height as usize];
+                self.changed = true;
+            }
+            // A `bct` may only change other resources, or resend a cell with `mct`: only
+            // an actual change of the resource shown calls for a redraw.
+            ServerMessage::TileContent(tile) if tile.x < self.width => {
+                let index = tile.y as usize * self.width as usize + tile.x as usize;
+                let new = tile.resources[self.resource.class()];
+                if let Some(count) = self.counts.get_mut(index).filter(|count| **count != new) {
+                    *count = new;
+                    self.changed = true;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Returns the amount of the resource on every block of `scale` by `scale` cells,
+    /// stored row by row, along with the number of blocks per row.
+    fn blocks(&self, scale: usize) -> (Vec<u32>, usize) {
+        let width = self.width.max(1) as usize;
+        let height = self.counts.len() / width;
+        let block_width = width.div_ceil(scale);
+        let mut blocks = vec![0; block_width * height.div_ceil(scale)];
+        for (index, &count) in self.counts.iter().enumerate() {
+            let (x, y) = (index % width, index / width);
+            blocks[y / scale * block_width + x / scale] += count;
+        }
+        (blocks, block_width)
+    }
+
+    /// Draws the heatmap on the error output if it changed and the last one is old
+    /// enough.
+    ///
+    /// `label` is written before every line. When the map is wider than the terminal,
+    /// every character stands for a square block of cells, so that the heatmap keeps
+    /// its shape instead of wrapping.
+    pub fn draw_if_due(&mut self, label: &str, lang: Lang) {
+        if !self.changed || self.last_draw.is_some_and(|last| last.elapsed() < PERIOD) {
+            return;
+        }
+        self.changed = false;
+        self.last_draw = Some(Instant::now());
+
+        // The label and the borders take some room too.
+        let available = terminal_columns(self.columns)
+            .saturating_sub(label.chars().count() + 2)
+            .max(1);
+        let scale = (self.width as usize).div_ceil(available).max(1);
+        let (blocks, block_width) = self.blocks(scale);
+
+        let max = blocks.iter().copied().max().unwrap_or(0);
+        let mut text = String::new();
+        let _ = write!(
+            text,
+            "{}{} {} (max {}",
+            label,
+            Text::DensityOf.get(lang),
+            Text::resource(self.resource.class()).get(lang),
+            max
+        );
+        if scale > 1 {
+            let _ = write!(text, ", {0}x{0} {1}", scale, Text::CellsPerChar.get(lang));
+        }
+        text.push_str(")\n");
+        for row in blocks.chunks(block_width) {
+            text.push_str(label);
+            text.push('|');
+            for &count in row {
+                let shade = if max == 0 {
+                    0
+                } else {
+                    (count as usize * (SHADES.len() - 1)).div_ceil(max as usize)
+                };
+                text.push(SHADES[shade] as char);
+            }
+            text.push_str("|\n");
+        }
+        eprint!("{}", text);
+    }
+}
@@ -0,0 +1,287 @@
+/// Module for the translations of the texts shown to the user.
+use clap::ValueEnum;
+use resources::ObjectClass;
+
+/// A language the monitor can be displayed in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Lang {
+    /// English.
+    En,
+    /// French.
+    Fr,
+}
+
+/// A text shown to the user, independent of the language.
+#[derive(Debug, Clone, Copy)]
+pub enum Text {
+    /// The prefix of status lines.
+    Status,
+    /// The monitor is connecting to a server.
+    ConnectingTo,
+    /// The monitor is connected to a server.
+    ConnectedTo,
+    /// The monitor lost its connection to a server.
+    DisconnectedFrom,
+    /// The connection to a server failed or was lost.
+    ConnectionError,
+    /// The time left before trying to connect again.
+    RetryingIn,
+    /// A request could not be sent because the server is not connected.
+    NotConnected,
+    /// The new address of a server is invalid.
+    InvalidAddress,
+    /// The unit of the tick rate.
+    TicksPerSecond,
+    /// The server did not recognize a request.
+    UnknownCommand,
+    /// The server rejected the parameters of a request.
+    BadParameters,
+    /// The requests typed by the user could not be sent.
+    ForwardFailed,
+    /// A request was addressed to a server that is not observed.
+    NoSuchServer,
+    /// The map is being received.
+    LoadingMap,
+    /// The unit of the message throughput.
+    MessagesPerSecond,
+    /// The time spent displaying the messages.
+    DisplayTime,
+    /// An incantation on a tile.
+    Incantation,
+    /// The food held by a player, and the name of the food resource.
+    Food,
+    /// The name of the linemate resource.
+    Linemate,
+    /// The name of the deraumere resource.
+    Deraumere,
+    /// The name of the sibur resource.
+    Sibur,
+    /// The name of the mendiane resource.
+    Mendiane,
+    /// The name of the phiras resource.
+    Phiras,
+    /// The name of the thystame resource.
+    Thystame,
+    /// The commands queued by a player.
+    Queue,
+    /// A player has no command queued.
+    Idle,
+    /// A player cannot queue any more commands.
+    Saturated,
+    /// The title of the heatmap of a resource.
+    DensityOf,
+    /// The number of cells drawn by each character of a heatmap.
+    CellsPerChar,
+    /// The level of the players taking part in an incantation.
+    Level,
+    /// The estimated time before an incantation ends.
+    EndsIn,
+    /// An incantation succeeded.
+    Succeeded,
+    /// An incantation failed.
+    Failed,
+    /// The title of the number of eggs of every team.
+    Eggs,
+    /// Eggs laid by a player whose team is not known.
+    UnknownTeam,
+    /// The prefix of the warnings about invalid messages of the server.
+    Warning,
+    /// The size of the map was out of the supported range and was clamped.
+    MapSizeClamped,
+    /// A position was out of the map and was wrapped around.
+    OutOfMap,
+    /// An orientation was out of range and was clamped.
+    InvalidOrientation,
+    /// A new player was ignored because too many players are known.
+    TooManyPlayers,
+    /// Further occurrences of a warning are not shown.
+    NotShownAgain,
+    /// A line of the server could not be parsed and was skipped.
+    SkippedLine,
+    /// The title of the requests handled by the monitor itself.
+    LocalRequests,
+    /// The title of the requests sent to the servers.
+    ServerRequests,
+    /// The help of the request showing the help.
+    HelpHelp,
+    /// The help of the `:server` request.
+    HelpServer,
+    /// The help of the `@<n>` prefix.
+    HelpTarget,
+    /// The help of the `bct` request.
+    HelpTileContent,
+    /// The help of the `mct` request.
+    HelpMapContent,
+    /// The help of the `tst` request.
+    HelpTeamStats,
+    /// The help of the `ppo` request.
+    HelpPlayerPosition,
+    /// The help of the `plv` request.
+    HelpPlayerLevel,
+    /// The help of the `pin` request.
+    HelpPlayerInventory,
+    /// The help of the `pqc #<n>` request.
+    HelpPlayerQueue,
+    /// The help of the `pqc` request without arguments.
+    HelpFollowQueues,
+    /// The help of the `sst` request.
+    HelpSetTimeUnit,
+    /// The help of the `json` request.
+    HelpJson,
+    /// The help of the `dbg` request.
+    HelpDebug,
+}
+
+impl Text {
+    /// Returns the name of the provided resource.
+    pub fn resource(class: ObjectClass) -> Self {
+        match class {
+            ObjectClass::Food => Self::Food,
+            ObjectClass::Linemate => Self::Linemate,
+            ObjectClass::Deraumere => Self::Deraumere,
+            ObjectClass::Sibur => Self::Sibur,
+            ObjectClass::Mendiane => Self::Mendiane,
+            ObjectClass::Phiras => Self::Phiras,
+            ObjectClass::Thystame => Self::Thystame,
+        }
+    }
+
+    /// Returns the text in the provided language.
+    pub fn get(self, lang: Lang) -> &'static str {
+        use Lang::*;
+        use Text::*;
+        match (self, lang) {
+            (Status, En) => "status",
+            (Status, Fr) => "état",
+            (ConnectingTo, En) => "connecting to",
+            (ConnectingTo, Fr) => "connexion à",
+            (ConnectedTo, En) => "connected to",
+            (ConnectedTo, Fr) => "connecté à",
+            (DisconnectedFrom, En) => "disconnected from",
+            (DisconnectedFrom, Fr) => "déconnecté de",
+            (ConnectionError, En) => "connection error",
+            (ConnectionError, Fr) => "erreur de connexion",
+            (RetryingIn, En) => "retrying in",
+            (RetryingIn, Fr) => "nouvel essai dans",
+            (NotConnected, En) => "not connected",
+            (NotConnected, Fr) => "non connecté",
+            (InvalidAddress, En) => "invalid address",
+            (InvalidAddress, Fr) => "adresse invalide",
+            (TicksPerSecond, En) => "ticks/s",
+            (TicksPerSecond, Fr) => "ticks/s",
+            (UnknownCommand, En) => "unknown command",
+            (UnknownCommand, Fr) => "commande inconnue",
+            (BadParameters, En) => "bad parameters",
+            (BadParameters, Fr) => "paramètres invalides",
+            (ForwardFailed, En) => "failed to forward requests",
+            (ForwardFailed, Fr) => "échec de l'envoi des requêtes",
+            (NoSuchServer, En) => "no such server",
+            (NoSuchServer, Fr) => "serveur inconnu",
+            (Food, En) => "food",
+            (Food, Fr) => "nourriture",
+            (Linemate, En) => "linemate",
+            (Linemate, Fr) => "linemate",
+            (Deraumere, En) => "deraumere",
+            (Deraumere, Fr) => "deraumere",
+            (Sibur, En) => "sibur",
+            (Sibur, Fr) => "sibur",
+            (Mendiane, En) => "mendiane",
+            (Mendiane, Fr) => "mendiane",
+            (Phiras, En) => "phiras",
+            (Phiras, Fr) => "phiras",
+            (Thystame, En) => "thystame",
+            (Thystame, Fr) => "thystame",
+            (Queue, En) => "queue",
+            (Queue, Fr) => "file",
+            (Idle, En) => "idle",
+            (Idle, Fr) => "inactif",
+            (Saturated, En) => "saturated",
+            (Saturated, Fr) => "saturée",
+            (DensityOf, En) => "density of",
+            (DensityOf, Fr) => "densité de",
+            (CellsPerChar, En) => "cells per character",
+            (CellsPerChar, Fr) => "cases par caractère",
+            (LoadingMap, En) => "loading the map",
+            (LoadingMap, Fr) => "chargement de la carte",
+            (MessagesPerSecond, En) => "messages/s",
+            (MessagesPerSecond, Fr) => "messages/s",
+            (DisplayTime, En) => "display",
+            (DisplayTime, Fr) => "affichage",
+            (Incantation, En) => "incantation",
+            (Incantation, Fr) => "incantation",
+            (Level, En) => "level",
+            (Level, Fr) => "niveau",
+            (EndsIn, En) => "ends in",
+            (EndsIn, Fr) => "fin dans",
+            (Succeeded, En) => "succeeded",
+            (Succeeded, Fr) => "réussie",
+            (Failed, En) => "failed",
+            (Failed, Fr) => "échouée",
+            (Eggs, En) => "eggs",
+            (Eggs, Fr) => "œufs",
+            (UnknownTeam, En) => "from an unknown team",
+            (UnknownTeam, Fr) => "d'une équipe inconnue",
+            (Warning, En) => "warning",
+            (Warning, Fr) => "attention",
+            (MapSizeClamped, En) => "map size out of range, clamped",
+            (MapSizeClamped, Fr) => "taille de carte hors limites, ramenée dans les limites",
+            (OutOfMap, En) => "position out of the map, wrapped around",
+            (OutOfMap, Fr) => "position hors de la carte, ramenée sur la carte",
+            (InvalidOrientation, En) => "invalid orientation, clamped",
+            (InvalidOrientation, Fr) => "orientation invalide, ramenée dans les limites",
+            (TooManyPlayers, En) => "too many players, new player ignored",
+            (TooManyPlayers, Fr) => "trop de joueurs, nouveau joueur ignoré",
+            (NotShownAgain, En) => "not shown again",
+            (NotShownAgain, Fr) => "ne sera plus affiché",
+            (SkippedLine, En) => "skipped the unreadable line",
+            (SkippedLine, Fr) => "ligne illisible ignorée :",
+            (LocalRequests, En) => "monitor requests",
+            (LocalRequests, Fr) => "requêtes du moniteur",
+            (ServerRequests, En) => "server requests",
+            (ServerRequests, Fr) => "requêtes du serveur",
+            (HelpHelp, En) => "show this help",
+            (HelpHelp, Fr) => "afficher cette aide",
+            (HelpServer, En) => "observe another server",
+            (HelpServer, Fr) => "observer un autre serveur",
+            (HelpTarget, En) => "send a request to the n-th server only",
+            (HelpTarget, Fr) => "envoyer une requête au n-ième serveur seulement",
+            (HelpTileContent, En) => "content of a tile",
+            (HelpTileContent, Fr) => "contenu d'une case",
+            (HelpMapContent, En) => "content of the whole map",
+            (HelpMapContent, Fr) => "contenu de toute la carte",
+            (HelpTeamStats, En) => "statistics of a team",
+            (HelpTeamStats, Fr) => "statistiques d'une équipe",
+            (HelpPlayerPosition, En) => "position of a player",
+            (HelpPlayerPosition, Fr) => "position d'un joueur",
+            (HelpPlayerLevel, En) => "level of a player",
+            (HelpPlayerLevel, Fr) => "niveau d'un joueur",
+            (HelpPlayerInventory, En) => "inventory of a player",
+            (HelpPlayerInventory, Fr) => "inventaire d'un joueur",
+            (HelpPlayerQueue, En) => "commands queued by a player",
+            (HelpPlayerQueue, Fr) => "commandes en attente d'un joueur",
+            (HelpFollowQueues, En) => "follow the command queues of every player",
+            (HelpFollowQueues, Fr) => "suivre les files de commandes de tous les joueurs",
+            (HelpSetTimeUnit, En) => "change the tick rate",
+            (HelpSetTimeUnit, Fr) => "changer la fréquence des ticks",
+            (HelpJson, En) => "switch between text and JSON messages",
+            (HelpJson, Fr) => "alterner entre messages texte et JSON",
+            (HelpDebug, En) => "statistics of the server",
+            (HelpDebug, Fr) => "statistiques du serveur",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_resource_has_a_name_in_every_language() {
+        for class in ObjectClass::ALL {
+            let name = Text::resource(class);
+            assert_eq!(name.get(Lang::Fr), class.name());
+            assert!(!name.get(Lang::En).is_empty());
+        }
+        assert_eq!(Text::resource(ObjectClass::Food).get(Lang::En), "food");
+    }
+}
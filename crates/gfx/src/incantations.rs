@@ -0,0 +1,114 @@
+/// Module tracking the incantations in progress.
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+use crate::i18n::{Lang, Text};
+use crate::server::messages::IncantationStart;
+
+/// The number of ticks an incantation lasts.
+const INCANTATION_TICKS: f32 = 300.0;
+
+/// An incantation in progress on a tile.
+#[derive(Debug)]
+pub struct Incantation {
+    /// The incantation, as announced by the server.
+    pub start: IncantationStart,
+    /// When the incantation was announced.
+    pub started_at: Instant,
+    /// The estimated duration of the incantation, if the tick rate of the server is
+    /// known.
+    pub duration: Option<Duration>,
+}
+
+impl Incantation {
+    /// Returns a displayable summary of the incantation, in the provided language.
+    pub fn summary(&self, lang: Lang) -> impl Display + '_ {
+        Summary {
+            incantation: self,
+            lang,
+        }
+    }
+}
+
+/// See [`Incantation::summary`].
+struct Summary<'a> {
+    /// The incantation to describe.
+    incantation: &'a Incantation,
+    /// The language of the summary.
+    lang: Lang,
+}
+
+impl Display for Summary<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let start = &self.incantation.start;
+        write!(
+            f,
+            "{} ({}, {}): {} {} ->",
+            Text::Incantation.get(self.lang),
+            start.x,
+            start.y,
+            Text::Level.get(self.lang),
+            start.level,
+        )?;
+        for id in &start.players {
+            write!(f, " #{}", id)?;
+        }
+        if let Some(duration) = self.incantation.duration {
+            let remaining = duration.saturating_sub(self.incantation.started_at.elapsed());
+            write!(
+                f,
+                ", {} ~{:.1}s",
+                Text::EndsIn.get(self.lang),
+                remaining.as_secs_f32()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The incantations in progress, by tile.
+#[derive(Debug, Default)]
+pub struct Incantations {
+    /// The incantations in progress, indexed by the position of their tile.
+    pending: HashMap<(u32, u32), Incantation>,
+}
+
+impl Incantations {
+    /// Records an incantation announced with `pic`.
+    ///
+    /// Its duration is estimated from `tick_rate`, the tick rate of the server.
+    pub fn start(&mut self, start: IncantationStart, tick_rate: Option<f32>) -> &Incantation {
+        let duration = tick_rate
+            .filter(|&rate| rate > 0.0)
+            .map(|rate| Duration::from_secs_f32(INCANTATION_TICKS / rate));
+        let incantation = Incantation {
+            start,
+            started_at: Instant::now(),
+            duration,
+        };
+        let position = (incantation.start.x, incantation.start.y);
+        self.pending.insert(position, incantation);
+        &self.pending[&position]
+    }
+
+    /// Forgets about the incantation of a tile once `pie` is received, showing its
+    /// outcome on the error output.
+    pub fn end(&mut self, x: u32, y: u32, success: bool, lang: Lang) {
+        if self.pending.remove(&(x, y)).is_none() {
+            return;
+        }
+        let result = if success {
+            Text::Succeeded
+        } else {
+            Text::Failed
+        };
+        eprintln!(
+            "{} ({}, {}): {}",
+            Text::Incantation.get(lang),
+            x,
+            y,
+            result.get(lang)
+        );
+    }
+}
@@ -0,0 +1,75 @@
+/// Module for the progress indicator shown while the initial state of the game arrives.
+use std::io::IsTerminal;
+
+use crate::i18n::{Lang, Text};
+use crate::server::messages::ServerMessage;
+
+/// Tracks the loading of the map, which the server sends as one `bct` message per cell
+/// right after the handshake.
+#[derive(Debug)]
+pub struct Loading {
+    /// The number of cells of the map, once received with `msz`.
+    expected: Option<u64>,
+    /// The number of cells received so far.
+    received: u64,
+    /// The last percentage shown.
+    shown: u64,
+    /// Whether the whole map has been received.
+    done: bool,
+    /// Whether the progress can be redrawn in place on the error output.
+    interactive: bool,
+    /// The language of the indicator.
+    lang: Lang,
+}
+
+impl Loading {
+    /// Starts waiting for the map.
+    ///
+    /// The progress is only redrawn in place if `redraw` is set and the error output is a
+    /// terminal, which is not the case when several maps are loaded at once.
+    pub fn new(lang: Lang, redraw: bool) -> Self {
+        Self {
+            expected: None,
+            received: 0,
+            shown: 0,
+            done: false,
+            interactive: redraw && std::io::stderr().is_terminal(),
+            lang,
+        }
+    }
+
+    /// Returns whether the whole map has been received.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Records a message received from the server, updating the progress indicator.
+    pub fn record(&mut self, msg: &ServerMessage) {
+        if self.done {
+            return;
+        }
+        match msg {
+            ServerMessage::MapSize(width, height) => {
+                self.expected = Some(u64::from(*width) * u64::from(*height));
+            }
+            ServerMessage::TileContent(_) => self.received += 1,
+            _ => return,
+        }
+        let Some(expected) = self.expected else {
+            return;
+        };
+
+        if self.received >= expected {
+            self.done = true;
+            let end = if self.interactive { "\r" } else { "" };
+            eprintln!("{}{}: 100%", end, Text::LoadingMap.get(self.lang));
+            return;
+        }
+
+        let percent = self.received * 100 / expected;
+        if self.interactive && percent > self.shown {
+            self.shown = percent;
+            eprint!("\r{}: {}%", Text::LoadingMap.get(self.lang), percent);
+        }
+    }
+}
@@ -1 +1,310 @@
-fn main() {}
+//! The Zappy graphical monitor.
+
+#![deny(clippy::unwrap_used, unsafe_op_in_unsafe_fn)]
+#![warn(missing_docs, clippy::must_use_candidate)]
+
+mod args;
+mod bars;
+mod bounds;
+mod connection;
+mod eggs;
+mod heatmap;
+mod i18n;
+mod incantations;
+mod loading;
+mod queues;
+mod server;
+mod shortcuts;
+mod status;
+mod throughput;
+mod trails;
+
+use args::Args;
+use bars::InventoryBar;
+use bounds::Bounds;
+use clap::Parser;
+use connection::{Backoff, Link};
+use eggs::Eggs;
+use heatmap::Heatmap;
+use i18n::{Lang, Text};
+use incantations::Incantations;
+use loading::Loading;
+use queues::QueueStack;
+use server::messages::ServerMessage;
+use server::{Result, Server, ServerError};
+use status::{Connection, Status};
+use std::fmt::Write as _;
+use std::io::{BufWriter, IsTerminal, Write};
+use std::time::{Duration, Instant};
+use throughput::Throughput;
+use trails::Trails;
+
+/// Forwards the requests typed on the standard input to the servers.
+///
+/// A request is sent to every server, unless it is prefixed with `@<n> `, in which case
+/// it is only sent to the `n`-th server. The `:server <host:port>` request is not sent:
+/// it changes the address of the server instead. `?` shows the requests that may be
+/// typed.
+fn forward_stdin(links: &[Link], lang: Lang) -> Result<()> {
+    for line in std::io::stdin().lines() {
+        let line = line?;
+        let line = line.trim();
+        if shortcuts::HELP.matches(line).is_some() {
+            let mut help = String::new();
+            shortcuts::write_help(&mut help, lang);
+            eprint!("{help}");
+            continue;
+        }
+        let target = line.strip_prefix('@').and_then(|rest| {
+            let (index, request) = rest.split_once(' ')?;
+            Some((index.parse::<usize>().ok()?.checked_sub(1)?, request.trim()))
+        });
+        match target {
+            Some((index, request)) => match links.get(index) {
+                Some(link) => forward(link, request, lang),
+                None => eprintln!("{}: `{}`", Text::NoSuchServer.get(lang), line),
+            },
+            None => {
+                for link in links {
+                    forward(link, line, lang);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Sends a request typed on the standard input to a server, or changes its address if the
+/// request is `:server <host:port>`.
+fn forward(link: &Link, request: &str, lang: Lang) {
+    if let Some(address) = shortcuts::SERVER.matches(request) {
+        match args::parse_endpoint(address) {
+            Ok(endpoint) => link.set_endpoint(endpoint),
+            Err(err) => eprintln!("{}: {}", Text::InvalidAddress.get(lang), err),
+        }
+        return;
+    }
+
+    match link.send(request) {
+        Ok(true) => (),
+        Ok(false) => eprintln!(
+            "{} ({}): `{}`",
+            Text::NotConnected.get(lang),
+            link.endpoint(),
+            request
+        ),
+        Err(err) => eprintln!("{}: {}", Text::ForwardFailed.get(lang), err),
+    }
+}
+
+/// Reports that the server rejected the last request for the provided reason.
+fn report_rejected(reason: Text, server: &Server, label: &str, lang: Lang) {
+    match server.last_request() {
+        Some(request) => eprintln!("{}{}: `{}`", label, reason.get(lang), request),
+        None => eprintln!("{}{}", label, reason.get(lang)),
+    }
+}
+
+/// Shows the messages of a server until the connection is lost.
+///
+/// `label` is written before every line of the standard output, to tell the servers
+/// apart when several of them are observed.
+fn observe(mut server: Server, status: &mut Status, label: &str, args: &Args) -> Result<()> {
+    let lang = args.lang;
+    let mut bounds = Bounds::new(lang);
+    let mut incantations = Incantations::default();
+    let mut throughput = args.stats.then(Throughput::new);
+    let mut heatmap = args
+        .heatmap
+        .map(|resource| Heatmap::new(resource, args.columns));
+    let colored = std::io::stdout().is_terminal();
+    let mut trails = args.trails.map(|length| Trails::new(length, colored));
+    let mut eggs = args
+        .eggs
+        .then(|| Eggs::new(std::io::stderr().is_terminal()));
+    let mut loading = Loading::new(lang, label.is_empty());
+    // The initial state of the game is a flood of messages: they are written in batches
+    // until it has been received, then as they arrive. Lines are written in one go so
+    // that the output of several servers does not get mixed up.
+    let mut out = BufWriter::new(std::io::stdout());
+    let mut line = String::new();
+
+    loop {
+        let received = server.receive_line()?;
+        // Messages this monitor does not know, and JSON lines once they were requested,
+        // are not worth losing the session over.
+        let mut msg = match received.parse::<ServerMessage>() {
+            Ok(msg) => msg,
+            Err(err) => {
+                eprintln!(
+                    "{}{}: {} `{}` ({})",
+                    label,
+                    Text::Warning.get(lang),
+                    Text::SkippedLine.get(lang),
+                    received,
+                    err,
+                );
+                continue;
+            }
+        };
+        if !bounds.check(&mut msg, label) {
+            continue;
+        }
+
+        let tag = msg.tag();
+        let display_start = Instant::now();
+
+        loading.record(&msg);
+        if !matches!(
+            msg,
+            ServerMessage::UnknownCommand | ServerMessage::BadParameter
+        ) {
+            line.clear();
+            let _ = writeln!(line, "{}{}", label, msg);
+            if let (true, ServerMessage::PlayerInventory(inventory)) = (args.bars, &msg) {
+                let _ = writeln!(line, "{}  {}", label, InventoryBar { inventory, lang });
+            }
+            if let (true, &ServerMessage::PlayerQueue(id, queued)) = (args.queues, &msg) {
+                let _ = writeln!(line, "{}  {}", label, QueueStack { id, queued, lang });
+            }
+            if let Some(trail) = trails.as_mut().and_then(|trails| trails.record(&msg)) {
+                let _ = writeln!(line, "{}  {}", label, trail);
+            }
+            out.write_all(line.as_bytes())?;
+        }
+        if loading.is_done() {
+            out.flush()?;
+        }
+        if let Some(scoreboard) = eggs.as_mut().and_then(|eggs| eggs.record(&msg, lang)) {
+            eprintln!("{}{}", label, scoreboard);
+        }
+        if let Some(heatmap) = &mut heatmap {
+            heatmap.record(&msg);
+            if loading.is_done() {
+                heatmap.draw_if_due(label, lang);
+            }
+        }
+
+        match msg {
+            ServerMessage::UnknownCommand => {
+                report_rejected(Text::UnknownCommand, &server, label, lang)
+            }
+            ServerMessage::BadParameter => {
+                report_rejected(Text::BadParameters, &server, label, lang)
+            }
+            ServerMessage::TimeUnit(tick_rate) => status.set_tick_rate(tick_rate),
+            ServerMessage::IncantationStart(start) => {
+                let incantation = incantations.start(start, status.tick_rate());
+                eprintln!("{}{}", label, incantation.summary(lang));
+            }
+            ServerMessage::IncantationEnd(x, y, success) => {
+                incantations.end(x, y, success, lang);
+            }
+            _ => (),
+        }
+
+        if let Some(throughput) = &mut throughput {
+            throughput.record(tag, display_start.elapsed());
+            throughput.report_if_due(lang);
+        }
+    }
+}
+
+/// Observes a server, connecting to it again whenever the connection cannot be established
+/// or gets lost.
+///
+/// The time between two attempts doubles after every failure, and the monitor gives up
+/// after `--retries` failed attempts in a row, if provided.
+fn watch(link: &Link, label: &str, args: &Args) -> Result<()> {
+    let mut status = Status::new(link.endpoint(), args.lang);
+    status.show();
+    let mut backoff = Backoff::new();
+
+    loop {
+        link.take_moved();
+        let endpoint = link.endpoint();
+        status.set_endpoint(endpoint.clone());
+        status.set_connection(Connection::Connecting);
+
+        let result = Server::new(&endpoint).and_then(|server| {
+            link.connected(server.requests()?);
+            if args.queues {
+                link.send("pqc")?;
+            }
+            status.set_connection(Connection::Connected);
+            backoff.reset();
+            observe(server, &mut status, label, args)
+        });
+        link.disconnected();
+        status.set_connection(Connection::Disconnected);
+
+        let err = match result {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        if args.retries.is_some_and(|max| backoff.attempts() >= max) {
+            return Err(err);
+        }
+        wait_before_retry(link, &mut status, &err, backoff.next_delay());
+    }
+}
+
+/// Waits for `delay` before connecting to a server again, showing why the last attempt
+/// failed and the time left.
+///
+/// The wait ends early when the address of the server is changed.
+fn wait_before_retry(link: &Link, status: &mut Status, err: &ServerError, delay: Duration) {
+    let deadline = Instant::now() + delay;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() || link.take_moved() {
+            break;
+        }
+        status.show_retry(err, remaining);
+        std::thread::sleep(remaining.min(Duration::from_secs(1)));
+    }
+    status.end_retry();
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    let endpoints = args.endpoints();
+    let links: Vec<_> = endpoints.iter().cloned().map(Link::new).collect();
+
+    let lang = args.lang;
+    let stdin_links = links.clone();
+    std::thread::spawn(move || {
+        if let Err(err) = forward_stdin(&stdin_links, lang) {
+            eprintln!("{}: {}", Text::ForwardFailed.get(lang), err);
+        }
+    });
+
+    let labelled = endpoints.len() > 1;
+    std::thread::scope(|scope| {
+        let observers: Vec<_> = links
+            .iter()
+            .zip(&endpoints)
+            .map(|(link, endpoint)| {
+                let label = if labelled {
+                    format!("[{}] ", endpoint)
+                } else {
+                    String::new()
+                };
+                let args = &args;
+                scope.spawn(move || watch(link, &label, args))
+            })
+            .collect();
+
+        // The servers are observed until the monitor gives up on every one of them, the
+        // last error is reported.
+        let mut result = Ok(());
+        for observer in observers {
+            match observer.join() {
+                Ok(Ok(())) => (),
+                Ok(Err(err)) => result = Err(err),
+                Err(panic) => std::panic::resume_unwind(panic),
+            }
+        }
+        result
+    })
+}
@@ -0,0 +1,38 @@
+/// Module for the command queues shown under the `pqc` messages with `--queues`.
+use std::fmt::Display;
+
+use crate::i18n::{Lang, Text};
+
+/// The maximum number of commands a player may have queued on the server.
+const MAX_QUEUED_COMMANDS: u32 = 10;
+
+/// The commands queued by a player at a glance, as a stack of one character per command,
+/// telling the players that saturate their queue apart from the idle ones.
+///
+/// For instance, `#3 queue [###-------] 3/10`.
+#[derive(Debug)]
+pub struct QueueStack {
+    /// The ID of the player.
+    pub id: u64,
+    /// The number of commands queued by the player.
+    pub queued: u32,
+    /// The language of the stack.
+    pub lang: Lang,
+}
+
+impl Display for QueueStack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{} {} [", self.id, Text::Queue.get(self.lang))?;
+        for i in 0..MAX_QUEUED_COMMANDS {
+            f.write_str(if i < self.queued { "#" } else { "-" })?;
+        }
+        write!(f, "] {}/{}", self.queued, MAX_QUEUED_COMMANDS)?;
+
+        if self.queued == 0 {
+            write!(f, " ({})", Text::Idle.get(self.lang))?;
+        } else if self.queued >= MAX_QUEUED_COMMANDS {
+            write!(f, " ({})", Text::Saturated.get(self.lang))?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+/// Server abstraction module.
+pub mod messages;
+
+mod errors;
+
+pub use errors::{Result, ServerError};
+
+use crate::args::Endpoint;
+use io::{Read, Write};
+use std::io;
+use std::net::{Shutdown, TcpStream};
+use std::sync::{Arc, Mutex};
+
+/// Abstraction over the server.
+#[derive(Debug)]
+pub struct Server {
+    /// The stream to the server.
+    stream: TcpStream,
+    /// Read buffer.
+    buf: String,
+    /// The last request sent to the server.
+    last_request: Arc<Mutex<Option<String>>>,
+}
+
+impl Server {
+    /// Creates a new server instance and connects to it as a graphical monitor.
+    pub fn new(endpoint: &Endpoint) -> Result<Self> {
+        let stream = TcpStream::connect((endpoint.host.as_str(), endpoint.port))?;
+        let mut self_ = Self {
+            stream,
+            buf: String::new(),
+            last_request: Arc::new(Mutex::new(None)),
+        };
+
+        let _received = self_.receive_line()?;
+
+        self_.stream.write_all(b"GRAPHIC\n")?;
+
+        Ok(self_)
+    }
+
+    /// Returns a [`Requests`] handle that can be used to send requests to the server from
+    /// another thread.
+    pub fn requests(&self) -> Result<Requests> {
+        Ok(Requests {
+            stream: self.stream.try_clone()?,
+            last_request: Arc::clone(&self.last_request),
+        })
+    }
+
+    /// Returns the last request sent to the server, if any.
+    pub fn last_request(&self) -> Option<String> {
+        self.last_request
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// Reads a line from the server, without the newline.
+    ///
+    /// Parsing it as a [`messages::ServerMessage`] is left to the caller, which may skip
+    /// the lines it does not understand.
+    pub fn receive_line(&mut self) -> Result<String> {
+        let mut buf = [0; 1024];
+
+        let newline = loop {
+            if let Some(newline) = self.buf.find('\n') {
+                break newline;
+            }
+            let len = self.stream.read(&mut buf)?;
+            if len == 0 {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+            self.buf.push_str(&String::from_utf8_lossy(&buf[..len]));
+        };
+        let line = self.buf.drain(..newline).collect();
+        self.buf.drain(..1);
+        Ok(line)
+    }
+}
+
+/// A handle used to send requests to the server.
+#[derive(Debug)]
+pub struct Requests {
+    /// The stream to the server.
+    stream: TcpStream,
+    /// The last request sent to the server.
+    last_request: Arc<Mutex<Option<String>>>,
+}
+
+impl Requests {
+    /// Sends a request to the server.
+    pub fn send(&mut self, request: &str) -> Result<()> {
+        *self
+            .last_request
+            .lock()
+            .unwrap_or_else(|err| err.into_inner()) = Some(request.to_string());
+        self.stream.write_fmt(format_args!("{}\n", request))?;
+        Ok(())
+    }
+
+    /// Closes the connection, which makes the monitor stop receiving messages from the
+    /// server.
+    pub fn close(&self) {
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
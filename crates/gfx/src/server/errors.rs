@@ -0,0 +1,69 @@
+/// Module for server error handling.
+use std::io;
+use std::{error::Error, fmt::Display, num::ParseIntError};
+
+/// A specialized [`Result`] type for server operations.
+pub type Result<T> = std::result::Result<T, ServerError>;
+
+use InvalidMsg::{InvalidInteger, MissingValue, ParsingError};
+
+/// Errors that can occur while communicating with the server.
+#[derive(Debug)]
+pub enum ServerError {
+    /// An IO error.
+    Io(io::Error),
+    /// An invalid message from the server.
+    InvalidMessage(InvalidMsg),
+}
+
+impl Error for ServerError {}
+
+impl Display for ServerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerError::Io(err) => write!(f, "IO error: {}", err),
+            ServerError::InvalidMessage(err) => write!(f, "Invalid message: {}", err),
+        }
+    }
+}
+
+impl From<io::Error> for ServerError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<InvalidMsg> for ServerError {
+    fn from(err: InvalidMsg) -> Self {
+        Self::InvalidMessage(err)
+    }
+}
+
+/// Error type specifying the kind of invalid message.
+#[derive(Debug)]
+pub enum InvalidMsg {
+    /// A missing value.
+    MissingValue,
+    /// An invalid value.
+    InvalidInteger(ParseIntError),
+    /// A parsing error.
+    ParsingError,
+}
+
+impl Error for InvalidMsg {}
+
+impl Display for InvalidMsg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MissingValue => write!(f, "missing value"),
+            InvalidInteger(err) => write!(f, "invalid value: {}", err),
+            ParsingError => write!(f, "parsing error"),
+        }
+    }
+}
+
+impl From<ParseIntError> for InvalidMsg {
+    fn from(err: ParseIntError) -> Self {
+        Self::InvalidInteger(err)
+    }
+}
@@ -0,0 +1,451 @@
+/// This module defines the messages that the server sends to graphical monitors.
+use std::fmt::Display;
+use std::str::FromStr;
+
+use resources::{ObjectClass, ResourceSet};
+
+use super::errors::InvalidMsg;
+
+/// Statistics about a team, as sent by the `tst` message.
+#[derive(Debug, Clone)]
+pub struct TeamStats {
+    /// The name of the team.
+    pub name: String,
+    /// The number of players in the team.
+    pub players: u32,
+    /// The number of eggs laid by the team.
+    pub eggs: u32,
+    /// The sum of the levels of the players in the team.
+    pub levels: u32,
+    /// The number of available slots in the team.
+    pub slots: u32,
+}
+
+/// The objects the players of a team hold when they spawn, as sent by the `tsi` message.
+///
+/// This is an extension of the protocol, only sent for the teams that were given a
+/// handicap.
+#[derive(Debug, Clone)]
+pub struct TeamStartingInventory {
+    /// The name of the team.
+    pub team: String,
+    /// The resources held by the players of the team when they spawn.
+    pub resources: ResourceSet,
+}
+
+/// A player that just connected, as sent by the `pnw` message.
+#[derive(Debug, Clone)]
+pub struct PlayerNew {
+    /// The ID of the player.
+    pub id: u64,
+    /// The horizontal position of the player.
+    pub x: u32,
+    /// The vertical position of the player.
+    pub y: u32,
+    /// The orientation of the player (1: north, 2: east, 3: south, 4: west).
+    pub orientation: u8,
+    /// The level of the player.
+    pub level: u32,
+    /// The name of the team of the player.
+    pub team: String,
+}
+
+/// The position and orientation of a player, as sent by the `ppo` message.
+#[derive(Debug, Clone)]
+pub struct PlayerPosition {
+    /// The ID of the player.
+    pub id: u64,
+    /// The horizontal position of the player.
+    pub x: u32,
+    /// The vertical position of the player.
+    pub y: u32,
+    /// The orientation of the player (1: north, 2: east, 3: south, 4: west).
+    pub orientation: u8,
+}
+
+/// The inventory of a player, as sent by the `pin` message.
+#[derive(Debug, Clone)]
+pub struct PlayerInventory {
+    /// The ID of the player.
+    pub id: u64,
+    /// The horizontal position of the player.
+    pub x: u32,
+    /// The vertical position of the player.
+    pub y: u32,
+    /// The resources held by the player.
+    pub resources: ResourceSet,
+}
+
+/// An egg laid by a player, as sent by the `enw` message.
+#[derive(Debug, Clone)]
+pub struct EggNew {
+    /// The ID of the egg.
+    pub id: u64,
+    /// The ID of the player that laid the egg.
+    pub parent: u64,
+    /// The horizontal position at which the egg was laid.
+    pub x: u32,
+    /// The vertical position at which the egg was laid.
+    pub y: u32,
+}
+
+/// An incantation that started, as sent by the `pic` message.
+#[derive(Debug, Clone)]
+pub struct IncantationStart {
+    /// The horizontal position of the tile of the incantation.
+    pub x: u32,
+    /// The vertical position of the tile of the incantation.
+    pub y: u32,
+    /// The level of the players taking part in the incantation.
+    pub level: u32,
+    /// The IDs of the players taking part in the incantation, starting with the one that
+    /// started it.
+    pub players: Vec<u64>,
+}
+
+/// The content of a tile, as sent by the `bct` message.
+#[derive(Debug, Clone)]
+pub struct TileContent {
+    /// The horizontal position of the tile.
+    pub x: u32,
+    /// The vertical position of the tile.
+    pub y: u32,
+    /// The resources lying on the tile.
+    pub resources: ResourceSet,
+}
+
+/// Enum representing a message from the server.
+#[derive(Debug, Clone)]
+pub enum ServerMessage {
+    /// The size of the map.
+    MapSize(u32, u32),
+    /// The content of a tile.
+    TileContent(TileContent),
+    /// The time unit of the server.
+    TimeUnit(f32),
+    /// The name of a team.
+    TeamName(String),
+    /// Statistics about a team.
+    TeamStats(TeamStats),
+    /// The starting inventory of a team.
+    TeamStartingInventory(TeamStartingInventory),
+    /// A new player connected.
+    PlayerNew(PlayerNew),
+    /// A player disconnected or died (`pdi`), with its ID.
+    PlayerDeath(u64),
+    /// A player expelled the players on its tile (`pex`), with its ID.
+    PlayerExpulsion(u64),
+    /// The position of a player.
+    PlayerPosition(PlayerPosition),
+    /// A player picked up an object (`pgt`), as `(id, class)`.
+    ///
+    /// The class of the object is its index in [`ObjectClass::ALL`].
+    PlayerPickup(u64, u8),
+    /// A player dropped an object (`pdr`), as `(id, class)`.
+    ///
+    /// The class of the object is its index in [`ObjectClass::ALL`].
+    PlayerDrop(u64, u8),
+    /// The level of a player (`plv`), as `(id, level)`.
+    PlayerLevel(u64, u32),
+    /// The inventory of a player.
+    PlayerInventory(PlayerInventory),
+    /// The number of ticks before a player starves (`ptl`), as `(id, ticks)`.
+    PlayerTimeToLive(u64, u64),
+    /// The number of commands queued by a player (`pqc`), as `(id, commands)`.
+    ///
+    /// This is an extension of the protocol, only sent after the `pqc` request.
+    PlayerQueue(u64, u32),
+    /// An incantation started.
+    IncantationStart(IncantationStart),
+    /// An incantation ended (`pie`), as `(x, y, success)`.
+    IncantationEnd(u32, u32, bool),
+    /// A player laid an egg.
+    EggNew(EggNew),
+    /// An egg hatched, creating a slot in its team (`eht`), with the ID of the egg.
+    EggHatched(u64),
+    /// The slot created by a hatched egg expired (`edi`), with the ID of the egg.
+    EggDeath(u64),
+    /// A team won the game (`seg`), as `(team, rule)`.
+    GameEnd(String, String),
+    /// A message from the server.
+    Message(String),
+    /// The server did not recognize the last command (`suc`).
+    UnknownCommand,
+    /// The last command had invalid parameters (`sbp`).
+    BadParameter,
+}
+
+impl ServerMessage {
+    /// Returns the tag of the message, such as `bct`.
+    pub fn tag(&self) -> &'static str {
+        match self {
+            ServerMessage::MapSize(..) => "msz",
+            ServerMessage::TileContent(_) => "bct",
+            ServerMessage::TimeUnit(_) => "sgt",
+            ServerMessage::TeamName(_) => "tna",
+            ServerMessage::TeamStats(_) => "tst",
+            ServerMessage::TeamStartingInventory(_) => "tsi",
+            ServerMessage::PlayerNew(_) => "pnw",
+            ServerMessage::PlayerDeath(_) => "pdi",
+            ServerMessage::PlayerExpulsion(_) => "pex",
+            ServerMessage::PlayerPosition(_) => "ppo",
+            ServerMessage::PlayerPickup(..) => "pgt",
+            ServerMessage::PlayerDrop(..) => "pdr",
+            ServerMessage::PlayerLevel(..) => "plv",
+            ServerMessage::PlayerInventory(_) => "pin",
+            ServerMessage::PlayerTimeToLive(..) => "ptl",
+            ServerMessage::PlayerQueue(..) => "pqc",
+            ServerMessage::IncantationStart(_) => "pic",
+            ServerMessage::IncantationEnd(..) => "pie",
+            ServerMessage::EggNew(_) => "enw",
+            ServerMessage::EggHatched(_) => "eht",
+            ServerMessage::EggDeath(_) => "edi",
+            ServerMessage::GameEnd(..) => "seg",
+            ServerMessage::Message(_) => "smg",
+            ServerMessage::UnknownCommand => "suc",
+            ServerMessage::BadParameter => "sbp",
+        }
+    }
+}
+
+impl Display for ServerMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerMessage::MapSize(x, y) => write!(f, "msz {} {}", x, y),
+            ServerMessage::TileContent(tile) => {
+                write!(f, "bct {} {} {}", tile.x, tile.y, tile.resources)
+            }
+            ServerMessage::TimeUnit(t) => write!(f, "sgt {}", t),
+            ServerMessage::TeamName(name) => write!(f, "tna {}", name),
+            ServerMessage::TeamStats(stats) => write!(
+                f,
+                "tst {} {} {} {} {}",
+                stats.name, stats.players, stats.eggs, stats.levels, stats.slots
+            ),
+            ServerMessage::TeamStartingInventory(start) => {
+                write!(f, "tsi {} {}", start.team, start.resources)
+            }
+            ServerMessage::PlayerNew(player) => write!(
+                f,
+                "pnw #{} {} {} {} {} {}",
+                player.id, player.x, player.y, player.orientation, player.level, player.team
+            ),
+            ServerMessage::PlayerDeath(id) => write!(f, "pdi #{}", id),
+            ServerMessage::PlayerExpulsion(id) => write!(f, "pex #{}", id),
+            ServerMessage::PlayerPosition(pos) => {
+                write!(f, "ppo #{} {} {} {}", pos.id, pos.x, pos.y, pos.orientation)
+            }
+            ServerMessage::PlayerPickup(id, class) => write!(f, "pgt #{} {}", id, class),
+            ServerMessage::PlayerDrop(id, class) => write!(f, "pdr #{} {}", id, class),
+            ServerMessage::PlayerLevel(id, level) => write!(f, "plv #{} {}", id, level),
+            ServerMessage::PlayerInventory(inv) => {
+                write!(f, "pin #{} {} {} {}", inv.id, inv.x, inv.y, inv.resources)
+            }
+            ServerMessage::PlayerTimeToLive(id, ticks) => write!(f, "ptl #{} {}", id, ticks),
+            ServerMessage::PlayerQueue(id, commands) => write!(f, "pqc #{} {}", id, commands),
+            ServerMessage::IncantationStart(pic) => {
+                write!(f, "pic {} {} {}", pic.x, pic.y, pic.level)?;
+                for id in &pic.players {
+                    write!(f, " #{}", id)?;
+                }
+                Ok(())
+            }
+            ServerMessage::IncantationEnd(x, y, success) => {
+                write!(f, "pie {} {} {}", x, y, u8::from(*success))
+            }
+            ServerMessage::EggNew(egg) => {
+                write!(f, "enw #{} #{} {} {}", egg.id, egg.parent, egg.x, egg.y)
+            }
+            ServerMessage::EggHatched(id) => write!(f, "eht #{}", id),
+            ServerMessage::EggDeath(id) => write!(f, "edi #{}", id),
+            ServerMessage::GameEnd(team, rule) => write!(f, "seg {} {}", team, rule),
+            ServerMessage::Message(msg) => write!(f, "smg {}", msg),
+            ServerMessage::UnknownCommand => write!(f, "suc"),
+            ServerMessage::BadParameter => write!(f, "sbp"),
+        }
+    }
+}
+
+impl FromStr for ServerMessage {
+    type Err = InvalidMsg;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tag, args) = s.split_once(' ').unwrap_or((s, ""));
+        let mut values = args.split(' ');
+        let mut next = || values.next().ok_or(InvalidMsg::MissingValue);
+
+        match tag {
+            "msz" => Ok(ServerMessage::MapSize(next()?.parse()?, next()?.parse()?)),
+            "bct" => {
+                let x = next()?.parse()?;
+                let y = next()?.parse()?;
+                let resources = parse_resources(&mut next)?;
+                Ok(ServerMessage::TileContent(TileContent { x, y, resources }))
+            }
+            "sgt" => Ok(ServerMessage::TimeUnit(
+                next()?.parse().map_err(|_| InvalidMsg::ParsingError)?,
+            )),
+            "tna" => Ok(ServerMessage::TeamName(args.to_string())),
+            "tst" => Ok(ServerMessage::TeamStats(TeamStats {
+                name: next()?.to_string(),
+                players: next()?.parse()?,
+                eggs: next()?.parse()?,
+                levels: next()?.parse()?,
+                slots: next()?.parse()?,
+            })),
+            "tsi" => {
+                let team = next()?.to_string();
+                let resources = parse_resources(&mut next)?;
+                Ok(ServerMessage::TeamStartingInventory(
+                    TeamStartingInventory { team, resources },
+                ))
+            }
+            "pnw" => Ok(ServerMessage::PlayerNew(PlayerNew {
+                id: parse_player_id(next()?)?,
+                x: next()?.parse()?,
+                y: next()?.parse()?,
+                orientation: next()?.parse()?,
+                level: next()?.parse()?,
+                team: next()?.to_string(),
+            })),
+            "pdi" => Ok(ServerMessage::PlayerDeath(parse_player_id(next()?)?)),
+            "pex" => Ok(ServerMessage::PlayerExpulsion(parse_player_id(next()?)?)),
+            "ppo" => Ok(ServerMessage::PlayerPosition(PlayerPosition {
+                id: parse_player_id(next()?)?,
+                x: next()?.parse()?,
+                y: next()?.parse()?,
+                orientation: next()?.parse()?,
+            })),
+            "pgt" => Ok(ServerMessage::PlayerPickup(
+                parse_player_id(next()?)?,
+                parse_object_class(next()?)?,
+            )),
+            "pdr" => Ok(ServerMessage::PlayerDrop(
+                parse_player_id(next()?)?,
+                parse_object_class(next()?)?,
+            )),
+            "plv" => Ok(ServerMessage::PlayerLevel(
+                parse_player_id(next()?)?,
+                next()?.parse()?,
+            )),
+            "pin" => {
+                let id = parse_player_id(next()?)?;
+                let x = next()?.parse()?;
+                let y = next()?.parse()?;
+                let resources = parse_resources(&mut next)?;
+                Ok(ServerMessage::PlayerInventory(PlayerInventory {
+                    id,
+                    x,
+                    y,
+                    resources,
+                }))
+            }
+            "ptl" => Ok(ServerMessage::PlayerTimeToLive(
+                parse_player_id(next()?)?,
+                next()?.parse()?,
+            )),
+            "pqc" => Ok(ServerMessage::PlayerQueue(
+                parse_player_id(next()?)?,
+                next()?.parse()?,
+            )),
+            "pic" => {
+                let x = next()?.parse()?;
+                let y = next()?.parse()?;
+                let level = next()?.parse()?;
+                let players = values.map(parse_player_id).collect::<Result<Vec<_>, _>>()?;
+                Ok(ServerMessage::IncantationStart(IncantationStart {
+                    x,
+                    y,
+                    level,
+                    players,
+                }))
+            }
+            "pie" => Ok(ServerMessage::IncantationEnd(
+                next()?.parse()?,
+                next()?.parse()?,
+                match next()? {
+                    "0" => false,
+                    "1" => true,
+                    _ => return Err(InvalidMsg::ParsingError),
+                },
+            )),
+            "enw" => Ok(ServerMessage::EggNew(EggNew {
+                id: parse_player_id(next()?)?,
+                parent: parse_player_id(next()?)?,
+                x: next()?.parse()?,
+                y: next()?.parse()?,
+            })),
+            "eht" => Ok(ServerMessage::EggHatched(parse_player_id(next()?)?)),
+            "edi" => Ok(ServerMessage::EggDeath(parse_player_id(next()?)?)),
+            "seg" => Ok(ServerMessage::GameEnd(
+                next()?.to_string(),
+                next()?.to_string(),
+            )),
+            "smg" => Ok(ServerMessage::Message(args.to_string())),
+            "suc" => Ok(ServerMessage::UnknownCommand),
+            "sbp" => Ok(ServerMessage::BadParameter),
+            _ => Err(InvalidMsg::ParsingError),
+        }
+    }
+}
+
+/// Parses a player or egg ID of the form `#<id>`.
+fn parse_player_id(s: &str) -> Result<u64, InvalidMsg> {
+    Ok(s.strip_prefix('#')
+        .ok_or(InvalidMsg::ParsingError)?
+        .parse()?)
+}
+
+/// Parses the class of an object, sent as its index in [`ObjectClass::ALL`].
+fn parse_object_class(s: &str) -> Result<u8, InvalidMsg> {
+    let class: u8 = s.parse()?;
+    if usize::from(class) >= ObjectClass::ALL.len() {
+        return Err(InvalidMsg::ParsingError);
+    }
+    Ok(class)
+}
+
+/// Parses the number of each resource, from food to thystame, taking one value from `next`
+/// per resource.
+fn parse_resources<'a>(
+    next: &mut impl FnMut() -> Result<&'a str, InvalidMsg>,
+) -> Result<ResourceSet, InvalidMsg> {
+    let mut counts = [0; 7];
+    for count in &mut counts {
+        *count = next()?.parse()?;
+    }
+    Ok(ResourceSet::from_counts(counts))
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// The conformance transcript of the graphics protocol, whose `< ` lines are what the
+    /// server sends to monitors.
+    const TRANSCRIPT: &str = include_str!("../../../../conformance/graphic.txt");
+
+    #[test]
+    fn conformance_messages_round_trip() {
+        let lines: Vec<&str> = TRANSCRIPT
+            .lines()
+            .filter_map(|line| line.strip_prefix("< "))
+            // The greeting is handled by the connection, not parsed as a message.
+            .filter(|&line| line != "BIENVENUE")
+            .collect();
+        assert!(!lines.is_empty());
+
+        for line in lines {
+            let message: ServerMessage = line.parse().unwrap();
+            assert_eq!(message.to_string(), line);
+        }
+    }
+
+    #[test]
+    fn unknown_messages_are_rejected() {
+        assert!("xyz 1 2".parse::<ServerMessage>().is_err());
+        assert!("msz 1".parse::<ServerMessage>().is_err());
+        assert!("pdi 3".parse::<ServerMessage>().is_err());
+    }
+}
@@ -0,0 +1,148 @@
+/// Module for the requests the user may type on the standard input, and their help.
+use std::fmt::Write;
+
+use crate::i18n::{Lang, Text};
+
+/// A request the user may type on the standard input.
+#[derive(Debug, Clone, Copy)]
+pub struct Shortcut {
+    /// The names the request may be typed with.
+    pub names: &'static [&'static str],
+    /// The arguments of the request, as shown in the help.
+    pub args: &'static str,
+    /// What the request does.
+    pub help: Text,
+}
+
+impl Shortcut {
+    /// Returns the arguments of `request` if it is this request, typed with any of its
+    /// names.
+    pub fn matches<'a>(&self, request: &'a str) -> Option<&'a str> {
+        self.names.iter().find_map(|name| {
+            let rest = request.strip_prefix(name)?;
+            match rest.strip_prefix(' ') {
+                Some(args) => Some(args.trim()),
+                None => rest.is_empty().then_some(""),
+            }
+        })
+    }
+}
+
+/// Shows the help.
+pub const HELP: Shortcut = Shortcut {
+    names: &["?", ":help"],
+    args: "",
+    help: Text::HelpHelp,
+};
+
+/// Changes the address of a server.
+pub const SERVER: Shortcut = Shortcut {
+    names: &[":server"],
+    args: "<host:port>",
+    help: Text::HelpServer,
+};
+
+/// Sends a request to a single server, when several of them are observed.
+///
+/// Its name is only shown in the help: the index of the server is parsed by
+/// [`crate::forward_stdin`].
+pub const TARGET: Shortcut = Shortcut {
+    names: &["@<n>"],
+    args: "<request>",
+    help: Text::HelpTarget,
+};
+
+/// The requests handled by the monitor itself, which are not sent to the servers.
+pub const LOCAL: &[Shortcut] = &[HELP, SERVER, TARGET];
+
+/// The requests understood by the servers.
+pub const FORWARDED: &[Shortcut] = &[
+    Shortcut {
+        names: &["bct"],
+        args: "<x> <y>",
+        help: Text::HelpTileContent,
+    },
+    Shortcut {
+        names: &["mct"],
+        args: "",
+        help: Text::HelpMapContent,
+    },
+    Shortcut {
+        names: &["tst"],
+        args: "<team>",
+        help: Text::HelpTeamStats,
+    },
+    Shortcut {
+        names: &["ppo"],
+        args: "#<n>",
+        help: Text::HelpPlayerPosition,
+    },
+    Shortcut {
+        names: &["plv"],
+        args: "#<n>",
+        help: Text::HelpPlayerLevel,
+    },
+    Shortcut {
+        names: &["pin"],
+        args: "#<n>",
+        help: Text::HelpPlayerInventory,
+    },
+    Shortcut {
+        names: &["pqc"],
+        args: "#<n>",
+        help: Text::HelpPlayerQueue,
+    },
+    Shortcut {
+        names: &["pqc"],
+        args: "",
+        help: Text::HelpFollowQueues,
+    },
+    Shortcut {
+        names: &["sst"],
+        args: "<frequency>",
+        help: Text::HelpSetTimeUnit,
+    },
+    Shortcut {
+        names: &["json"],
+        args: "",
+        help: Text::HelpJson,
+    },
+    Shortcut {
+        names: &["dbg"],
+        args: "",
+        help: Text::HelpDebug,
+    },
+];
+
+/// Writes the help listing every request to `buf`, one per line, under a title for the
+/// local requests and one for the forwarded ones.
+pub fn write_help(buf: &mut String, lang: Lang) {
+    let syntax = |shortcut: &Shortcut| {
+        let names = shortcut.names.join(", ");
+        match shortcut.args {
+            "" => names,
+            args => format!("{names} {args}"),
+        }
+    };
+    let width = LOCAL
+        .iter()
+        .chain(FORWARDED)
+        .map(|shortcut| syntax(shortcut).len())
+        .max()
+        .unwrap_or(0);
+
+    for (title, shortcuts) in [
+        (Text::LocalRequests, LOCAL),
+        (Text::ServerRequests, FORWARDED),
+    ] {
+        let _ = writeln!(buf, "{}:", title.get(lang));
+        for shortcut in shortcuts {
+            let _ = writeln!(
+                buf,
+                "  {:width$}  {}",
+                syntax(shortcut),
+                shortcut.help.get(lang)
+            );
+        }
+    }
+}
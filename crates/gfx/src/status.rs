@@ -0,0 +1,144 @@
+/// Module for the connection status indicator.
+use std::fmt::Display;
+use std::io::{IsTerminal, Write};
+use std::time::Duration;
+
+use crate::args::Endpoint;
+use crate::i18n::{Lang, Text};
+use crate::server::ServerError;
+
+/// The state of the connection to the server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connection {
+    /// The connection is being established.
+    Connecting,
+    /// The monitor is connected to the server.
+    Connected,
+    /// The connection was lost or could not be established.
+    Disconnected,
+}
+
+/// The connection status of the monitor, shown in the terminal title and on the error
+/// output whenever it changes.
+#[derive(Debug)]
+pub struct Status {
+    /// The address of the server.
+    endpoint: Endpoint,
+    /// The state of the connection.
+    connection: Connection,
+    /// The tick rate of the server, once received with `sgt`.
+    tick_rate: Option<f32>,
+    /// The language of the status.
+    lang: Lang,
+    /// Whether the error banner is being shown, until the next connection attempt.
+    retrying: bool,
+}
+
+impl Status {
+    /// Creates the status of a monitor about to connect to the server.
+    pub fn new(endpoint: Endpoint, lang: Lang) -> Self {
+        Self {
+            endpoint,
+            connection: Connection::Connecting,
+            tick_rate: None,
+            lang,
+            retrying: false,
+        }
+    }
+
+    /// Updates the address of the server.
+    pub fn set_endpoint(&mut self, endpoint: Endpoint) {
+        if self.endpoint != endpoint {
+            self.endpoint = endpoint;
+            self.tick_rate = None;
+            self.show();
+        }
+    }
+
+    /// Updates the state of the connection.
+    pub fn set_connection(&mut self, connection: Connection) {
+        if self.connection != connection {
+            self.connection = connection;
+            self.show();
+        }
+    }
+
+    /// Updates the tick rate of the server.
+    pub fn set_tick_rate(&mut self, tick_rate: f32) {
+        if self.tick_rate != Some(tick_rate) {
+            self.tick_rate = Some(tick_rate);
+            self.show();
+        }
+    }
+
+    /// Returns the tick rate of the server, once received with `sgt`.
+    pub fn tick_rate(&self) -> Option<f32> {
+        self.tick_rate
+    }
+
+    /// Shows the current status.
+    pub fn show(&self) {
+        eprintln!("{}: {}", Text::Status.get(self.lang), self);
+
+        let mut stdout = std::io::stdout();
+        if stdout.is_terminal() {
+            // OSC 0 sets the title of the terminal window.
+            let _ = write!(stdout, "\x1b]0;zappy monitor - {}\x07", self);
+            let _ = stdout.flush();
+        }
+    }
+
+    /// Shows the error that made the connection fail or get lost, and the time left before
+    /// the next attempt.
+    ///
+    /// On a terminal, the banner is redrawn in place as the countdown goes. Otherwise, it
+    /// is only written once per attempt.
+    pub fn show_retry(&mut self, err: &ServerError, remaining: Duration) {
+        let banner = format!(
+            "{} ({}): {} - {} {}s",
+            Text::ConnectionError.get(self.lang),
+            self.endpoint,
+            err,
+            Text::RetryingIn.get(self.lang),
+            remaining.as_secs_f32().ceil(),
+        );
+
+        let mut stderr = std::io::stderr();
+        if stderr.is_terminal() {
+            let _ = write!(stderr, "\r\x1b[2K\x1b[1;31m{}\x1b[0m", banner);
+        } else if !self.retrying {
+            let _ = writeln!(stderr, "{}", banner);
+        }
+        self.retrying = true;
+    }
+
+    /// Stops showing the error banner, before a new connection attempt.
+    ///
+    /// The last state of the banner is left on the terminal.
+    pub fn end_retry(&mut self) {
+        if self.retrying && std::io::stderr().is_terminal() {
+            eprintln!();
+        }
+        self.retrying = false;
+    }
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self.connection {
+            Connection::Connecting => Text::ConnectingTo,
+            Connection::Connected => Text::ConnectedTo,
+            Connection::Disconnected => Text::DisconnectedFrom,
+        };
+        write!(f, "{} {}", text.get(self.lang), self.endpoint)?;
+        if let Some(tick_rate) = self.tick_rate {
+            write!(
+                f,
+                " ({} {})",
+                tick_rate,
+                Text::TicksPerSecond.get(self.lang)
+            )?;
+        }
+        Ok(())
+    }
+}
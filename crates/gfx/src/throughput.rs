@@ -0,0 +1,86 @@
+/// Module for the throughput statistics of the monitor.
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+use crate::i18n::{Lang, Text};
+
+/// The period over which statistics are accumulated before being reported.
+const PERIOD: Duration = Duration::from_secs(1);
+
+/// Counts the messages received from the server and the time spent displaying them, so
+/// that slowdowns can be traced to bursts of messages (such as the initial `bct` flood)
+/// or to the terminal.
+#[derive(Debug)]
+pub struct Throughput {
+    /// When the current period started.
+    period_start: Instant,
+    /// The number of messages received during the current period, by tag.
+    counts: BTreeMap<&'static str, u32>,
+    /// The time spent displaying messages during the current period.
+    display_time: Duration,
+}
+
+impl Throughput {
+    /// Starts counting.
+    pub fn new() -> Self {
+        Self {
+            period_start: Instant::now(),
+            counts: BTreeMap::new(),
+            display_time: Duration::ZERO,
+        }
+    }
+
+    /// Records a message, received with the provided tag, that took `display_time` to
+    /// display.
+    pub fn record(&mut self, tag: &'static str, display_time: Duration) {
+        *self.counts.entry(tag).or_default() += 1;
+        self.display_time += display_time;
+    }
+
+    /// Shows the statistics of the current period on the error output if it is over, then
+    /// starts a new one.
+    pub fn report_if_due(&mut self, lang: Lang) {
+        if self.period_start.elapsed() < PERIOD {
+            return;
+        }
+        eprintln!("{}", Report { stats: self, lang });
+        self.period_start = Instant::now();
+        self.counts.clear();
+        self.display_time = Duration::ZERO;
+    }
+}
+
+/// See [`Throughput::report_if_due`].
+struct Report<'a> {
+    /// The statistics to report.
+    stats: &'a Throughput,
+    /// The language of the report.
+    lang: Lang,
+}
+
+impl Display for Report<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let seconds = self.stats.period_start.elapsed().as_secs_f32();
+        let total: u32 = self.stats.counts.values().sum();
+        write!(
+            f,
+            "{:.0} {}",
+            total as f32 / seconds,
+            Text::MessagesPerSecond.get(self.lang)
+        )?;
+        for (i, (tag, count)) in self.stats.counts.iter().enumerate() {
+            let separator = if i == 0 { " (" } else { ", " };
+            write!(f, "{}{} {:.0}", separator, tag, *count as f32 / seconds)?;
+        }
+        if !self.stats.counts.is_empty() {
+            write!(f, ")")?;
+        }
+        write!(
+            f,
+            ", {} {:.1}ms",
+            Text::DisplayTime.get(self.lang),
+            self.stats.display_time.as_secs_f32() * 1000.0
+        )
+    }
+}
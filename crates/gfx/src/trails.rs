@@ -0,0 +1,136 @@
+/// Module for the trails showing where the players went recently.
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+
+use crate::server::messages::ServerMessage;
+
+/// The colors of the teams, as ANSI foreground color codes, used in turn.
+pub const TEAM_COLORS: [u8; 6] = [31, 32, 33, 34, 35, 36];
+
+/// The recent positions of a player.
+#[derive(Debug)]
+struct Trail {
+    /// The index of the team of the player, in the order the teams were first seen.
+    team: usize,
+    /// The positions of the player, from the most recent to the oldest.
+    positions: VecDeque<(u32, u32)>,
+}
+
+/// Keeps the last positions of every player, from the `pnw`, `ppo` and `pin` messages.
+#[derive(Debug)]
+pub struct Trails {
+    /// The number of positions kept per player.
+    length: usize,
+    /// Whether the trails are colored by team and fade out.
+    colored: bool,
+    /// The names of the teams seen so far, in the order of the `tna` messages.
+    teams: Vec<String>,
+    /// The trail of every player, by ID.
+    players: HashMap<u64, Trail>,
+}
+
+impl Trails {
+    /// Starts keeping the last `length` positions of every player.
+    pub fn new(length: usize, colored: bool) -> Self {
+        Self {
+            length: length.max(1),
+            colored,
+            teams: Vec::new(),
+            players: HashMap::new(),
+        }
+    }
+
+    /// Records a message received from the server.
+    ///
+    /// # Returns
+    ///
+    /// The trail of the player that moved, if the message is a `ppo` moving a player.
+    pub fn record(&mut self, msg: &ServerMessage) -> Option<TrailLine<'_>> {
+        let (id, position) = match msg {
+            ServerMessage::TeamName(name) => {
+                team_index(&mut self.teams, name);
+                return None;
+            }
+            ServerMessage::PlayerNew(player) => {
+                let team = team_index(&mut self.teams, &player.team);
+                let trail = Trail {
+                    team,
+                    positions: VecDeque::from([(player.x, player.y)]),
+                };
+                self.players.insert(player.id, trail);
+                return None;
+            }
+            ServerMessage::PlayerDeath(id) => {
+                self.players.remove(id);
+                return None;
+            }
+            ServerMessage::PlayerPosition(pos) => (pos.id, (pos.x, pos.y)),
+            ServerMessage::PlayerInventory(inv) => (inv.id, (inv.x, inv.y)),
+            _ => return None,
+        };
+
+        let trail = self.players.get_mut(&id)?;
+        if trail.positions.front() == Some(&position) {
+            return None;
+        }
+        if trail.positions.len() == self.length {
+            trail.positions.pop_back();
+        }
+        trail.positions.push_front(position);
+
+        matches!(msg, ServerMessage::PlayerPosition(_)).then_some(TrailLine {
+            id,
+            trail,
+            colored: self.colored,
+        })
+    }
+}
+
+/// Returns the index of a team in `teams`, adding it at the end if it is not there yet.
+///
+/// Teams are added as their `tna` message arrives, so that every module colors a team the
+/// same way.
+pub fn team_index(teams: &mut Vec<String>, name: &str) -> usize {
+    match teams.iter().position(|team| team == name) {
+        Some(team) => team,
+        None => {
+            teams.push(name.to_string());
+            teams.len() - 1
+        }
+    }
+}
+
+/// The trail of a player, from its current position to the oldest one kept.
+///
+/// For instance, `#3 trail 4,5 < 4,6 < 5,6`.
+#[derive(Debug)]
+pub struct TrailLine<'a> {
+    /// The ID of the player.
+    id: u64,
+    /// The trail of the player.
+    trail: &'a Trail,
+    /// Whether the trail is colored by team and fades out.
+    colored: bool,
+}
+
+impl Display for TrailLine<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.colored {
+            let color = TEAM_COLORS[self.trail.team % TEAM_COLORS.len()];
+            write!(f, "\x1b[{}m", color)?;
+        }
+        write!(f, "#{} trail", self.id)?;
+        for (i, (x, y)) in self.trail.positions.iter().enumerate() {
+            let separator = if i == 0 { " " } else { " < " };
+            // Past positions are dimmed.
+            if self.colored && i == 1 {
+                write!(f, "\x1b[2m")?;
+            }
+            write!(f, "{}{},{}", separator, x, y)?;
+        }
+        if self.colored {
+            write!(f, "\x1b[0m")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,54 @@
+//! The requirements of each elevation.
+
+use crate::ResourceSet;
+
+/// The requirements of an elevation from a level to the next one.
+#[derive(Debug, Clone, Copy)]
+pub struct Requirement {
+    /// The number of players of the same level that must be on the tile.
+    pub players: u8,
+    /// The stones that must be on the tile.
+    pub stones: ResourceSet,
+}
+
+/// The requirements of every elevation, indexed by the level of the players minus one.
+///
+/// Stones are counted from linemate to thystame, after the food that is never required.
+const REQUIREMENTS: [Requirement; 7] = [
+    Requirement {
+        players: 1,
+        stones: ResourceSet::from_counts([0, 1, 0, 0, 0, 0, 0]),
+    },
+    Requirement {
+        players: 2,
+        stones: ResourceSet::from_counts([0, 1, 1, 1, 0, 0, 0]),
+    },
+    Requirement {
+        players: 2,
+        stones: ResourceSet::from_counts([0, 2, 0, 1, 0, 2, 0]),
+    },
+    Requirement {
+        players: 4,
+        stones: ResourceSet::from_counts([0, 1, 1, 2, 0, 1, 0]),
+    },
+    Requirement {
+        players: 4,
+        stones: ResourceSet::from_counts([0, 1, 2, 1, 3, 0, 0]),
+    },
+    Requirement {
+        players: 6,
+        stones: ResourceSet::from_counts([0, 1, 2, 3, 0, 1, 0]),
+    },
+    Requirement {
+        players: 6,
+        stones: ResourceSet::from_counts([0, 2, 2, 2, 2, 2, 1]),
+    },
+];
+
+/// Returns the requirements of the elevation from `level` to the next one.
+///
+/// Returns `None` if `level` is the last one.
+#[must_use]
+pub fn elevation_requirement(level: u32) -> Option<&'static Requirement> {
+    REQUIREMENTS.get(usize::try_from(level.checked_sub(1)?).ok()?)
+}
@@ -0,0 +1,215 @@
+//! The resources of the Zappy world, shared by the server and the clients.
+
+#![no_std]
+#![deny(clippy::unwrap_used, unsafe_op_in_unsafe_fn)]
+#![warn(missing_docs, clippy::must_use_candidate)]
+
+use core::fmt::{self, Display};
+use core::ops::{Index, IndexMut};
+
+mod elevation;
+
+pub use self::elevation::*;
+
+/// The class of an object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectClass {
+    /// Food.
+    Food,
+    /// Linemate.
+    Linemate,
+    /// Deraumere.
+    Deraumere,
+    /// Sibur.
+    Sibur,
+    /// Mendiane.
+    Mendiane,
+    /// Phiras.
+    Phiras,
+    /// Thystame.
+    Thystame,
+}
+
+impl ObjectClass {
+    /// All object classes, in the order used by the graphics protocol.
+    pub const ALL: [Self; 7] = [
+        Self::Food,
+        Self::Linemate,
+        Self::Deraumere,
+        Self::Sibur,
+        Self::Mendiane,
+        Self::Phiras,
+        Self::Thystame,
+    ];
+
+    /// The stones, that is every object class but food, in the order used by the graphics
+    /// protocol.
+    pub const STONES: [Self; 6] = [
+        Self::Linemate,
+        Self::Deraumere,
+        Self::Sibur,
+        Self::Mendiane,
+        Self::Phiras,
+        Self::Thystame,
+    ];
+
+    /// Parses an object class from the provided argument.
+    #[must_use]
+    pub fn from_arg(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            b"nourriture" => Some(Self::Food),
+            b"linemate" => Some(Self::Linemate),
+            b"deraumere" => Some(Self::Deraumere),
+            b"sibur" => Some(Self::Sibur),
+            b"mendiane" => Some(Self::Mendiane),
+            b"phiras" => Some(Self::Phiras),
+            b"thystame" => Some(Self::Thystame),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the object class, as used by the player protocol.
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Food => "nourriture",
+            Self::Linemate => "linemate",
+            Self::Deraumere => "deraumere",
+            Self::Sibur => "sibur",
+            Self::Mendiane => "mendiane",
+            Self::Phiras => "phiras",
+            Self::Thystame => "thystame",
+        }
+    }
+
+    /// Returns the index of the object class in [`ObjectClass::ALL`].
+    #[inline]
+    #[must_use]
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// A number of objects of each class, such as the content of a cell or the inventory of a
+/// player.
+///
+/// It is indexed by [`ObjectClass`] and displayed as the counts separated by spaces, in
+/// the order of [`ObjectClass::ALL`], like in the `bct` and `pin` messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ResourceSet([u32; 7]);
+
+impl ResourceSet {
+    /// Creates a set holding `counts` objects of each class, in the order of
+    /// [`ObjectClass::ALL`].
+    #[inline]
+    #[must_use]
+    pub const fn from_counts(counts: [u32; 7]) -> Self {
+        Self(counts)
+    }
+
+    /// Returns the number of objects of each class, in the order of
+    /// [`ObjectClass::ALL`].
+    #[inline]
+    #[must_use]
+    pub fn counts(&self) -> &[u32; 7] {
+        &self.0
+    }
+
+    /// Adds an object to the set.
+    #[inline]
+    pub fn add(&mut self, class: ObjectClass) {
+        self[class] += 1;
+    }
+
+    /// Removes an object from the set.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the set held no object of this class.
+    pub fn remove(&mut self, class: ObjectClass) -> bool {
+        let count = &mut self[class];
+        if *count == 0 {
+            return false;
+        }
+        *count -= 1;
+        true
+    }
+
+    /// Adds every object of `other` to the set.
+    pub fn add_all(&mut self, other: &Self) {
+        for (count, &added) in self.0.iter_mut().zip(&other.0) {
+            *count += added;
+        }
+    }
+
+    /// Returns the objects of the set that are not in `other`.
+    ///
+    /// Classes of which `other` holds more objects than the set are left empty.
+    #[must_use]
+    pub fn saturating_sub(&self, other: &Self) -> Self {
+        let mut result = *self;
+        for (count, &removed) in result.0.iter_mut().zip(&other.0) {
+            *count = count.saturating_sub(removed);
+        }
+        result
+    }
+
+    /// Returns whether the set holds at least as many objects of each class as `other`.
+    #[must_use]
+    pub fn contains(&self, other: &Self) -> bool {
+        self.0
+            .iter()
+            .zip(&other.0)
+            .all(|(&count, &needed)| count >= needed)
+    }
+
+    /// Returns whether the set holds no object at all.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|&count| count == 0)
+    }
+
+    /// Returns the number of objects of each class, in the order of
+    /// [`ObjectClass::ALL`].
+    pub fn iter(&self) -> impl Iterator<Item = (ObjectClass, u32)> + '_ {
+        ObjectClass::ALL.into_iter().zip(self.0.iter().copied())
+    }
+}
+
+impl Index<ObjectClass> for ResourceSet {
+    type Output = u32;
+
+    #[inline]
+    fn index(&self, class: ObjectClass) -> &u32 {
+        &self.0[class.index()]
+    }
+}
+
+impl IndexMut<ObjectClass> for ResourceSet {
+    #[inline]
+    fn index_mut(&mut self, class: ObjectClass) -> &mut u32 {
+        &mut self.0[class.index()]
+    }
+}
+
+impl FromIterator<ObjectClass> for ResourceSet {
+    fn from_iter<I: IntoIterator<Item = ObjectClass>>(iter: I) -> Self {
+        let mut set = Self::default();
+        for class in iter {
+            set.add(class);
+        }
+        set
+    }
+}
+
+impl Display for ResourceSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, count) in self.0.iter().enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", count)?;
+        }
+        Ok(())
+    }
+}
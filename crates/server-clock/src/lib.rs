@@ -0,0 +1,117 @@
+//! The estimation of the round-trip time to a Zappy server and of when its ticks happen.
+
+#![no_std]
+#![deny(clippy::unwrap_used, unsafe_op_in_unsafe_fn)]
+#![warn(missing_docs, clippy::must_use_candidate)]
+
+use core::time::Duration;
+
+/// The weight of a new measurement in the estimation of when ticks happen.
+const PHASE_WEIGHT: f64 = 0.25;
+
+/// How fast the estimation of the round-trip time rises towards slower measurements.
+///
+/// Measurements faster than the estimation replace it right away: the fastest responses
+/// are the ones that waited the least for a tick.
+const ROUND_TRIP_RISE_WEIGHT: f64 = 1.0 / 32.0;
+
+/// Estimates the round-trip time to the server and when its ticks happen.
+///
+/// The estimation relies on the commands that last no tick, such as `connect_nbr`: the
+/// server answers them at its next tick, so their response leaves the server right when
+/// a tick is simulated. A command sent just before a tick is answered after the
+/// round-trip time alone, and every response arrives half a round trip after a tick.
+///
+/// The server executes the commands of a player in order: a command that lasts no tick
+/// still waits for the ones sent before it. Only responses to commands sent while no
+/// other command was waiting for its response may be recorded.
+///
+/// Times are measured from an epoch chosen by the caller, so that this does not depend
+/// on a particular clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ServerClock {
+    /// The estimated round-trip time to the server, once a response has been timed.
+    round_trip: Option<Duration>,
+    /// The duration of a tick, once known.
+    tick_period: Option<Duration>,
+    /// The estimated time of a tick, once a response has been timed.
+    tick: Option<Duration>,
+}
+
+impl ServerClock {
+    /// Creates a new [`ServerClock`] knowing nothing about the server.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            round_trip: None,
+            tick_period: None,
+            tick: None,
+        }
+    }
+
+    /// Sets the duration of a tick, as announced by the server or estimated otherwise.
+    pub fn set_tick_period(&mut self, period: Duration) {
+        self.tick_period = Some(period);
+    }
+
+    /// Records the response to a command answered at the next tick, sent at `sent` and
+    /// received at `received`.
+    ///
+    /// The command must have been sent while no other command was waiting for its
+    /// response.
+    pub fn record_immediate_response(&mut self, sent: Duration, received: Duration) {
+        let sample = received.saturating_sub(sent);
+        let round_trip = match self.round_trip {
+            Some(estimate) if sample > estimate => {
+                estimate + (sample - estimate).mul_f64(ROUND_TRIP_RISE_WEIGHT)
+            }
+            _ => sample,
+        };
+        self.round_trip = Some(round_trip);
+
+        let tick = received.saturating_sub(round_trip / 2);
+        self.tick = Some(match (self.tick, self.tick_period) {
+            (Some(estimate), Some(period)) if !period.is_zero() => {
+                let period = period.as_secs_f64();
+                let elapsed = tick.as_secs_f64() - estimate.as_secs_f64();
+                // The tick the response followed is the predicted one closest to it.
+                let cycles = elapsed / period;
+                let nearest = if cycles < 0.0 {
+                    (cycles - 0.5) as i64
+                } else {
+                    (cycles + 0.5) as i64
+                } as f64;
+                let error = elapsed - nearest * period;
+                let corrected = estimate.as_secs_f64() + nearest * period + error * PHASE_WEIGHT;
+                Duration::from_secs_f64(corrected.max(0.0))
+            }
+            _ => tick,
+        });
+    }
+
+    /// Returns the estimated round-trip time to the server, once a response has been
+    /// timed.
+    #[must_use]
+    pub fn round_trip(&self) -> Option<Duration> {
+        self.round_trip
+    }
+
+    /// Returns how long after `now` the server simulates its next tick, once both the
+    /// duration of a tick and a response are known.
+    ///
+    /// A command sent right away reaches the server half a round trip later: to be
+    /// executed at a given tick, it must be sent that long before.
+    #[must_use]
+    pub fn next_tick_in(&self, now: Duration) -> Option<Duration> {
+        let period = self.tick_period.filter(|period| !period.is_zero())?;
+        let tick = self.tick?;
+        let elapsed = now.as_secs_f64() - tick.as_secs_f64();
+        let into_period = elapsed % period.as_secs_f64();
+        let into_period = if into_period < 0.0 {
+            into_period + period.as_secs_f64()
+        } else {
+            into_period
+        };
+        Some(period.saturating_sub(Duration::from_secs_f64(into_period)))
+    }
+}
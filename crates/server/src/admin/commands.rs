@@ -0,0 +1,88 @@
+//! Defines the commands that an admin may send to the server.
+
+use core::str::FromStr;
+
+use crate::client::slice_split_once;
+use crate::state::{is_valid_tick_frequency, ObjectClass, PlayerId};
+
+/// A command that an admin may send to the server.
+#[derive(Debug)]
+pub enum AdminCommand {
+    /// The `pause` command.
+    Pause,
+    /// The `resume` command.
+    Resume,
+    /// The `sst <frequency>` command.
+    SetTimeUnit(f32),
+    /// The `kill #<player>` command.
+    Kill(PlayerId),
+    /// The `tp #<player> <x> <y>` command.
+    Teleport(PlayerId, u32, u32),
+    /// The `spawn <x> <y> <object>` command.
+    Spawn(u32, u32, ObjectClass),
+    /// The `stats` command.
+    Stats,
+    /// The `conns` command.
+    Connections,
+    /// The `save` command.
+    Save,
+    /// The `journal [#<player>]` command.
+    Journal(Option<PlayerId>),
+}
+
+impl AdminCommand {
+    /// Parses a command from the provided line.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the command is unknown or if its arguments are invalid.
+    pub fn parse(line: &[u8]) -> Option<Self> {
+        let (cmd_name, args) = slice_split_once(line, b' ').unwrap_or((line, b""));
+        let mut args = args.split(|&b| b == b' ');
+
+        let cmd = match cmd_name {
+            b"pause" => Self::Pause,
+            b"resume" => Self::Resume,
+            b"sst" => Self::SetTimeUnit(
+                parse(args.next()?).filter(|&freq| is_valid_tick_frequency(freq))?,
+            ),
+            b"kill" => Self::Kill(parse_player(args.next()?)?),
+            b"tp" => Self::Teleport(
+                parse_player(args.next()?)?,
+                parse(args.next()?)?,
+                parse(args.next()?)?,
+            ),
+            b"spawn" => Self::Spawn(
+                parse(args.next()?)?,
+                parse(args.next()?)?,
+                ObjectClass::from_arg(args.next()?)?,
+            ),
+            b"stats" => Self::Stats,
+            b"conns" => Self::Connections,
+            b"save" => Self::Save,
+            b"journal" => Self::Journal(match args.next() {
+                None | Some(b"") => None,
+                Some(arg) => Some(parse_player(arg)?),
+            }),
+            _ => return None,
+        };
+
+        // Trailing arguments are most likely a typo, better not guess what was meant.
+        // Commands without arguments still yield a single empty one.
+        if args.next().is_some_and(|arg| !arg.is_empty()) {
+            return None;
+        }
+
+        Some(cmd)
+    }
+}
+
+/// Parses a value from an argument.
+fn parse<T: FromStr>(arg: &[u8]) -> Option<T> {
+    core::str::from_utf8(arg).ok()?.parse().ok()
+}
+
+/// Parses a player ID of the form `#<id>` (the `#` being optional).
+fn parse_player(arg: &[u8]) -> Option<PlayerId> {
+    parse(arg.strip_prefix(b"#").unwrap_or(arg))
+}
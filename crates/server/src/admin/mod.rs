@@ -0,0 +1,182 @@
+//! Handles the admin console, listening on its own port.
+//!
+//! The admin console is kept separate from the graphics protocol so that observers cannot
+//! take control of the game. Sessions must authenticate with the key passed on the
+//! command-line before sending any command.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::sync::Arc;
+use core::fmt::Write;
+use core::sync::atomic::Ordering::Relaxed;
+
+use crate::client::{Client, ClientError};
+use crate::gfx_connection::messages;
+use crate::server::Server;
+use crate::state::state;
+
+mod commands;
+
+pub use self::commands::*;
+
+/// The maximum number of admin connections waiting to be accepted.
+const ADMIN_BACKLOG: u32 = 8;
+
+/// Runs the admin console on the provided port.
+///
+/// Sessions must send `key` to authenticate.
+pub async fn run_admin_server(port: u16, key: Arc<[u8]>) {
+    ft_log::info!("starting up the admin console on port {port}");
+
+    let server = match Server::new(port, ADMIN_BACKLOG) {
+        Ok(ok) => ok,
+        Err(err) => {
+            ft_log::error!("failed to create the admin TCP server: {err}");
+            return;
+        }
+    };
+
+    loop {
+        let (conn, address) = match server.accept().await {
+            Ok(ok) => ok,
+            Err(err) => {
+                ft_log::error!("failed to accept an admin connection: {err}");
+                continue;
+            }
+        };
+
+        ft_async::EXECUTOR.spawn(
+            ft_async::Priority::Normal,
+            handle_connection(conn, address, Arc::clone(&key)),
+        );
+    }
+}
+
+/// Handles a connection to the admin console.
+async fn handle_connection(conn: ft::File, addr: ft::net::SocketAddr, key: Arc<[u8]>) {
+    let client = Client::new(conn);
+    let id = client.id();
+
+    ft_log::info!("accepted an admin connection from `{addr}` (#{id})");
+    {
+        let mut lock = state();
+        lock.open_connection(id, format!("{addr}").into());
+        lock.set_admin_connection(id);
+    }
+
+    let reason = match crate::catch::catch_panic(handle(client, &key)).await {
+        None => {
+            ft_log::error!("handling admin #{id} panicked, closing the connection");
+            String::from("panicked")
+        }
+        Some(Ok(())) => String::from("closed by the server"),
+        Some(Err(err @ ClientError::Unexpected(_))) => {
+            ft_log::error!("failed to handle admin #{id}: {err}");
+            format!("{err}")
+        }
+        Some(Err(err)) => format!("{err}"),
+    };
+
+    state().close_connection(id, reason.into());
+}
+
+/// Handles an admin session.
+///
+/// # Protocol
+///
+/// 1. The server sends `ADMIN`.
+/// 2. The client sends the admin key. The server answers `ok`, or `ko` before closing the
+///    connection if the key is wrong.
+/// 3. The client sends commands, one per line. Each command is answered with `ok` or
+///    `ko`, possibly preceded by the requested information.
+async fn handle(mut client: Client, key: &[u8]) -> Result<(), ClientError> {
+    let id = client.id();
+
+    client.send_raw(b"ADMIN\n").await?;
+    if !keys_match(client.recv_line().await?, key) {
+        ft_log::warning!("admin #{id} provided an invalid key");
+        client.send_raw(b"ko\n").await?;
+        return Ok(());
+    }
+    client.send_raw(b"ok\n").await?;
+    ft_log::info!("admin #{id} authenticated");
+
+    let mut buf = String::new();
+
+    loop {
+        let line = client.recv_line().await?;
+
+        buf.clear();
+        match AdminCommand::parse(line) {
+            Some(cmd) => {
+                ft_log::info!("admin #{id}: {cmd:?}");
+                execute(cmd, &mut buf).await;
+            }
+            None => {
+                ft_log::trace!(
+                    "admin #{id} sent an invalid command: `{}`",
+                    line.escape_ascii()
+                );
+                buf.push_str("ko\n");
+            }
+        }
+
+        client.send_raw(buf.as_bytes()).await?;
+    }
+}
+
+/// Executes an admin command, writing the response that must be sent back to the admin
+/// into `buf`.
+async fn execute(cmd: AdminCommand, buf: &mut String) {
+    let mut lock = state();
+
+    let success = match cmd {
+        AdminCommand::Pause => {
+            lock.set_paused(true);
+            true
+        }
+        AdminCommand::Resume => {
+            lock.set_paused(false);
+            true
+        }
+        AdminCommand::SetTimeUnit(freq) => {
+            lock.set_tick_frequency(freq);
+            true
+        }
+        AdminCommand::Kill(player) => lock.kill(player),
+        AdminCommand::Teleport(player, x, y) => lock.teleport(player, x, y),
+        AdminCommand::Spawn(x, y, class) => lock.spawn_object(x, y, class),
+        AdminCommand::Stats => {
+            messages::debug_stats(buf, "", &lock);
+            let _ = writeln!(buf, "paused {}", u8::from(lock.is_paused()));
+            true
+        }
+        AdminCommand::Connections => {
+            for connection in lock.connections() {
+                let _ = writeln!(buf, "conn {connection}");
+            }
+            true
+        }
+        AdminCommand::Save => {
+            // The game is saved by the main loop, which knows where to.
+            crate::SAVE_REQUESTED.store(true, Relaxed);
+            true
+        }
+        AdminCommand::Journal(player) => lock.write_journal(buf, "cmd ", player),
+    };
+
+    buf.push_str(if success { "ok\n" } else { "ko\n" });
+}
+
+/// Compares the key sent by a client with the expected one.
+///
+/// The comparison takes the same time wherever the first difference is, so that the key
+/// cannot be guessed one byte at a time.
+fn keys_match(provided: &[u8], expected: &[u8]) -> bool {
+    provided.len() == expected.len()
+        && provided
+            .iter()
+            .zip(expected)
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
@@ -3,9 +3,15 @@
 use core::fmt;
 use core::str::FromStr;
 
+use alloc::boxed::Box;
 use alloc::vec;
 use alloc::vec::Vec;
 use ft::CharStar;
+use ft_log::Verbosity;
+
+use crate::state::{
+    is_valid_tick_frequency, OverflowPolicy, WinCondition, MAX_TICK_FREQUENCY, MIN_TICK_FREQUENCY,
+};
 
 /// An error that can occur while parsing the command-line arguments.
 pub enum Error<'a> {
@@ -15,10 +21,30 @@ pub enum Error<'a> {
     MissingValue(&'a CharStar),
     /// A flag was passed with an invalid value.
     InvalidNumber(&'a CharStar),
+    /// The tick frequency was out of the range accepted by the server.
+    InvalidTickFrequency(f32),
     /// An unknown flag was passed.
     UnknownArgument(&'a CharStar),
     /// A team name was invalid.
     InvalidTeamName(&'a [u8]),
+    /// A team name was empty.
+    EmptyTeamName,
+    /// A team name was longer than the maximum length.
+    TeamNameTooLong(&'a str),
+    /// A team name was reserved for another use.
+    ReservedTeamName(&'a str),
+    /// The same team name was passed twice.
+    DuplicateTeamName(&'a str),
+    /// An unknown win condition was passed.
+    InvalidWinCondition(&'a CharStar),
+    /// An unknown catch-up policy was passed.
+    InvalidCatchUp(&'a CharStar),
+    /// An unknown overflow policy was passed.
+    InvalidOverflowPolicy(&'a CharStar),
+    /// An admin port was provided without an admin key.
+    MissingAdminKey,
+    /// A module filter was not of the form `<module>=<level>`.
+    InvalidLogModule(&'a CharStar),
 }
 
 impl fmt::Display for Error<'_> {
@@ -27,18 +53,114 @@ impl fmt::Display for Error<'_> {
             Self::UnexpectedPositional(arg) => write!(f, "unexpected positional argument: `{arg}`"),
             Self::MissingValue(arg) => write!(f, "missing value for argument: `{arg}`"),
             Self::InvalidNumber(arg) => write!(f, "invalid number for argument: `{arg}`"),
+            Self::InvalidTickFrequency(freq) => write!(
+                f,
+                "invalid tick frequency: `{freq}` (must be between {MIN_TICK_FREQUENCY} and {MAX_TICK_FREQUENCY})"
+            ),
             Self::UnknownArgument(arg) => write!(f, "unknown argument: `{arg}`"),
             Self::InvalidTeamName(name) => write!(
                 f,
                 "invalid team name: `{}`",
                 core::str::from_utf8(name).unwrap_or("<invalid UTF-8>")
             ),
+            Self::EmptyTeamName => write!(f, "team names cannot be empty"),
+            Self::TeamNameTooLong(name) => write!(f, "team name is too long: `{name}`"),
+            Self::ReservedTeamName(name) => write!(f, "team name is reserved: `{name}`"),
+            Self::DuplicateTeamName(name) => write!(f, "duplicate team name: `{name}`"),
+            Self::InvalidWinCondition(arg) => write!(f, "invalid win condition: `{arg}`"),
+            Self::InvalidCatchUp(arg) => write!(f, "invalid catch-up policy: `{arg}`"),
+            Self::InvalidOverflowPolicy(arg) => write!(f, "invalid overflow policy: `{arg}`"),
+            Self::MissingAdminKey => write!(
+                f,
+                "`--admin-port` requires an admin key, passed with the `ZAPPY_ADMIN_KEY` environment variable"
+            ),
+            Self::InvalidLogModule(arg) => write!(f, "invalid module filter: `{arg}`"),
+        }
+    }
+}
+
+/// The prefix of the environment variable holding the key of the admin console.
+const ADMIN_KEY_VAR: &[u8] = b"ZAPPY_ADMIN_KEY=";
+
+/// What the server does with the ticks it could not simulate on time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchUp {
+    /// The late ticks are simulated back to back until the game is on time again.
+    Burst,
+    /// The ticks that are more than a whole period late are not simulated.
+    Skip,
+}
+
+impl CatchUp {
+    /// Parses a catch-up policy from the provided argument: `burst` or `skip`.
+    pub fn from_arg(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            b"burst" => Some(Self::Burst),
+            b"skip" => Some(Self::Skip),
+            _ => None,
         }
     }
 }
 
+impl fmt::Display for CatchUp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Burst => write!(f, "burst"),
+            Self::Skip => write!(f, "skip"),
+        }
+    }
+}
+
+/// What the user asked the server to do.
+pub enum Mode<'a> {
+    /// Run the server with the provided arguments.
+    Run(Box<Args<'a>>),
+    /// Print the usage of the server.
+    Help,
+    /// Print the version of the server.
+    Version,
+}
+
+/// The usage of the server, showing the default value of every option.
+///
+/// The defaults are taken from [`Args::default`], so that the help never drifts from
+/// them.
+pub struct Usage;
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let defaults = Args::default();
+        write!(
+            f,
+            include_str!("usage.txt"),
+            port = defaults.port,
+            width = defaults.width,
+            height = defaults.height,
+            teams = defaults.teams.join(","),
+            max_team_name_len = defaults.max_team_name_len,
+            initial_slot_count = defaults.initial_slot_count,
+            tick_frequency = defaults.tick_frequency,
+            seed = defaults.seed,
+            jitter_ms = defaults.jitter_ms,
+            max_violations = defaults.max_violations,
+            fork_ticks = defaults.fork_ticks,
+            hatch_ticks = defaults.hatch_ticks,
+            hatched_slot_ticks = defaults.hatched_slot_ticks,
+            spawn_radius = defaults.spawn_radius,
+            reconnect_grace_secs = defaults.reconnect_grace_secs,
+            audit_ticks = defaults.audit_ticks,
+            win_condition = defaults.win_condition,
+            catch_up = defaults.catch_up,
+            drift_warning_ms = defaults.drift_warning_ms,
+            gfx_buffer_cap = defaults.gfx_buffer_cap,
+            gfx_overflow = defaults.gfx_overflow,
+            listen_backlog = defaults.listen_backlog,
+        )
+    }
+}
+
 /// Describes the command-line arguments passed by the user to the server.
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub struct Args<'a> {
     /// The TCP port to connect to.
     ///
@@ -46,6 +168,12 @@ pub struct Args<'a> {
     ///
     /// **Default:** `1234`
     pub port: u16,
+    /// The maximum number of connections waiting to be accepted by the server.
+    ///
+    /// Passed using the `--backlog` flag.
+    ///
+    /// **Default:** `128`
+    pub listen_backlog: u32,
     /// The width of the world.
     ///
     /// Passed using the `-x` flag.
@@ -64,6 +192,12 @@ pub struct Args<'a> {
     ///
     /// **Default:** `["Blue", "Red"]`
     pub teams: Vec<&'a str>,
+    /// The maximum length of a team name, in bytes.
+    ///
+    /// Passed using the `-l` flag.
+    ///
+    /// **Default:** `32`
+    pub max_team_name_len: usize,
     /// The initial number of players at the begining of the game, per team.
     ///
     /// Passed using the `-c` flag.
@@ -76,11 +210,178 @@ pub struct Args<'a> {
     ///
     /// **Default:** `10`
     pub tick_frequency: f32,
+    /// The path to a configuration file, re-read when the server receives **SIGHUP**.
+    ///
+    /// Passed using the `-f` flag.
+    ///
+    /// **Default:** none
+    pub config_file: Option<&'a CharStar>,
+    /// The path to a file to which the game is saved when the server stops, or when an
+    /// admin sends `save`.
+    ///
+    /// Passed using the `--save` flag.
+    ///
+    /// **Default:** none
+    pub save_file: Option<&'a CharStar>,
+    /// The path to a file to which every command executed by the players is written when
+    /// the game ends, and when the server stops.
+    ///
+    /// Passed using the `--journal` flag.
+    ///
+    /// **Default:** none
+    pub journal_file: Option<&'a CharStar>,
+    /// The path to a file from which a saved game is resumed.
+    ///
+    /// Passed using the `--load` flag.
+    ///
+    /// **Default:** none
+    pub load_file: Option<&'a CharStar>,
+    /// A file descriptor to which the events of the game are written, one line per event.
+    ///
+    /// Passed using the `-e` flag.
+    ///
+    /// **Default:** none
+    pub event_log_fd: Option<i32>,
+    /// The seed of the random number generator.
+    ///
+    /// Passed using the `-s` flag.
+    ///
+    /// **Default:** `0`
+    pub seed: u64,
+    /// The maximum delay randomly added to the completion of each command, in
+    /// milliseconds.
+    ///
+    /// Passed using the `--jitter` flag, or its `-j` alias.
+    ///
+    /// **Default:** `0`
+    pub jitter_ms: u32,
+    /// The number of invalid commands a player may send in a row before being
+    /// disconnected.
+    ///
+    /// Passed using the `-m` flag.
+    ///
+    /// **Default:** `3`
+    pub max_violations: u32,
+    /// The number of ticks the `fork` command takes.
+    ///
+    /// Passed using the `-d` flag.
+    ///
+    /// **Default:** `42`
+    pub fork_ticks: u32,
+    /// The number of ticks it takes for an egg to hatch.
+    ///
+    /// Passed using the `-b` flag.
+    ///
+    /// **Default:** `600`
+    pub hatch_ticks: u32,
+    /// The number of ticks during which the slot created by a hatched egg stays available,
+    /// `0` meaning forever.
+    ///
+    /// Passed using the `-w` flag.
+    ///
+    /// **Default:** `0`
+    pub hatched_slot_ticks: u32,
+    /// How far from the center of their team's region players spawn, `0` spawning them
+    /// anywhere.
+    ///
+    /// Passed using the `-r` flag.
+    ///
+    /// **Default:** `0`
+    pub spawn_radius: u32,
+    /// The number of seconds during which the state of a disconnected player is kept,
+    /// waiting for them to reconnect, `0` disabling reconnections.
+    ///
+    /// Passed using the `-g` flag.
+    ///
+    /// **Default:** `0`
+    pub reconnect_grace_secs: u32,
+    /// The number of ticks between two checks that no object appeared or vanished, `0`
+    /// disabling the checks.
+    ///
+    /// Passed using the `-u` flag.
+    ///
+    /// **Default:** `0`
+    pub audit_ticks: u32,
+    /// The rule deciding which team wins the game.
+    ///
+    /// Passed using the `-v` flag.
+    ///
+    /// **Default:** `level`
+    pub win_condition: WinCondition,
+    /// What to do with the ticks that could not be simulated on time.
+    ///
+    /// Passed using the `-i` flag.
+    ///
+    /// **Default:** `burst`
+    pub catch_up: CatchUp,
+    /// How late a tick may be simulated before a warning is issued, in milliseconds, `0`
+    /// disabling the warnings.
+    ///
+    /// Passed using the `-z` flag.
+    ///
+    /// **Default:** `1000`
+    pub drift_warning_ms: u32,
+    /// The maximum number of bytes waiting to be written to a graphics monitor.
+    ///
+    /// Passed using the `-o` flag.
+    ///
+    /// **Default:** `1048576`
+    pub gfx_buffer_cap: usize,
+    /// What happens when a graphics monitor exceeds [`Args::gfx_buffer_cap`].
+    ///
+    /// Passed using the `-q` flag.
+    ///
+    /// **Default:** `drop`
+    pub gfx_overflow: OverflowPolicy,
+    /// Whether graphics monitors may connect to the server.
+    ///
+    /// Disabled using the `--no-graphic` flag.
+    ///
+    /// **Default:** `true`
+    pub graphic: bool,
+    /// Whether the number of players on every cell is checked against their positions
+    /// after every command.
+    ///
+    /// Enabled using the `--check-cells` flag.
+    ///
+    /// **Default:** `false`
+    pub check_cells: bool,
+    /// The TCP port on which the admin console listens.
+    ///
+    /// Passed using the `--admin-port` flag, or its `-a` alias.
+    ///
+    /// **Default:** none
+    pub admin_port: Option<u16>,
+    /// The key that admin sessions must provide to authenticate.
+    ///
+    /// Read from the `ZAPPY_ADMIN_KEY` environment variable rather than from the
+    /// command-line, which other users of the machine can read.
+    ///
+    /// **Default:** none
+    pub admin_key: Option<&'a [u8]>,
+    /// The minimum verbosity level of the messages logged by some modules and their
+    /// submodules.
+    ///
+    /// Passed using the `--log-module` flag, once per module.
+    ///
+    /// **Default:** none
+    pub log_modules: Vec<(&'a str, Verbosity)>,
 }
 
 impl<'a> Args<'a> {
-    /// Parses the arguments passed to the program.
-    pub fn parse_args(args: &[&'a CharStar]) -> Result<Self, Error<'a>> {
+    /// Parses the arguments and the environment passed to the program.
+    ///
+    /// `-h`, `--help` and `--version` take precedence over any other argument, even
+    /// invalid ones.
+    pub fn parse_args(args: &[&'a CharStar], env: &[&'a CharStar]) -> Result<Mode<'a>, Error<'a>> {
+        for arg in args.iter().skip(1) {
+            match arg.as_bytes() {
+                b"-h" | b"--help" => return Ok(Mode::Help),
+                b"--version" => return Ok(Mode::Version),
+                _ => (),
+            }
+        }
+
         let mut args = args.iter();
 
         args.next(); // skip the program name
@@ -92,18 +393,81 @@ impl<'a> Args<'a> {
                 return Err(Error::UnexpectedPositional(arg));
             }
 
-            match arg.as_bytes_bounded(4) {
+            match arg.as_bytes_bounded(16) {
                 b"-p" => result.port = parse_number(arg, &mut args)?,
                 b"-x" => result.width = parse_number(arg, &mut args)?,
                 b"-y" => result.height = parse_number(arg, &mut args)?,
                 b"-n" => result.teams = parse_team_names(arg, &mut args)?,
+                b"-l" => result.max_team_name_len = parse_number(arg, &mut args)?,
                 b"-c" => result.initial_slot_count = parse_number(arg, &mut args)?,
                 b"-t" => result.tick_frequency = parse_number(arg, &mut args)?,
+                b"-f" => result.config_file = Some(args.next().ok_or(Error::MissingValue(arg))?),
+                b"-e" => result.event_log_fd = Some(parse_number(arg, &mut args)?),
+                b"-s" => result.seed = parse_number(arg, &mut args)?,
+                b"-j" | b"--jitter" => result.jitter_ms = parse_number(arg, &mut args)?,
+                b"-m" => result.max_violations = parse_number(arg, &mut args)?,
+                b"-d" => result.fork_ticks = parse_number(arg, &mut args)?,
+                b"-b" => result.hatch_ticks = parse_number(arg, &mut args)?,
+                b"-w" => result.hatched_slot_ticks = parse_number(arg, &mut args)?,
+                b"-r" => result.spawn_radius = parse_number(arg, &mut args)?,
+                b"-g" => result.reconnect_grace_secs = parse_number(arg, &mut args)?,
+                b"-u" => result.audit_ticks = parse_number(arg, &mut args)?,
+                b"-v" => {
+                    let value = args.next().ok_or(Error::MissingValue(arg))?;
+                    result.win_condition = WinCondition::from_arg(value.as_bytes())
+                        .ok_or(Error::InvalidWinCondition(value))?;
+                }
+                b"-i" => {
+                    let value = args.next().ok_or(Error::MissingValue(arg))?;
+                    result.catch_up =
+                        CatchUp::from_arg(value.as_bytes()).ok_or(Error::InvalidCatchUp(value))?;
+                }
+                b"-z" => result.drift_warning_ms = parse_number(arg, &mut args)?,
+                b"-o" => result.gfx_buffer_cap = parse_number(arg, &mut args)?,
+                b"-q" => {
+                    let value = args.next().ok_or(Error::MissingValue(arg))?;
+                    result.gfx_overflow = OverflowPolicy::from_arg(value.as_bytes())
+                        .ok_or(Error::InvalidOverflowPolicy(value))?;
+                }
+                b"-a" | b"--admin-port" => result.admin_port = Some(parse_number(arg, &mut args)?),
+                b"--no-graphic" => result.graphic = false,
+                b"--check-cells" => result.check_cells = true,
+                b"--backlog" => result.listen_backlog = parse_number(arg, &mut args)?,
+                b"--save" => result.save_file = Some(args.next().ok_or(Error::MissingValue(arg))?),
+                b"--load" => result.load_file = Some(args.next().ok_or(Error::MissingValue(arg))?),
+                b"--journal" => {
+                    result.journal_file = Some(args.next().ok_or(Error::MissingValue(arg))?);
+                }
+                b"--log-module" => {
+                    let value = args.next().ok_or(Error::MissingValue(arg))?;
+                    let filter =
+                        parse_log_module(value.as_bytes()).ok_or(Error::InvalidLogModule(value))?;
+                    result.log_modules.push(filter);
+                }
                 _ => return Err(Error::UnknownArgument(arg)),
             }
         }
 
-        Ok(result)
+        if !is_valid_tick_frequency(result.tick_frequency) {
+            return Err(Error::InvalidTickFrequency(result.tick_frequency));
+        }
+
+        // The maximum length may be passed after the team names, so they can only be
+        // validated once all the arguments have been parsed.
+        validate_team_names(&result.teams, result.max_team_name_len)?;
+
+        // An admin console without a key would let anyone take control of the game.
+        if result.admin_port.is_some() {
+            result.admin_key = env
+                .iter()
+                .find_map(|var| var.as_bytes().strip_prefix(ADMIN_KEY_VAR))
+                .filter(|key| !key.is_empty());
+            if result.admin_key.is_none() {
+                return Err(Error::MissingAdminKey);
+            }
+        }
+
+        Ok(Mode::Run(Box::new(result)))
     }
 }
 
@@ -111,11 +475,37 @@ impl Default for Args<'_> {
     fn default() -> Self {
         Self {
             port: 1234,
+            listen_backlog: 128,
             width: 32,
             height: 32,
             teams: vec!["Blue", "Red"],
+            max_team_name_len: 32,
             initial_slot_count: 1,
             tick_frequency: 10.0,
+            config_file: None,
+            save_file: None,
+            load_file: None,
+            journal_file: None,
+            event_log_fd: None,
+            seed: 0,
+            jitter_ms: 0,
+            max_violations: 3,
+            fork_ticks: 42,
+            hatch_ticks: 600,
+            hatched_slot_ticks: 0,
+            spawn_radius: 0,
+            reconnect_grace_secs: 0,
+            audit_ticks: 0,
+            win_condition: WinCondition::MaxLevel,
+            catch_up: CatchUp::Burst,
+            drift_warning_ms: 1000,
+            gfx_buffer_cap: 1 << 20,
+            gfx_overflow: OverflowPolicy::DropOldest,
+            graphic: true,
+            check_cells: false,
+            admin_port: None,
+            admin_key: None,
+            log_modules: Vec::new(),
         }
     }
 }
@@ -134,6 +524,26 @@ where
         .ok_or(Error::InvalidNumber(value))
 }
 
+/// Parses a module filter of the form `<module>=<level>`, such as
+/// `server::gfx_connection=warning`.
+fn parse_log_module(bytes: &[u8]) -> Option<(&str, Verbosity)> {
+    let (module, level) = core::str::from_utf8(bytes).ok()?.split_once('=')?;
+    if module.is_empty() {
+        return None;
+    }
+
+    let level = match level {
+        "trace" => Verbosity::Trace,
+        "info" => Verbosity::Info,
+        "debug" => Verbosity::Debug,
+        "warning" => Verbosity::Warning,
+        "error" => Verbosity::Error,
+        _ => return None,
+    };
+
+    Some((module, level))
+}
+
 /// Parses the team names from the given arguments.
 fn parse_team_names<'a, 'b, I>(arg: &'a CharStar, mut args: I) -> Result<Vec<&'a str>, Error<'a>>
 where
@@ -145,11 +555,6 @@ where
     let teams = args.next().ok_or(Error::MissingValue(arg))?;
     for name in teams.split(b',') {
         let name = core::str::from_utf8(name).map_err(|_| Error::InvalidTeamName(name))?;
-
-        if name == "GRAPHIC" {
-            return Err(Error::InvalidTeamName(name.as_bytes()));
-        }
-
         values.push(name);
     }
 
@@ -159,3 +564,35 @@ where
 
     Ok(values)
 }
+
+/// Returns whether the provided character may appear in a team name.
+fn is_valid_team_name_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'-' || c == b'_'
+}
+
+/// Validates the team names passed to the server.
+///
+/// Team names must be non-empty, unique, at most `max_len` bytes long and made of ASCII
+/// letters, digits, `-` and `_`. `GRAPHIC` is reserved for graphics monitors, whatever its
+/// case.
+fn validate_team_names<'a>(teams: &[&'a str], max_len: usize) -> Result<(), Error<'a>> {
+    for (i, &name) in teams.iter().enumerate() {
+        if name.is_empty() {
+            return Err(Error::EmptyTeamName);
+        }
+        if name.len() > max_len {
+            return Err(Error::TeamNameTooLong(name));
+        }
+        if !name.bytes().all(is_valid_team_name_char) {
+            return Err(Error::InvalidTeamName(name.as_bytes()));
+        }
+        if name.eq_ignore_ascii_case("GRAPHIC") {
+            return Err(Error::ReservedTeamName(name));
+        }
+        if teams[..i].contains(&name) {
+            return Err(Error::DuplicateTeamName(name));
+        }
+    }
+
+    Ok(())
+}
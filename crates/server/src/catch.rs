@@ -0,0 +1,43 @@
+//! Keeps a panicking task from taking the whole server down.
+
+use alloc::boxed::Box;
+use core::future::Future;
+use core::panic::AssertUnwindSafe;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+// The unit tests run on top of the standard library, which unwinds by itself.
+#[cfg(test)]
+use std::panic::catch_unwind;
+#[cfg(not(test))]
+use unwinding::panic::catch_unwind;
+
+/// Returns a future that runs `future`, completing with `None` instead of unwinding if it
+/// panics.
+///
+/// The panic message is printed by the panic handler. Once the panic is caught, `future`
+/// is dropped, which closes the connections it owned and runs its guards.
+pub fn catch_panic<F: Future>(future: F) -> CatchPanic<F> {
+    CatchPanic {
+        inner: Box::pin(future),
+    }
+}
+
+/// See [`catch_panic`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct CatchPanic<F> {
+    /// The future that may panic.
+    inner: Pin<Box<F>>,
+}
+
+impl<F: Future> Future for CatchPanic<F> {
+    type Output = Option<F::Output>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let inner = self.inner.as_mut();
+        match catch_unwind(AssertUnwindSafe(move || inner.poll(cx))) {
+            Ok(poll) => poll.map(Some),
+            Err(_) => Poll::Ready(None),
+        }
+    }
+}
@@ -1,5 +1,6 @@
 //! A simple client abstraction for the Zappy server.
 
+use core::fmt;
 use core::sync::atomic::AtomicUsize;
 use core::sync::atomic::Ordering::Relaxed;
 
@@ -7,6 +8,9 @@ use ft::collections::ReadBuffer;
 
 use crate::player::PlayerError;
 
+/// The ID of the next client to connect.
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
 /// Represents a client connected to the server.
 ///
 /// This type provides a simple abstraction over the TCP connection and allows sending
@@ -25,8 +29,6 @@ pub struct Client {
 impl Client {
     /// Creates a new [`Client`] from the provided file descriptor.
     pub fn new(conn: ft::File) -> Self {
-        static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
-
         Self {
             id: NEXT_ID.fetch_add(1, Relaxed),
             conn,
@@ -34,6 +36,12 @@ impl Client {
         }
     }
 
+    /// Makes sure that the clients connecting from now on get an ID of at least `first`,
+    /// so that they do not share the ID of a player restored from a snapshot.
+    pub fn reserve_ids(first: usize) {
+        NEXT_ID.fetch_max(first, Relaxed);
+    }
+
     /// Returns the ID of the client.
     #[inline]
     pub fn id(&self) -> usize {
@@ -48,13 +56,21 @@ impl Client {
 
     /// Sends the provided buffer to the client.
     pub async fn send_raw(&mut self, buf: &[u8]) -> ft::Result<()> {
-        ft_async::futures::ready_for_writing(*self.conn).await;
+        ft_async::futures::ready_for_writing(*self.conn).await?;
         ft_async::futures::write_all(*self.conn, buf).await
     }
 
+    /// Sends the reply the protocol expects when `err` ends the connection, then returns
+    /// the error so that the connection gets closed.
+    pub async fn reject<T>(&mut self, err: PlayerError) -> Result<T, ClientError> {
+        // The connection is closed anyway, failing to send the reply changes nothing.
+        let _ = self.send_raw(err.reply()).await;
+        Err(err.into())
+    }
+
     /// Reads an entire line from the client, returning it.
     pub async fn recv_line(&mut self) -> ft::Result<&[u8]> {
-        ft_async::futures::ready_for_reading(*self.conn).await;
+        ft_async::futures::ready_for_reading(*self.conn).await?;
         ft_async::futures::read_line(*self.conn, &mut self.read_buf).await
     }
 }
@@ -69,6 +85,16 @@ pub enum ClientError {
     Player(PlayerError),
 }
 
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Unexpected(err) => write!(f, "{err}"),
+            Self::Disconnected => write!(f, "disconnected"),
+            Self::Player(err) => write!(f, "{err}"),
+        }
+    }
+}
+
 impl From<ft::Errno> for ClientError {
     fn from(value: ft::Errno) -> Self {
         match value {
@@ -84,3 +110,11 @@ impl From<PlayerError> for ClientError {
         Self::Player(value)
     }
 }
+
+/// Splits the provided slice into two parts at the first occurrence of the provided delimiter.
+pub fn slice_split_once(slice: &[u8], delim: u8) -> Option<(&[u8], &[u8])> {
+    slice
+        .iter()
+        .position(|&b| b == delim)
+        .map(|pos| (&slice[..pos], &slice[pos + 1..]))
+}
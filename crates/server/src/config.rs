@@ -0,0 +1,227 @@
+//! Parsing logic of the configuration file.
+//!
+//! The configuration file is a list of `key=value` lines. Empty lines and lines starting
+//! with `#` are ignored.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use ft::collections::ReadBuffer;
+use ft::CharStar;
+
+use crate::state::{is_valid_tick_frequency, ObjectClass, ResourceSet, State};
+
+/// The highest average number of objects of a class per cell.
+const MAX_DENSITY: f32 = 10.0;
+
+/// An error that can occur while loading the configuration file.
+pub enum Error {
+    /// The configuration file could not be read.
+    Io(ft::Errno),
+    /// A line is not of the form `key=value`.
+    InvalidLine(usize),
+    /// A line contains an unknown key.
+    UnknownKey(usize),
+    /// A line contains an invalid value.
+    InvalidValue(usize),
+}
+
+impl From<ft::Errno> for Error {
+    #[inline]
+    fn from(value: ft::Errno) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Io(err) => write!(f, "failed to read the configuration file: {err}"),
+            Self::InvalidLine(line) => write!(f, "line {line}: expected `key=value`"),
+            Self::UnknownKey(line) => write!(f, "line {line}: unknown key"),
+            Self::InvalidValue(line) => write!(f, "line {line}: invalid value"),
+        }
+    }
+}
+
+/// The settings that may be changed through the configuration file.
+///
+/// Settings that are not specified in the file are left untouched when the configuration
+/// is applied.
+#[derive(Debug, Default, Clone)]
+pub struct Config {
+    /// The number of ticks simulated by the server, per second.
+    ///
+    /// Specified using the `tick_frequency` key.
+    pub tick_frequency: Option<f32>,
+    /// The number of ticks the `fork` command takes.
+    ///
+    /// Specified using the `fork_ticks` key.
+    pub fork_ticks: Option<u32>,
+    /// The number of ticks it takes for an egg to hatch.
+    ///
+    /// Specified using the `hatch_ticks` key.
+    pub hatch_ticks: Option<u32>,
+    /// The number of ticks during which the slot created by a hatched egg stays available,
+    /// `0` meaning forever.
+    ///
+    /// Specified using the `hatched_slot_ticks` key.
+    pub hatched_slot_ticks: Option<u32>,
+    /// The number of ticks between two respawns of the objects of the world, `0`
+    /// disabling them.
+    ///
+    /// Specified using the `respawn_ticks` key.
+    pub respawn_ticks: Option<u32>,
+    /// The average number of objects of some classes per cell, restored by respawns.
+    ///
+    /// Specified using one `density.<object>` key per class, such as `density.nourriture`.
+    pub densities: Vec<(ObjectClass, f32)>,
+    /// The objects the players of some teams hold when they spawn, by team name.
+    ///
+    /// Specified using one `starting_inventory.<team>` key per team, whose value is the
+    /// number of objects of each class, from food to thystame, separated by spaces.
+    pub starting_inventories: Vec<(Box<str>, ResourceSet)>,
+}
+
+impl Config {
+    /// Loads the configuration file at the provided path.
+    pub fn load(path: &CharStar) -> Result<Self, Error> {
+        let file = ft::File::open(path)?;
+        let mut buf = ReadBuffer::new();
+
+        loop {
+            buf.reserve(1024).map_err(ft::Errno::from)?;
+            if buf.fill_with_fd(*file)? == 0 {
+                break;
+            }
+        }
+
+        Self::parse(buf.pending())
+    }
+
+    /// Parses the content of a configuration file.
+    pub fn parse(content: &[u8]) -> Result<Self, Error> {
+        let mut result = Self::default();
+
+        for (index, line) in content.split(|&b| b == b'\n').enumerate() {
+            let line_number = index + 1;
+            let line = line.trim_ascii();
+
+            if line.is_empty() || line.starts_with(b"#") {
+                continue;
+            }
+
+            let (key, value) = line
+                .iter()
+                .position(|&b| b == b'=')
+                .map(|pos| (line[..pos].trim_ascii(), line[pos + 1..].trim_ascii()))
+                .ok_or(Error::InvalidLine(line_number))?;
+
+            match key {
+                b"tick_frequency" => {
+                    result.tick_frequency = Some(
+                        parse_value(value)
+                            .filter(|&freq| is_valid_tick_frequency(freq))
+                            .ok_or(Error::InvalidValue(line_number))?,
+                    );
+                }
+                b"fork_ticks" => result.fork_ticks = Some(parse_u32(value, line_number)?),
+                b"hatch_ticks" => result.hatch_ticks = Some(parse_u32(value, line_number)?),
+                b"hatched_slot_ticks" => {
+                    result.hatched_slot_ticks = Some(parse_u32(value, line_number)?);
+                }
+                b"respawn_ticks" => result.respawn_ticks = Some(parse_u32(value, line_number)?),
+                _ if key.starts_with(b"density.") => {
+                    let class = ObjectClass::from_arg(&key[b"density.".len()..])
+                        .ok_or(Error::UnknownKey(line_number))?;
+                    let density = parse_value(value)
+                        .filter(|density: &f32| (0.0..=MAX_DENSITY).contains(density))
+                        .ok_or(Error::InvalidValue(line_number))?;
+                    result.densities.push((class, density));
+                }
+                _ => match key.strip_prefix(b"starting_inventory.") {
+                    Some(team) => {
+                        let team = core::str::from_utf8(team)
+                            .ok()
+                            .filter(|team| !team.is_empty())
+                            .ok_or(Error::InvalidLine(line_number))?;
+                        let inventory = parse_objects(value, line_number)?;
+                        result.starting_inventories.push((team.into(), inventory));
+                    }
+                    None => return Err(Error::UnknownKey(line_number)),
+                },
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Applies the configuration to the provided state.
+    pub fn apply(&self, state: &mut State) {
+        if let Some(freq) = self.tick_frequency {
+            ft_log::info!("tick frequency set to {freq}hz");
+            state.set_tick_frequency(freq);
+        }
+
+        let mut timing = state.egg_timing();
+        if let Some(ticks) = self.fork_ticks {
+            ft_log::info!("fork delay set to {ticks} ticks");
+            timing.fork_ticks = ticks;
+        }
+        if let Some(ticks) = self.hatch_ticks {
+            ft_log::info!("hatch delay set to {ticks} ticks");
+            timing.hatch_ticks = ticks;
+        }
+        if let Some(ticks) = self.hatched_slot_ticks {
+            ft_log::info!("hatched slot lifetime set to {ticks} ticks");
+            timing.slot_ticks = ticks;
+        }
+        state.set_egg_timing(timing);
+
+        if let Some(ticks) = self.respawn_ticks {
+            ft_log::info!("respawn period set to {ticks} ticks");
+            state.set_respawn_ticks(ticks);
+        }
+        for &(class, density) in &self.densities {
+            ft_log::info!("density of {} set to {density} per cell", class.name());
+            state.set_density(class, density);
+        }
+
+        for (team, inventory) in &self.starting_inventories {
+            match state.team_id_by_name(team) {
+                Some(team_id) => {
+                    ft_log::info!("starting inventory of team `{team}` set to {inventory}");
+                    state.set_starting_inventory(team_id, *inventory);
+                }
+                None => ft_log::warning!("no team named `{team}`, starting inventory ignored"),
+            }
+        }
+    }
+}
+
+/// Parses a value from the configuration file.
+fn parse_value<T: core::str::FromStr>(value: &[u8]) -> Option<T> {
+    core::str::from_utf8(value).ok()?.parse().ok()
+}
+
+/// Parses the number of objects of each class, from food to thystame, separated by
+/// spaces.
+fn parse_objects(value: &[u8], line_number: usize) -> Result<ResourceSet, Error> {
+    let mut counts = [0; 7];
+    let mut values = value
+        .split(u8::is_ascii_whitespace)
+        .filter(|value| !value.is_empty());
+    for count in &mut counts {
+        *count = parse_u32(values.next().unwrap_or_default(), line_number)?;
+    }
+    if values.next().is_some() {
+        return Err(Error::InvalidValue(line_number));
+    }
+    Ok(ResourceSet::from_counts(counts))
+}
+
+/// Parses a number of ticks from the configuration file.
+fn parse_u32(value: &[u8], line_number: usize) -> Result<u32, Error> {
+    parse_value(value).ok_or(Error::InvalidValue(line_number))
+}
@@ -0,0 +1,177 @@
+//! Defines the commands that a graphics monitor may send to the server.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+use super::messages;
+use crate::client::slice_split_once;
+use crate::state::{is_valid_tick_frequency, PlayerState, State};
+
+/// A command that a graphics monitor may send to the server.
+#[derive(Debug)]
+pub enum GfxCommand<'a> {
+    /// The `bct <x> <y>` command.
+    TileContent(&'a [u8]),
+    /// The `mct` command.
+    MapContent,
+    /// The `tst <team>` command.
+    TeamStats(&'a [u8]),
+    /// The `dbg` command.
+    Debug,
+    /// The `sst <frequency>` command.
+    SetTimeUnit(&'a [u8]),
+    /// The `ppo #<player>` command.
+    PlayerPosition(&'a [u8]),
+    /// The `plv #<player>` command.
+    PlayerLevel(&'a [u8]),
+    /// The `pin #<player>` command.
+    PlayerInventory(&'a [u8]),
+    /// The `pqc #<player>` command.
+    PlayerQueue(&'a [u8]),
+    /// The `json` command, switching between the text and JSON encodings.
+    ToggleJson,
+    /// The `pqc` command without arguments, switching whether the number of commands
+    /// queued by every player is pushed to the monitor whenever it changes.
+    ToggleQueues,
+}
+
+impl<'a> GfxCommand<'a> {
+    /// Parses a command from the provided line.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the command is unknown.
+    pub fn parse(line: &'a [u8]) -> Option<Self> {
+        let (cmd_name, args) = slice_split_once(line, b' ').unwrap_or((line, b""));
+
+        match cmd_name {
+            b"bct" => Some(Self::TileContent(args)),
+            b"mct" => Some(Self::MapContent),
+            b"tst" => Some(Self::TeamStats(args)),
+            b"dbg" => Some(Self::Debug),
+            b"sst" => Some(Self::SetTimeUnit(args)),
+            b"ppo" => Some(Self::PlayerPosition(args)),
+            b"plv" => Some(Self::PlayerLevel(args)),
+            b"pin" => Some(Self::PlayerInventory(args)),
+            b"pqc" if args.is_empty() => Some(Self::ToggleQueues),
+            b"pqc" => Some(Self::PlayerQueue(args)),
+            b"json" => Some(Self::ToggleJson),
+            _ => None,
+        }
+    }
+
+    /// Executes the command, writing the response that must be sent back to the monitor
+    /// into `buf`.
+    ///
+    /// When the parameters of the command are invalid, the `sbp` response is written.
+    ///
+    /// # Panics
+    ///
+    /// If the command is `mct`, which the connection handler streams instead.
+    pub fn execute(&self, state: &mut State, buf: &mut String) {
+        match *self {
+            Self::TileContent(args) => {
+                let world = state.world();
+                let Some((x, y)) =
+                    parse_position(args).filter(|&(x, y)| x < world.width() && y < world.height())
+                else {
+                    ft_log::trace!("`bct`: invalid position");
+                    buf.push_str("sbp\n");
+                    return;
+                };
+
+                messages::tile_content(buf, x, y, world.cell(x, y));
+            }
+            Self::MapContent => {
+                unreachable!("`mct` is streamed by the connection handler, see `send_map_content`")
+            }
+            Self::TeamStats(name) => {
+                let Some(team) = core::str::from_utf8(name)
+                    .ok()
+                    .and_then(|name| state.team_id_by_name(name))
+                else {
+                    ft_log::trace!("`tst`: unknown team");
+                    buf.push_str("sbp\n");
+                    return;
+                };
+
+                let stats = state.team_stats(team);
+                let _ = writeln!(
+                    buf,
+                    "tst {} {} {} {} {}",
+                    state.team_name(team),
+                    stats.players,
+                    stats.eggs,
+                    stats.levels,
+                    stats.available_slots,
+                );
+            }
+            Self::Debug => messages::debug_stats(buf, "smg dbg ", state),
+            Self::SetTimeUnit(arg) => {
+                let Some(freq) = core::str::from_utf8(arg)
+                    .ok()
+                    .and_then(|arg| arg.parse().ok())
+                    .filter(|&freq| is_valid_tick_frequency(freq))
+                else {
+                    ft_log::trace!("`sst`: invalid frequency");
+                    buf.push_str("sbp\n");
+                    return;
+                };
+
+                ft_log::info!("tick frequency set to {freq}hz by a graphics monitor");
+                // Every graphics monitor, this one included, receives `sgt`.
+                state.set_tick_frequency(freq);
+            }
+            Self::PlayerPosition(arg) => {
+                if let Some(player) = find_player(state, arg, buf) {
+                    messages::player_position(buf, player);
+                }
+            }
+            Self::PlayerLevel(arg) => {
+                if let Some(player) = find_player(state, arg, buf) {
+                    messages::player_level(buf, player);
+                }
+            }
+            Self::PlayerInventory(arg) => {
+                if let Some(player) = find_player(state, arg, buf) {
+                    messages::player_inventory(buf, player);
+                }
+            }
+            Self::PlayerQueue(arg) => {
+                if let Some(player) = find_player(state, arg, buf) {
+                    messages::player_queue(buf, player);
+                }
+            }
+            // The encoding and the subscriptions belong to the connection, the handler
+            // switches them.
+            Self::ToggleJson | Self::ToggleQueues => (),
+        }
+    }
+}
+
+/// Parses a position of the form `<x> <y>`.
+fn parse_position(args: &[u8]) -> Option<(u32, u32)> {
+    let (x, y) = slice_split_once(args, b' ')?;
+    let x = core::str::from_utf8(x).ok()?.parse().ok()?;
+    let y = core::str::from_utf8(y).ok()?.parse().ok()?;
+    Some((x, y))
+}
+
+/// Finds the player designated by the argument of a command, of the form `#<id>` (the `#`
+/// being optional).
+///
+/// When no such player is connected, the `sbp` response is written to `buf`.
+fn find_player<'s>(state: &'s State, arg: &[u8], buf: &mut String) -> Option<&'s PlayerState> {
+    let arg = arg.strip_prefix(b"#").unwrap_or(arg);
+    let player = core::str::from_utf8(arg)
+        .ok()
+        .and_then(|id| id.parse().ok())
+        .and_then(|id| state.player(id));
+
+    if player.is_none() {
+        ft_log::trace!("unknown player: `{}`", arg.escape_ascii());
+        buf.push_str("sbp\n");
+    }
+
+    player
+}
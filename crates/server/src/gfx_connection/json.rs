@@ -0,0 +1,238 @@
+//! Encoding of the messages sent to graphics monitors as JSON lines.
+//!
+//! Monitors that sent the `json` command receive every message as a compact JSON object
+//! instead of a line of text. Messages are always produced as text first, then encoded,
+//! so that both encodings cannot drift apart.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+/// How a field of a message is encoded.
+#[derive(Clone, Copy)]
+enum Field {
+    /// A number, or a player or egg ID of the form `#<id>`.
+    Number(&'static str),
+    /// A single word, as a string.
+    Word(&'static str),
+    /// The rest of the line, as a string.
+    Rest(&'static str),
+    /// The seven resource quantities of a tile or inventory, as an array.
+    Resources,
+}
+
+use Field::{Number, Resources, Rest, Word};
+
+/// Returns the fields of the message with the provided tag, or `None` if the message is
+/// unknown.
+fn fields(tag: &str) -> Option<&'static [Field]> {
+    Some(match tag {
+        "msz" => &[Number("x"), Number("y")],
+        "bct" => &[Number("x"), Number("y"), Resources],
+        "sgt" => &[Number("t")],
+        "tna" => &[Rest("name")],
+        "tst" => &[
+            Word("team"),
+            Number("players"),
+            Number("eggs"),
+            Number("levels"),
+            Number("slots"),
+        ],
+        "pnw" => &[
+            Number("id"),
+            Number("x"),
+            Number("y"),
+            Number("o"),
+            Number("level"),
+            Rest("team"),
+        ],
+        "tsi" => &[Word("team"), Resources],
+        "ppo" => &[Number("id"), Number("x"), Number("y"), Number("o")],
+        "plv" => &[Number("id"), Number("level")],
+        "pqc" => &[Number("id"), Number("commands")],
+        "pin" => &[Number("id"), Number("x"), Number("y"), Resources],
+        "pgt" | "pdr" => &[Number("id"), Number("resource")],
+        "pdi" | "pex" => &[Number("id")],
+        "enw" => &[Number("egg"), Number("id"), Number("x"), Number("y")],
+        "eht" | "edi" => &[Number("egg")],
+        "pic" => &[Number("x"), Number("y"), Number("level"), Rest("players")],
+        "pie" => &[Number("x"), Number("y"), Number("result")],
+        "smg" => &[Rest("text")],
+        "suc" | "sbp" => &[],
+        _ => return None,
+    })
+}
+
+/// Encodes every line of `text` as a JSON object, appending them to `out`.
+///
+/// Whether a field is a number or a string depends on its position in the message, never
+/// on its content: team names and texts are always strings. Numeric fields are written
+/// as-is when they follow the JSON number grammar, and as strings otherwise. Unknown
+/// messages are encoded with their arguments as an array of strings.
+pub fn encode(text: &str, out: &mut String) {
+    for line in text.lines() {
+        let (tag, mut args) = line.split_once(' ').unwrap_or((line, ""));
+
+        out.push_str("{\"msg\":");
+        write_string(out, tag);
+
+        let Some(fields) = fields(tag) else {
+            out.push_str(",\"args\":[");
+            for (i, arg) in args.split(' ').filter(|a| !a.is_empty()).enumerate() {
+                if i != 0 {
+                    out.push(',');
+                }
+                write_string(out, arg);
+            }
+            out.push_str("]}\n");
+            continue;
+        };
+
+        for field in fields {
+            match *field {
+                Number(name) => {
+                    let (value, rest) = args.split_once(' ').unwrap_or((args, ""));
+                    args = rest;
+                    let _ = write!(out, ",\"{name}\":");
+                    write_value(out, value.strip_prefix('#').unwrap_or(value));
+                }
+                Word(name) => {
+                    let (value, rest) = args.split_once(' ').unwrap_or((args, ""));
+                    args = rest;
+                    let _ = write!(out, ",\"{name}\":");
+                    write_string(out, value);
+                }
+                Rest(name) => {
+                    let _ = write!(out, ",\"{name}\":");
+                    write_string(out, args);
+                    args = "";
+                }
+                Resources => {
+                    out.push_str(",\"resources\":[");
+                    for (i, value) in args.split(' ').enumerate() {
+                        if i != 0 {
+                            out.push(',');
+                        }
+                        write_value(out, value);
+                    }
+                    out.push(']');
+                    args = "";
+                }
+            }
+        }
+
+        out.push_str("}\n");
+    }
+}
+
+/// Writes the value of a numeric field, as a string if it is not a valid JSON number.
+fn write_value(out: &mut String, value: &str) {
+    if is_json_number(value) {
+        out.push_str(value);
+    } else {
+        write_string(out, value);
+    }
+}
+
+/// Returns whether `value` follows the JSON number grammar: an optional minus sign, an
+/// integer without leading zeros, then an optional fraction and an optional exponent.
+fn is_json_number(value: &str) -> bool {
+    let mut rest = value.as_bytes();
+    if let [b'-', tail @ ..] = rest {
+        rest = tail;
+    }
+
+    rest = match rest {
+        [b'0', tail @ ..] => tail,
+        [b'1'..=b'9', ..] => skip_digits(rest),
+        _ => return false,
+    };
+    if let [b'.', tail @ ..] = rest {
+        rest = skip_digits(tail);
+        if rest.len() == tail.len() {
+            return false;
+        }
+    }
+    if let [b'e' | b'E', tail @ ..] = rest {
+        let tail = match tail {
+            [b'+' | b'-', tail @ ..] => tail,
+            tail => tail,
+        };
+        rest = skip_digits(tail);
+        if rest.len() == tail.len() {
+            return false;
+        }
+    }
+
+    rest.is_empty()
+}
+
+/// Returns `bytes` without its leading ASCII digits.
+fn skip_digits(bytes: &[u8]) -> &[u8] {
+    let count = bytes.iter().take_while(|b| b.is_ascii_digit()).count();
+    &bytes[count..]
+}
+
+/// Writes a JSON string, escaping the characters that need to be.
+fn write_string(out: &mut String, value: &str) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the JSON encoding of `line`.
+    fn encode_line(line: &str) -> String {
+        let mut out = String::new();
+        encode(line, &mut out);
+        out
+    }
+
+    #[test]
+    fn only_json_numbers_are_written_bare() {
+        for number in ["0", "7", "-3", "12.5", "0.25", "1e5", "2E-3", "1.5e+2"] {
+            assert!(is_json_number(number), "{number}");
+        }
+        for other in [
+            "", "-", "007", "+5", ".5", "5.", "1e", "1e+", "inf", "NaN", "0x1", "1 ",
+        ] {
+            assert!(!is_json_number(other), "{other}");
+        }
+    }
+
+    #[test]
+    fn team_names_are_always_strings() {
+        assert_eq!(
+            encode_line("pnw #4 1 2 3 1 007"),
+            "{\"msg\":\"pnw\",\"id\":4,\"x\":1,\"y\":2,\"o\":3,\"level\":1,\"team\":\"007\"}\n"
+        );
+        assert_eq!(
+            encode_line("tna 1e5"),
+            "{\"msg\":\"tna\",\"name\":\"1e5\"}\n"
+        );
+        assert_eq!(
+            encode_line("tst 42 1 0 1 2"),
+            "{\"msg\":\"tst\",\"team\":\"42\",\"players\":1,\"eggs\":0,\"levels\":1,\"slots\":2}\n"
+        );
+    }
+
+    #[test]
+    fn invalid_numbers_are_written_as_strings() {
+        assert_eq!(encode_line("sgt +5"), "{\"msg\":\"sgt\",\"t\":\"+5\"}\n");
+        assert_eq!(
+            encode_line("bct 0 .5 1 2 3 4 5 6 7"),
+            "{\"msg\":\"bct\",\"x\":0,\"y\":\".5\",\"resources\":[1,2,3,4,5,6,7]}\n"
+        );
+    }
+}
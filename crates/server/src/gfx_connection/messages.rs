@@ -0,0 +1,165 @@
+//! Formatting of the messages sent to graphics monitors.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::state::{Egg, EggId, ObjectClass, PlayerId, PlayerState, ResourceSet, State, WorldCell};
+
+/// Writes the `pnw` message of a player (connection of a new player) to `buf`.
+pub fn player_new(buf: &mut String, player: &PlayerState, team: &str) {
+    let (x, y) = player.position();
+    let _ = writeln!(
+        buf,
+        "pnw #{} {x} {y} {} {} {team}",
+        player.id(),
+        player.orientation().to_gfx(),
+        player.level(),
+    );
+}
+
+/// Writes the `tsi` message of a team (objects its players hold when they spawn) to
+/// `buf`.
+pub fn team_starting_inventory(buf: &mut String, team: &str, inventory: &ResourceSet) {
+    let _ = writeln!(buf, "tsi {team} {inventory}");
+}
+
+/// Writes the `pgt` message of a player (picking up an object of class `class`) to `buf`.
+///
+/// The class is sent as its index in [`ObjectClass::ALL`].
+pub fn player_pickup(buf: &mut String, player: &PlayerState, class: ObjectClass) {
+    let _ = writeln!(buf, "pgt #{} {}", player.id(), class.index());
+}
+
+/// Writes the `pdr` message of a player (dropping an object of class `class`) to `buf`.
+///
+/// The class is sent as its index in [`ObjectClass::ALL`].
+pub fn player_drop(buf: &mut String, player: &PlayerState, class: ObjectClass) {
+    let _ = writeln!(buf, "pdr #{} {}", player.id(), class.index());
+}
+
+/// Writes the `pdi` message of a player (disconnection or death) to `buf`.
+pub fn player_death(buf: &mut String, player: &PlayerState) {
+    let _ = writeln!(buf, "pdi #{}", player.id());
+}
+
+/// Writes the `pex` message of a player (expelling the players on their tile) to `buf`.
+pub fn player_expulsion(buf: &mut String, player: &PlayerState) {
+    let _ = writeln!(buf, "pex #{}", player.id());
+}
+
+/// Writes the `ppo` message of a player (position and orientation) to `buf`.
+pub fn player_position(buf: &mut String, player: &PlayerState) {
+    let (x, y) = player.position();
+    let _ = writeln!(
+        buf,
+        "ppo #{} {x} {y} {}",
+        player.id(),
+        player.orientation().to_gfx(),
+    );
+}
+
+/// Writes the `enw` message of an egg (laid by a player) to `buf`.
+pub fn egg_new(buf: &mut String, egg: &Egg) {
+    let (x, y) = egg.position();
+    let _ = writeln!(buf, "enw #{} #{} {x} {y}", egg.id(), egg.parent());
+}
+
+/// Writes the `eht` message of an egg (hatched, creating a slot in its team) to `buf`.
+pub fn egg_hatched(buf: &mut String, egg: EggId) {
+    let _ = writeln!(buf, "eht #{egg}");
+}
+
+/// Writes the `edi` message of an egg (the slot it created expired) to `buf`.
+pub fn egg_death(buf: &mut String, egg: EggId) {
+    let _ = writeln!(buf, "edi #{egg}");
+}
+
+/// Writes the `plv` message of a player (level) to `buf`.
+pub fn player_level(buf: &mut String, player: &PlayerState) {
+    let _ = writeln!(buf, "plv #{} {}", player.id(), player.level());
+}
+
+/// Writes the `pic` message of an elevation (started on the cell at `(x, y)` by the
+/// `players` of level `level`) to `buf`.
+pub fn incantation_start(buf: &mut String, (x, y): (u32, u32), level: u32, players: &[PlayerId]) {
+    let _ = write!(buf, "pic {x} {y} {level}");
+    for player in players {
+        let _ = write!(buf, " #{player}");
+    }
+    buf.push('\n');
+}
+
+/// Writes the `pie` message of an elevation (ended on the cell at `(x, y)`, successfully
+/// or not) to `buf`.
+pub fn incantation_end(buf: &mut String, (x, y): (u32, u32), success: bool) {
+    let _ = writeln!(buf, "pie {x} {y} {}", u8::from(success));
+}
+
+/// Writes the `pin` message of a player (position and inventory) to `buf`.
+///
+/// Food is counted in whole units, as in the response to `inventaire`: the ticks the
+/// player has left are sent with `ptl`.
+pub fn player_inventory(buf: &mut String, player: &PlayerState) {
+    let (x, y) = player.position();
+    let _ = writeln!(buf, "pin #{} {x} {y} {}", player.id(), player.inventory());
+}
+
+/// Writes the `ptl` message of a player (number of ticks before they starve) to `buf`.
+pub fn player_time_to_live(buf: &mut String, player: &PlayerState) {
+    let _ = writeln!(buf, "ptl #{} {}", player.id(), player.time_to_live());
+}
+
+/// Writes the `pqc` message of a player (number of commands waiting to be executed) to
+/// `buf`.
+pub fn player_queue(buf: &mut String, player: &PlayerState) {
+    let _ = writeln!(buf, "pqc #{} {}", player.id(), player.queued_commands());
+}
+
+/// Writes the `bct` message of a cell (content of the cell) to `buf`.
+pub fn tile_content(buf: &mut String, x: u32, y: u32, cell: &WorldCell) {
+    let _ = writeln!(buf, "bct {x} {y} {}", cell.objects());
+}
+
+/// Writes the debug statistics of the server to `buf`, one `<prefix><name> <value>` line
+/// per statistic.
+pub fn debug_stats(buf: &mut String, prefix: &str, state: &State) {
+    let stats = state.debug_stats();
+    let _ = writeln!(buf, "{prefix}tick {}", stats.tick);
+    let _ = writeln!(
+        buf,
+        "{prefix}tick_lateness {}us",
+        stats.tick_lateness.as_micros()
+    );
+    let _ = writeln!(buf, "{prefix}skipped_ticks {}", stats.skipped_ticks);
+    let _ = writeln!(buf, "{prefix}players {}", stats.players);
+    for (team, name) in state.team_names().enumerate() {
+        let _ = writeln!(
+            buf,
+            "{prefix}team {name} players {}",
+            state.team_stats(team).players
+        );
+    }
+    let _ = writeln!(buf, "{prefix}queued_commands {}", stats.queued_commands);
+    for (name, command) in state.command_metrics().iter() {
+        let _ = writeln!(
+            buf,
+            "{prefix}command {name} executed {} failed {} latency {:.1}",
+            command.executed,
+            command.failed,
+            command.average_latency(),
+        );
+    }
+    let _ = writeln!(buf, "{prefix}eggs {}", stats.eggs);
+    let _ = writeln!(buf, "{prefix}monitors {}", stats.monitors);
+    let _ = writeln!(
+        buf,
+        "{prefix}dropped_gfx_messages {}",
+        stats.dropped_gfx_messages
+    );
+    let _ = writeln!(
+        buf,
+        "{prefix}dropped_log_messages {}",
+        ft_log::dropped_messages()
+    );
+    let _ = writeln!(buf, "{prefix}tasks {}", ft_async::EXECUTOR.task_count());
+}
@@ -0,0 +1,314 @@
+//! Handles the connections of graphical monitors.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::time::Duration;
+
+use ft_async::futures::Budget;
+
+use crate::client::{Client, ClientError};
+use crate::state::{state, GfxWrite};
+
+mod commands;
+mod json;
+pub mod messages;
+
+pub use self::commands::*;
+
+/// The number of cells sent in a single write when dumping the whole map.
+///
+/// This bounds the size of the buffer used to send `mct` responses, whatever the size of
+/// the map.
+const CELLS_PER_CHUNK: usize = 256;
+
+/// The amount of time between two pings sent to the graphics monitors.
+const PING_PERIOD: Duration = Duration::from_secs(5);
+
+/// A guard that unregisters a graphics monitor when dropped.
+struct MonitorGuard(usize);
+
+impl Drop for MonitorGuard {
+    fn drop(&mut self) {
+        state().remove_gfx_monitor(self.0);
+    }
+}
+
+/// Handles a graphics monitor connection.
+///
+/// Once the initial state is sent, the replies to the monitor go through its queue, like
+/// the messages broadcast to every monitor, so that they are never interleaved.
+///
+/// When this function returns, the client connection is closed.
+pub async fn handle(mut client: Client) -> Result<(), ClientError> {
+    let id = client.id();
+
+    // The monitor only keeps the changes of the map while its initial state is being
+    // sent: the cells it has already received may change before it is done.
+    state().add_gfx_monitor(&client);
+    let _guard = MonitorGuard(id);
+
+    send_initial_state(&mut client).await?;
+
+    let mut buf = String::new();
+    let mut json_buf = String::new();
+    let mut json = false;
+    let mut follows_queues = false;
+
+    loop {
+        let line = client.recv_line().await?;
+
+        buf.clear();
+        match GfxCommand::parse(line) {
+            // The map may be huge, it is streamed instead of being built in one go.
+            Some(GfxCommand::MapContent) => {
+                send_map_content(&mut client, &mut buf, json, false).await?;
+                continue;
+            }
+            Some(GfxCommand::ToggleJson) => {
+                json = !json;
+                state().set_gfx_monitor_json(id, json);
+                let _ = writeln!(buf, "smg json {}", u8::from(json));
+            }
+            Some(GfxCommand::ToggleQueues) => {
+                follows_queues = !follows_queues;
+                let mut lock = state();
+                lock.set_gfx_monitor_follows_queues(id, follows_queues);
+                let _ = writeln!(buf, "smg pqc {}", u8::from(follows_queues));
+                // The following messages only tell about changes.
+                if follows_queues {
+                    for player in lock.players() {
+                        messages::player_queue(&mut buf, player);
+                    }
+                }
+            }
+            Some(cmd) => cmd.execute(&mut state(), &mut buf),
+            None => {
+                ft_log::trace!(
+                    "graphics monitor #{id} sent an unknown command: `{}`",
+                    core::str::from_utf8(line)
+                        .unwrap_or("<invalid UTF-8>")
+                        .escape_debug()
+                );
+                buf.push_str("suc\n");
+            }
+        }
+
+        if json {
+            json_buf.clear();
+            json::encode(&buf, &mut json_buf);
+            send_reply(&client, json_buf.as_bytes()).await?;
+        } else {
+            send_reply(&client, buf.as_bytes()).await?;
+        }
+    }
+}
+
+/// Queues a reply to the monitor after the messages broadcast to it so far, then waits
+/// for it to be written.
+async fn send_reply(client: &Client, reply: &[u8]) -> ft::Result<()> {
+    if !state().queue_gfx_reply(client.id(), reply) {
+        return Err(ft::Errno::CONNRESET);
+    }
+    wait_until_written(client).await
+}
+
+/// Writes the queues of the graphics monitors until the one of the monitor is empty,
+/// waiting for its connection to accept more whenever it is full.
+async fn wait_until_written(client: &Client) -> ft::Result<()> {
+    loop {
+        let mut writes = Vec::new();
+        state().take_gfx_writes(&mut writes);
+        write_to_graphics_monitors(&mut writes);
+
+        // The monitor is removed when it does not read its messages.
+        match state().gfx_monitor_has_pending(client.id()) {
+            None => return Err(ft::Errno::CONNRESET),
+            Some(false) => return Ok(()),
+            Some(true) => ft_async::futures::ready_for_writing(client.fd()).await?,
+        }
+    }
+}
+
+/// Sends the initial state of the game to a graphics monitor:
+/// 1. The dimensions of the world (`msz`).
+/// 2. The time unit of the server (`sgt`).
+/// 3. The name of every team (`tna`), then the starting inventory of the teams that
+///    were given a handicap (`tsi`).
+/// 4. The content of every cell (`bct`).
+/// 5. Every connected player (`pnw`), their inventory (`pin`) and the number of ticks
+///    before they starve (`ptl`).
+/// 6. Every egg that has not hatched yet (`enw`).
+///
+/// The first parts are written directly, as nothing else is written to the monitor until
+/// the last one is queued.
+async fn send_initial_state(client: &mut Client) -> ft::Result<()> {
+    let mut buf = String::new();
+
+    let lock = state();
+    let _ = writeln!(
+        buf,
+        "msz {} {}",
+        lock.world().width(),
+        lock.world().height()
+    );
+    let _ = writeln!(buf, "sgt {}", lock.tick_frequency());
+    for name in lock.team_names() {
+        let _ = writeln!(buf, "tna {name}");
+    }
+    for (name, inventory) in lock.team_handicaps() {
+        messages::team_starting_inventory(&mut buf, name, inventory);
+    }
+    drop(lock);
+    client.send_raw(buf.as_bytes()).await?;
+
+    send_map_content(client, &mut buf, false, true).await?;
+
+    // The players and the eggs are listed at once with the monitor starting to receive
+    // the changes, so that it learns about every player before any of them moves.
+    buf.clear();
+    let mut lock = state();
+    for player in lock.players() {
+        messages::player_new(&mut buf, player, lock.team_name(player.team_id()));
+        messages::player_inventory(&mut buf, player);
+        messages::player_time_to_live(&mut buf, player);
+    }
+    for egg in lock.eggs() {
+        messages::egg_new(&mut buf, egg);
+    }
+    let registered = lock.finish_gfx_monitor_loading(client.id(), buf.as_bytes());
+    drop(lock);
+    if !registered {
+        return Err(ft::Errno::CONNRESET);
+    }
+    wait_until_written(client).await
+}
+
+/// Sends the content of every cell of the world (`bct`), row by row, in chunks of
+/// [`CELLS_PER_CHUNK`] cells, encoded as JSON lines if `json` is set.
+///
+/// The chunks are queued after the messages broadcast to the monitor, unless `initial`
+/// is set: the initial state is written directly.
+///
+/// The state is locked again for every cell, so the world may change while it is being
+/// sent. Such changes are pushed to the monitor at the end of the tick anyway.
+async fn send_map_content(
+    client: &mut Client,
+    buf: &mut String,
+    json: bool,
+    initial: bool,
+) -> ft::Result<()> {
+    let lock = state();
+    let width = lock.world().width() as usize;
+    let cell_count = width * lock.world().height() as usize;
+    drop(lock);
+
+    // Queued chunks may be written without waiting for the socket, so the task does not
+    // rely on sending to yield to the other tasks.
+    let mut budget = Budget::new(Budget::DEFAULT);
+    for start in (0..cell_count).step_by(CELLS_PER_CHUNK) {
+        // Responses to players go first.
+        crate::priority::bulk_turn().await;
+
+        buf.clear();
+        for index in start..cell_count.min(start + CELLS_PER_CHUNK) {
+            let (x, y) = ((index % width) as u32, (index / width) as u32);
+            messages::tile_content(buf, x, y, state().world().cell(x, y));
+            budget.spend().await;
+        }
+
+        if json {
+            let text = core::mem::take(buf);
+            json::encode(&text, buf);
+        }
+
+        if initial {
+            client.send_raw(buf.as_bytes()).await?;
+        } else {
+            send_reply(client, buf.as_bytes()).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Periodically pings the graphics monitors.
+///
+/// Monitors whose connection is dead are only noticed when writing to them. Pinging them
+/// regularly ensures that they get dropped even when nothing happens in the game.
+pub async fn run_heartbeat() {
+    let mut pings = ft_async::futures::interval(PING_PERIOD);
+    // The first tick of the interval is due right away.
+    pings.tick().await;
+
+    loop {
+        pings.tick().await;
+
+        // This also flushes the messages the monitors could not accept earlier.
+        broadcast_to_graphics_monitors("smg ping\n");
+    }
+}
+
+/// Sends `pqc` messages to the graphics monitors that follow command queues, see
+/// [`State::push_to_gfx_queue_followers`](crate::state::State::push_to_gfx_queue_followers).
+pub fn broadcast_to_queue_followers(msg: &str) {
+    let lock = state();
+    let any = lock.gfx_monitors().iter().any(|m| m.follows_queues());
+    let json = lock.gfx_monitors().iter().any(|m| m.json());
+    drop(lock);
+    if !any {
+        return;
+    }
+
+    let json_msg = encode_if(json, msg);
+    let mut writes = Vec::new();
+    let mut lock = state();
+    lock.push_to_gfx_queue_followers(msg, &json_msg);
+    lock.take_gfx_writes(&mut writes);
+    drop(lock);
+
+    write_to_graphics_monitors(&mut writes);
+}
+
+/// Sends a message to every graphics monitor currently connected to the server, see
+/// [`State::push_to_gfx_monitors`](crate::state::State::push_to_gfx_monitors).
+///
+/// The message is encoded as JSON lines for the monitors that asked for it. The lock on
+/// the state is only held to queue the message and to take the queues out: encoding and
+/// writing happen without it.
+pub fn broadcast_to_graphics_monitors(msg: &str) {
+    let lock = state();
+    let monitors = lock.gfx_monitors();
+    let (any, json) = (!monitors.is_empty(), monitors.iter().any(|m| m.json()));
+    drop(lock);
+    if !any {
+        return;
+    }
+
+    let json_msg = encode_if(json, msg);
+    let mut writes = Vec::new();
+    let mut lock = state();
+    lock.push_to_gfx_monitors(msg, &json_msg);
+    lock.take_gfx_writes(&mut writes);
+    drop(lock);
+
+    write_to_graphics_monitors(&mut writes);
+}
+
+/// Returns `msg` encoded as JSON lines if `json` is `true`, or an empty string.
+fn encode_if(json: bool, msg: &str) -> String {
+    let mut json_msg = String::new();
+    if json {
+        json::encode(msg, &mut json_msg);
+    }
+    json_msg
+}
+
+/// Writes the messages taken out of the queues of the graphics monitors without holding
+/// the lock on the state, then puts back what they did not accept.
+fn write_to_graphics_monitors(writes: &mut Vec<GfxWrite>) {
+    for write in writes.iter_mut() {
+        write.write();
+    }
+    state().finish_gfx_writes(writes);
+}
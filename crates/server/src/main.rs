@@ -1,29 +1,42 @@
 //! The Zappy server.
 
-#![no_std]
-#![no_main]
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(not(test), no_main)]
+#![cfg_attr(test, allow(dead_code))]
 #![deny(clippy::unwrap_used, unsafe_op_in_unsafe_fn)]
 #![warn(missing_docs, clippy::must_use_candidate)]
 
 extern crate alloc;
+// The unit tests run on top of the standard library, which handles panics itself.
+#[cfg(not(test))]
 extern crate unwinding;
 
+use alloc::format;
 use alloc::string::String;
 use alloc::vec::Vec;
 
-use self::args::Args;
+use self::args::{Args, CatchUp, Mode, Usage};
+use self::catch::catch_panic;
 use self::client::{Client, ClientError};
+use self::config::Config;
 use self::player::PlayerError;
 use self::server::Server;
-use self::state::{set_state, state, State};
+use self::state::{read_snapshot, set_state, state, write_snapshot, State, MIN_TICK_FREQUENCY};
+use ft_async::Priority;
 
-use core::sync::atomic::AtomicBool;
+use core::fmt::Write;
 use core::sync::atomic::Ordering::Relaxed;
+use core::sync::atomic::{AtomicBool, AtomicU64};
 use core::time::Duration;
 
+mod admin;
 mod args;
+mod catch;
 mod client;
+mod config;
+mod gfx_connection;
 mod player;
+mod priority;
 mod server;
 mod state;
 
@@ -34,52 +47,197 @@ const EXIT_FAILURE: u8 = 1;
 /// The exit code to return in case of usage error.
 const EXIT_USAGE: u8 = 2;
 
+/// The maximum number of connections accepted at once, before letting other tasks run.
+const MAX_ACCEPT_BURST: usize = 64;
+
+/// The time between two checks for buffered log messages.
+const LOG_FLUSH_PERIOD: Duration = Duration::from_millis(10);
+
+/// The number of times in a row simulating ticks may panic before any tick completes,
+/// before the server gives up and stops.
+const MAX_TICK_RESTARTS: u32 = 8;
+
 /// This boolean is set to `true` when the server is interrupted by an external signal
 /// (such as **SIGINT**).
 static INTERRUPTED: AtomicBool = AtomicBool::new(false);
 
+/// This boolean is set to `true` when ticks can no longer be simulated, stopping the
+/// server.
+static TICKS_STOPPED: AtomicBool = AtomicBool::new(false);
+
+/// This boolean is set to `true` when the server receives **SIGHUP**, requesting the
+/// configuration file to be re-read.
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// This boolean is set to `true` when an admin requests the game to be saved.
+static SAVE_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// This boolean is set to `true` when the game ends, requesting the command journal to be
+/// written.
+static JOURNAL_REQUESTED: AtomicBool = AtomicBool::new(false);
+
 /// The **SIGINT** and **SIGTERM** signal handler.
 extern "C" fn interrupt_handler(_: ft::Signal) {
     INTERRUPTED.store(true, Relaxed);
 }
 
-fn main(args: &[&ft::CharStar], _env: &[&ft::CharStar]) -> u8 {
-    let args = match Args::parse_args(args) {
-        Ok(ok) => ok,
+/// The **SIGHUP** signal handler.
+extern "C" fn reload_handler(_: ft::Signal) {
+    RELOAD_REQUESTED.store(true, Relaxed);
+}
+
+fn main(args: &[&ft::CharStar], env: &[&ft::CharStar]) -> u8 {
+    let args = match Args::parse_args(args, env) {
+        Ok(Mode::Run(args)) => *args,
+        Ok(Mode::Help) => {
+            ft::printf!("{}", Usage);
+            return EXIT_SUCCESS;
+        }
+        Ok(Mode::Version) => {
+            ft::printf!("zappy_server {}\n", env!("CARGO_PKG_VERSION"));
+            return EXIT_SUCCESS;
+        }
         Err(err) => {
-            ft::eprintf!(
-                core::concat!("\x1B[1;31merror:\x1B[0m {}\n\n", include_str!("usage.txt")),
-                err
-            );
+            ft::eprintf!("\x1B[1;31merror:\x1B[0m {}\n\n{}", err, Usage);
             return EXIT_USAGE;
         }
     };
 
+    for &(module, level) in &args.log_modules {
+        // The filters outlive the arguments, and are only set once.
+        if !ft_log::set_module_level(String::from(module).leak(), level) {
+            ft_log::warning!("too many module filters, ignoring `{module}`");
+        }
+    }
+
     ft_log::trace!("ARGUMENTS:");
     ft_log::trace!("  - port: {}", args.port);
+    ft_log::trace!("  - listen backlog: {}", args.listen_backlog);
     ft_log::trace!("  - size: {}x{}", args.width, args.height);
     ft_log::trace!("  - teams: {:?}", args.teams);
     ft_log::trace!("  - team slots: {}", args.initial_slot_count);
     ft_log::trace!("  - tick frequency: {}hz", args.tick_frequency);
+    ft_log::trace!("  - seed: {}", args.seed);
+    ft_log::trace!(
+        "  - eggs: laid in {} ticks, hatched in {} ticks",
+        args.fork_ticks,
+        args.hatch_ticks
+    );
+    if args.hatched_slot_ticks != 0 {
+        ft_log::trace!(
+            "  - hatched slots expire after {} ticks",
+            args.hatched_slot_ticks
+        );
+    }
+    if args.jitter_ms != 0 {
+        ft_log::trace!("  - jitter: {}ms", args.jitter_ms);
+    }
+    if let Some(path) = args.config_file {
+        ft_log::trace!("  - configuration file: {path}");
+    }
+    if let Some(path) = args.save_file {
+        ft_log::trace!("  - save file: {path}");
+    }
+    if let Some(path) = args.load_file {
+        ft_log::trace!("  - load file: {path}");
+    }
+    if let Some(path) = args.journal_file {
+        ft_log::trace!("  - command journal: {path}");
+    }
+    if let Some(fd) = args.event_log_fd {
+        ft_log::trace!("  - event log: fd {fd}");
+    }
+    if args.spawn_radius != 0 {
+        ft_log::trace!("  - spawn radius: {} cells", args.spawn_radius);
+    }
+    if args.reconnect_grace_secs != 0 {
+        ft_log::trace!(
+            "  - reconnection grace period: {}s",
+            args.reconnect_grace_secs
+        );
+    }
+    ft_log::trace!("  - win condition: {}", args.win_condition);
+    ft_log::trace!("  - catch-up policy: {}", args.catch_up);
+    if !args.graphic {
+        ft_log::trace!("  - graphics monitors: disabled");
+    }
+    if args.check_cells {
+        ft_log::trace!("  - cell occupancy checks: enabled");
+    }
+    if let Some(port) = args.admin_port {
+        ft_log::trace!("  - admin port: {port}");
+    }
 
     ft_log::trace!("initializing the global state...");
     set_state(State::from_args(&args));
 
+    if let Some(path) = args.load_file {
+        ft_log::trace!("restoring the saved game...");
+        if let Err(err) = read_snapshot(path).and_then(|bytes| state().restore(&bytes)) {
+            ft_log::error!("`{path}`: {err}");
+            return EXIT_FAILURE;
+        }
+    }
+
+    if let Some(path) = args.config_file {
+        ft_log::trace!("loading the configuration file...");
+        match Config::load(path) {
+            Ok(config) => config.apply(&mut state()),
+            Err(err) => {
+                ft_log::error!("`{path}`: {err}");
+                return EXIT_FAILURE;
+            }
+        }
+    }
+
     ft_log::trace!("setting up the signal handlers...");
     ft::Signal::INT.set_handler(ft::process::SigHandler::from_fn(interrupt_handler));
     ft::Signal::TERM.set_handler(ft::process::SigHandler::from_fn(interrupt_handler));
+    ft::Signal::HUP.set_handler(ft::process::SigHandler::from_fn(reload_handler));
 
     ft_log::trace!("spawning tasks...");
-    ft_async::EXECUTOR.spawn(run_server(args.port));
-    ft_async::EXECUTOR.spawn(run_ticks(args.tick_frequency));
+    // Ticks must be simulated on time, whatever the number of clients.
+    let drift_warning = Duration::from_millis(args.drift_warning_ms.into());
+    ft_async::EXECUTOR.spawn(Priority::High, run_ticks(args.catch_up, drift_warning));
+    ft_async::EXECUTOR.spawn(Priority::Normal, run_server(args.port, args.listen_backlog));
+    if args.graphic {
+        ft_async::EXECUTOR.spawn(Priority::Low, self::gfx_connection::run_heartbeat());
+    }
+    if let (Some(port), Some(key)) = (args.admin_port, args.admin_key) {
+        let admin_server = self::admin::run_admin_server(port, key.into());
+        ft_async::EXECUTOR.spawn(Priority::Normal, admin_server);
+    }
+
+    // From now on, log messages are written by a task rather than by the code logging
+    // them, so that a full standard output never stalls the event loop.
+    ft_async::EXECUTOR.spawn(Priority::Low, flush_logs());
+    ft_log::set_buffered(true);
 
     ft_log::trace!("running the executor...");
+    let mut exit_code = EXIT_SUCCESS;
     loop {
         if INTERRUPTED.load(Relaxed) {
             ft_log::trace!("interrupted, exiting...");
             break;
         }
 
+        if TICKS_STOPPED.load(Relaxed) {
+            exit_code = EXIT_FAILURE;
+            break;
+        }
+
+        if RELOAD_REQUESTED.swap(false, Relaxed) {
+            reload_config(args.config_file);
+        }
+
+        if SAVE_REQUESTED.swap(false, Relaxed) {
+            save_game(args.save_file);
+        }
+
+        if JOURNAL_REQUESTED.swap(false, Relaxed) {
+            write_journal(args.journal_file);
+        }
+
         if ft_async::EXECUTOR.is_empty() {
             ft_log::trace!("no more tasks to run, exiting...");
             break;
@@ -96,21 +254,110 @@ fn main(args: &[&ft::CharStar], _env: &[&ft::CharStar]) -> u8 {
             // An error occured.
             Err(err) => {
                 ft_log::error!("failed to block until a task is ready: {err}");
-                return EXIT_FAILURE;
+                exit_code = EXIT_FAILURE;
+                break;
             }
         }
     }
 
-    EXIT_SUCCESS
+    if args.save_file.is_some() {
+        save_game(args.save_file);
+    }
+    write_journal(args.journal_file);
+
+    ft_log::set_buffered(false);
+    let _ = ft_log::flush_all();
+    exit_code
 }
 
+// The unit tests are run by the entry point of the test harness.
+#[cfg(not(test))]
 ft::entry_point!(main);
 
+/// Re-reads the configuration file, if any, and applies it to the global state.
+///
+/// When the file cannot be loaded, the current settings are kept.
+fn reload_config(path: Option<&ft::CharStar>) {
+    let Some(path) = path else {
+        ft_log::warning!("received SIGHUP, but no configuration file was provided");
+        return;
+    };
+
+    ft_log::info!("reloading the configuration file `{path}`...");
+    match Config::load(path) {
+        Ok(config) => config.apply(&mut state()),
+        Err(err) => ft_log::error!("`{path}`: {err}"),
+    }
+}
+
+/// Saves the game to the provided file, if any.
+fn save_game(path: Option<&ft::CharStar>) {
+    let Some(path) = path else {
+        ft_log::warning!("an admin requested a save, but no save file was provided");
+        return;
+    };
+
+    let snapshot = state().snapshot();
+    match write_snapshot(path, &snapshot) {
+        Ok(()) => ft_log::info!("saved the game to `{path}` ({} bytes)", snapshot.len()),
+        Err(err) => ft_log::error!("`{path}`: {err}"),
+    }
+}
+
+/// Writes the command journal to the provided file, if any.
+fn write_journal(path: Option<&ft::CharStar>) {
+    let Some(path) = path else {
+        return;
+    };
+
+    let mut journal = String::new();
+    let lock = state();
+    lock.write_journal(&mut journal, "", None);
+    let count = lock.journal_len().unwrap_or(0);
+    drop(lock);
+
+    let result = ft::File::create(path).and_then(|file| file.write_all(journal.as_bytes()));
+    match result {
+        Ok(()) => ft_log::info!("wrote {count} commands to the command journal `{path}`"),
+        Err(err) => ft_log::error!("failed to write the command journal to `{path}`: {err}"),
+    }
+}
+
+/// Writes the buffered log messages to the standard output whenever it can be written to
+/// without blocking.
+///
+/// Messages dropped because the log buffer was full are reported once there is room
+/// again.
+async fn flush_logs() {
+    let mut reported_drops = 0;
+
+    loop {
+        if ft_log::has_pending() {
+            let flushed = ft_async::futures::ready_for_writing(ft::Fd::STDOUT)
+                .await
+                .and_then(|()| ft_log::flush());
+            if let Err(err) = flushed {
+                // The messages logged from now on are dropped once the buffer is full.
+                ft::eprintf!("failed to write the log messages: {err}\n");
+                return;
+            }
+        } else {
+            ft_async::futures::sleep_for(LOG_FLUSH_PERIOD).await;
+        }
+
+        let dropped = ft_log::dropped_messages();
+        if dropped != reported_drops {
+            ft_log::warning!("dropped {} log messages", dropped - reported_drops);
+            reported_drops = dropped;
+        }
+    }
+}
+
 /// Runs the server on the provided port.
-async fn run_server(port: u16) {
+async fn run_server(port: u16, backlog: u32) {
     ft_log::info!("starting up the server on port {port}");
 
-    let server = match Server::new(port) {
+    let server = match Server::new(port, backlog) {
         Ok(ok) => ok,
         Err(err) => {
             ft_log::error!("failed to create a TCP server: {err}");
@@ -118,38 +365,51 @@ async fn run_server(port: u16) {
         }
     };
 
+    let mut accepted = Vec::new();
     loop {
-        let (conn, address) = match server.accept().await {
-            Ok(ok) => ok,
-            Err(err) => {
-                ft_log::error!("failed to accept a connection: {err}");
-                continue;
-            }
-        };
+        if let Err(err) = server.accept_many(MAX_ACCEPT_BURST, &mut accepted).await {
+            ft_log::error!("failed to accept a connection: {err}");
+        }
+        if accepted.len() > 1 {
+            ft_log::trace!("accepted {} connections at once", accepted.len());
+        }
 
-        ft_async::EXECUTOR.spawn(handle_connection(conn, address));
+        // The handlers of the whole burst are spawned before any of them runs, so that
+        // the clients all receive `BIENVENUE` during the next round of the executor.
+        for (conn, address) in accepted.drain(..) {
+            ft_async::EXECUTOR.spawn(Priority::Normal, handle_connection(conn, address));
+        }
     }
 }
 
 /// Handles a connection from a client.
+///
+/// A panic while handling the client only closes its connection.
 async fn handle_connection(conn: ft::File, addr: ft::net::SocketAddr) {
     let client = Client::new(conn);
     let id = client.id();
 
     ft_log::info!("accepted a connection from `{addr}` (#{id})");
+    state().open_connection(id, format!("{addr}").into());
 
-    match try_handle_connection(client).await {
-        Ok(()) => (),
-        Err(ClientError::Disconnected) => {
-            ft_log::info!("client #{id} disconnected");
+    let reason = match catch_panic(try_handle_connection(client)).await {
+        None => {
+            ft_log::error!("handling client #{id} panicked, closing the connection");
+            String::from("panicked")
         }
-        Err(ClientError::Unexpected(err)) => {
+        Some(Ok(())) => String::from("closed by the server"),
+        Some(Err(err @ ClientError::Unexpected(_))) => {
             ft_log::error!("failed to handle client #{id}: {err}");
+            format!("{err}")
         }
-        Err(ClientError::Player(err)) => {
-            ft_log::info!("player #{id} behaved badly: {err}");
+        Some(Err(ClientError::Player(err))) => {
+            ft_log::info!("rejected client #{id}: {err}");
+            format!("{err}")
         }
-    }
+        Some(Err(err)) => format!("{err}"),
+    };
+
+    state().close_connection(id, reason.into());
 }
 
 /// See [`handle_connection`].
@@ -172,51 +432,160 @@ async fn try_handle_connection(mut client: Client) -> Result<(), ClientError> {
     let team_name = client.recv_line().await?;
 
     if team_name == b"GRAPHIC" {
+        if !state().gfx_enabled() {
+            return client.reject(PlayerError::GraphicDisabled).await;
+        }
         ft_log::trace!("client #{id} is a graphical monitor");
-        unimplemented!("graphical monitors are not yet supported");
+        self::gfx_connection::handle(client).await
+    } else if let Some(token) = team_name.strip_prefix(b"RECONNECT ") {
+        ft_log::trace!("client #{id} is a reconnecting player");
+        // The line is borrowed from the client, which the player handler takes.
+        let token: Vec<u8> = token.into();
+        self::player::resume(client, &token).await
     } else {
-        let team_name =
-            core::str::from_utf8(team_name).map_err(|_| PlayerError::InvalidTeamName)?;
-        let team_id = state()
-            .team_id_by_name(team_name)
-            .ok_or_else(|| PlayerError::UnknownTeam(team_name.into()))?;
-        self::player::handle(client, team_id).await
+        let team_id = match core::str::from_utf8(team_name) {
+            Ok(team_name) => state()
+                .team_id_by_name(team_name)
+                .ok_or_else(|| PlayerError::UnknownTeam(team_name.into())),
+            Err(_) => Err(PlayerError::InvalidTeamName),
+        };
+        match team_id {
+            Ok(team_id) => self::player::handle(client, team_id).await,
+            Err(err) => client.reject(err).await,
+        }
     }
 }
 
+/// Returns the time between two ticks simulated at `frequency` ticks per second.
+///
+/// The frequency is always checked with [`state::is_valid_tick_frequency`], but the
+/// period of the lowest frequency is used rather than panicking if it is not.
+fn tick_period(frequency: f32) -> Duration {
+    Duration::try_from_secs_f32(1.0 / frequency)
+        .unwrap_or_else(|_| Duration::from_secs_f32(1.0 / MIN_TICK_FREQUENCY))
+}
+
 /// Runs ticks on all the clients.
-async fn run_ticks(freq: f32) {
-    if let Err(err) = try_run_ticks(freq).await {
-        ft_log::error!("failed to run ticks: {err}");
+///
+/// The game goes on if simulating a tick panics: ticks start being simulated again from
+/// the current time. The server stops if it panics [`MAX_TICK_RESTARTS`] times in a row
+/// without completing a single tick.
+///
+/// When ticks are simulated more than `drift_warning` late, a warning is logged and the
+/// graphics monitors are notified, unless `drift_warning` is zero.
+async fn run_ticks(catch_up: CatchUp, drift_warning: Duration) {
+    let completed = AtomicU64::new(0);
+    let mut restarts = 0;
+    loop {
+        let completed_before = completed.load(Relaxed);
+        if catch_panic(try_run_ticks(catch_up, drift_warning, &completed))
+            .await
+            .is_some()
+        {
+            return;
+        }
+
+        if completed.load(Relaxed) != completed_before {
+            restarts = 0;
+        }
+        restarts += 1;
+        if restarts >= MAX_TICK_RESTARTS {
+            ft_log::error!("simulating a tick keeps panicking, stopping the server");
+            TICKS_STOPPED.store(true, Relaxed);
+            return;
+        }
+        ft_log::error!("simulating a tick panicked, resuming the game");
     }
 }
 
 /// See [`run_ticks`].
-async fn try_run_ticks(freq: f32) -> ft::Result<()> {
-    let period = Duration::from_secs_f32(1.0 / freq);
-    let mut next_tick = ft::Clock::MONOTONIC.get();
+///
+/// `completed` is incremented every time a tick is completely simulated.
+async fn try_run_ticks(catch_up: CatchUp, drift_warning: Duration, completed: &AtomicU64) {
+    let mut ticks = ft_async::futures::interval(tick_period(state().tick_frequency()));
+    let mut drifting = false;
 
     let mut responses = Vec::new();
     let mut send_buf = String::new();
+    let mut gfx_buf = String::new();
+    let mut queue_buf = String::new();
+    let mut broken = Vec::new();
 
     loop {
         // Wait until the next tick.
-        ft_async::futures::sleep(next_tick).await;
-        next_tick += period;
+        let due = ticks.tick().await;
+        let now = ft::Clock::MONOTONIC.get();
+        let lateness = now.saturating_sub(due);
 
         // Notify the state.
         responses.clear();
-        state().tick(&mut responses);
+        let mut lock = state();
+        lock.set_tick_lateness(lateness);
+        lock.tick(&mut responses);
+        lock.take_gfx_outbox(&mut gfx_buf);
+        lock.take_gfx_queue_outbox(&mut queue_buf);
+        // The tick frequency may be changed while the server is running, so the period
+        // has to be computed again every time.
+        ticks.set_period(tick_period(lock.tick_frequency()));
+        if catch_up == CatchUp::Skip {
+            let skipped = ticks.skip_missed(now);
+            if skipped != 0 {
+                lock.record_skipped_ticks(skipped.into());
+            }
+        }
+        drop(lock);
+
+        let was_drifting = drifting;
+        drifting = !drift_warning.is_zero() && lateness > drift_warning;
+        if drifting && !was_drifting {
+            ft_log::warning!("ticks are simulated {}ms late", lateness.as_millis());
+            if !state().gfx_monitors().is_empty() {
+                let _ = writeln!(gfx_buf, "smg drift {}", lateness.as_millis());
+            }
+        }
 
         // Send the responses to the clients.
+        // Bulk traffic to the graphics monitors is held back until they are all sent.
         // TODO: optimize this by sending the responses concurrently.
         // There's two ways to do this:
         //  1. Create a proper future that sends all the responses concurrently.
         //  2. Spawn a task per message, but that leaves no good way to re-use buffers.
         //     This might not be a big problem though.
+        // A connection that fails does not keep the others from receiving their responses:
+        // it is dropped from the game and skipped for the rest of the tick.
+        let batch = self::priority::ResponseBatch::begin();
+        broken.clear();
         for (conn, response) in responses.iter() {
+            if broken.contains(conn) {
+                continue;
+            }
             send_buf.clear();
-            response.send_to(*conn, &mut send_buf).await?;
+            if let Err(err) = response.send_to(*conn, &mut send_buf).await {
+                ft_log::warning!("failed to send a response to a player: {err}");
+                broken.push(*conn);
+            }
+        }
+        drop(batch);
+        let mut lock = state();
+        for &conn in &broken {
+            if let Some(player) = lock.disconnect(conn) {
+                ft_log::info!("player #{player} was disconnected");
+            }
         }
+        // The players that died have received `mort`, their connections can be closed.
+        lock.wake_removed_players();
+        drop(lock);
+
+        // Push the changes that occurred during the tick to the graphics monitors.
+        if !gfx_buf.is_empty() {
+            self::gfx_connection::broadcast_to_graphics_monitors(&gfx_buf);
+            gfx_buf.clear();
+        }
+        if !queue_buf.is_empty() {
+            self::gfx_connection::broadcast_to_queue_followers(&queue_buf);
+            queue_buf.clear();
+        }
+
+        completed.fetch_add(1, Relaxed);
     }
 }
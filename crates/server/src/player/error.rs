@@ -22,12 +22,35 @@ pub enum PlayerError {
         /// The ID of the team.
         id: TeamId,
     },
+    /// The player tried to reconnect with a token that matches no disconnected player.
+    UnknownReconnectToken,
+    /// The client asked to be a graphics monitor, but they are disabled.
+    GraphicDisabled,
     /// The player sent an unknown command.
     UnknownCommand(Box<[u8]>),
     /// The player sent an unknown object class.
     UnknownObjectClass(Box<[u8]>),
 }
 
+impl PlayerError {
+    /// Returns the line sent to the client before its connection is closed because of
+    /// this error.
+    ///
+    /// The protocol has no way to tell what went wrong: the reason is only logged by the
+    /// server.
+    pub fn reply(&self) -> &'static [u8] {
+        match *self {
+            PlayerError::InvalidTeamName
+            | PlayerError::UnknownTeam(_)
+            | PlayerError::TeamFull { .. }
+            | PlayerError::UnknownReconnectToken
+            | PlayerError::GraphicDisabled
+            | PlayerError::UnknownCommand(_)
+            | PlayerError::UnknownObjectClass(_) => b"ko\n",
+        }
+    }
+}
+
 impl fmt::Display for PlayerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
@@ -40,6 +63,12 @@ impl fmt::Display for PlayerError {
             PlayerError::InvalidTeamName => {
                 write!(f, "invalid team name")
             }
+            PlayerError::UnknownReconnectToken => {
+                write!(f, "unknown reconnection token")
+            }
+            PlayerError::GraphicDisabled => {
+                write!(f, "graphics monitors are disabled")
+            }
             PlayerError::UnknownCommand(ref cmd_name) => {
                 write!(
                     f,
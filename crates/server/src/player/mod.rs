@@ -1,6 +1,10 @@
 use alloc::format;
+use core::fmt::Write;
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::Poll;
 
-use crate::client::{Client, ClientError};
+use crate::client::{slice_split_once, Client, ClientError};
 use crate::state::{state, Command, ObjectClass, PlayerId, TeamId};
 
 mod error;
@@ -8,11 +12,20 @@ mod error;
 pub use self::error::*;
 
 /// A guard that makes a player leave their team when dropped.
-struct PlayerGuard(PlayerId);
+struct PlayerGuard {
+    /// The ID of the player.
+    player_id: PlayerId,
+    /// Whether the player may reconnect after leaving.
+    may_reconnect: bool,
+}
 
 impl Drop for PlayerGuard {
     fn drop(&mut self) {
-        state().leave(self.0);
+        if self.may_reconnect {
+            state().detach(self.player_id);
+        } else {
+            state().leave(self.player_id);
+        }
     }
 }
 
@@ -20,62 +33,137 @@ impl Drop for PlayerGuard {
 ///
 /// When this function returns, the client connection is closed.
 pub async fn handle(mut client: Client, team_id: TeamId) -> Result<(), ClientError> {
-    let player_id = state().try_join_team(&client, team_id)?;
-    let _guard = PlayerGuard(player_id);
+    let joined = state().try_join_team(&client, team_id);
+    match joined {
+        Ok(player_id) => play(client, player_id, team_id).await,
+        Err(err) => client.reject(err).await,
+    }
+}
+
+/// Handles the connection of a player resuming with a reconnection token.
+///
+/// When this function returns, the client connection is closed.
+pub async fn resume(mut client: Client, token: &[u8]) -> Result<(), ClientError> {
+    let reattached = core::str::from_utf8(token)
+        .ok()
+        .and_then(|token| u64::from_str_radix(token, 16).ok())
+        .ok_or(PlayerError::UnknownReconnectToken)
+        .and_then(|token| state().reattach(&client, token));
+    match reattached {
+        Ok((player_id, team_id)) => play(client, player_id, team_id).await,
+        Err(err) => client.reject(err).await,
+    }
+}
+
+/// Runs the game for a player that joined a team, until the connection is closed.
+async fn play(mut client: Client, player_id: PlayerId, team_id: TeamId) -> Result<(), ClientError> {
+    let mut guard = PlayerGuard {
+        player_id,
+        may_reconnect: true,
+    };
 
-    finish_handshake(&mut client, team_id).await?;
+    finish_handshake(&mut client, player_id, team_id).await?;
+
+    let max_violations = state().max_violations();
+    let mut violations = 0;
 
     loop {
-        let line = client.recv_line().await?;
-        let (cmd_name, args) = slice_split_once(line, b' ').unwrap_or((line, b""));
-
-        let cmd = match cmd_name {
-            b"avance" => Command::MoveForward,
-            b"droite" => Command::TurnRight,
-            b"gauche" => Command::TurnLeft,
-            b"voir" => Command::LookAround,
-            b"inventaire" => Command::Inventory,
-            b"prend" => {
-                let object = ObjectClass::from_arg(args)
-                    .ok_or_else(|| PlayerError::UnknownObjectClass(args.into()))?;
-                Command::PickUpObject(object)
+        // The connection is closed as soon as the player leaves the game (because they
+        // starved or were killed by an admin), without waiting for them to send anything.
+        let Some(line) = until_removed(player_id, client.recv_line()).await else {
+            return Ok(());
+        };
+        let line = line?;
+
+        let cmd = match parse_command(line) {
+            Ok(cmd) => {
+                violations = 0;
+                cmd
             }
-            b"pose" => {
-                let object = ObjectClass::from_arg(args)
-                    .ok_or_else(|| PlayerError::UnknownObjectClass(args.into()))?;
-                Command::DropObject(object)
+            Err(err) => {
+                violations += 1;
+                if violations > max_violations {
+                    // Misbehaving players are not waited for.
+                    guard.may_reconnect = false;
+                    return client.reject(err).await;
+                }
+                ft_log::info!(
+                    "player #{player_id} sent an invalid command ({violations}/{max_violations}): {err}"
+                );
+                Command::Invalid
             }
-            b"expulse" => Command::KnockPlayer,
-            b"broadcast" => Command::Broadcast(args.into()),
-            b"incantation" => Command::Evolve,
-            b"fork" => Command::LayAnEgg,
-            b"connect_nbr" => Command::AvailableTeamSlots,
-            _ => return Err(PlayerError::UnknownCommand(cmd_name.into()).into()),
         };
 
-        state().player_mut(player_id).schedule_command(cmd);
+        state().schedule_command(player_id, cmd);
     }
 }
 
+/// Runs `future` until it completes, or until the player leaves the game.
+///
+/// # Returns
+///
+/// `None` if the player left the game first.
+async fn until_removed<F: Future>(player_id: PlayerId, future: F) -> Option<F::Output> {
+    let mut future = pin!(future);
+    poll_fn(|cx| {
+        if !state().watch_player(player_id, cx.waker()) {
+            return Poll::Ready(None);
+        }
+        future.as_mut().poll(cx).map(Some)
+    })
+    .await
+}
+
+/// Parses a command sent by a player.
+fn parse_command(line: &[u8]) -> Result<Command, PlayerError> {
+    let (cmd_name, args) = slice_split_once(line, b' ').unwrap_or((line, b""));
+
+    let cmd = match cmd_name {
+        b"avance" => Command::MoveForward,
+        b"droite" => Command::TurnRight,
+        b"gauche" => Command::TurnLeft,
+        b"voir" => Command::LookAround,
+        b"inventaire" => Command::Inventory,
+        b"prend" => {
+            let object = ObjectClass::from_arg(args)
+                .ok_or_else(|| PlayerError::UnknownObjectClass(args.into()))?;
+            Command::PickUpObject(object)
+        }
+        b"pose" => {
+            let object = ObjectClass::from_arg(args)
+                .ok_or_else(|| PlayerError::UnknownObjectClass(args.into()))?;
+            Command::DropObject(object)
+        }
+        b"expulse" => Command::KnockPlayer,
+        b"broadcast" => Command::Broadcast(args.into()),
+        b"incantation" => Command::Evolve,
+        b"fork" => Command::LayAnEgg,
+        b"connect_nbr" => Command::AvailableTeamSlots,
+        _ => return Err(PlayerError::UnknownCommand(cmd_name.into())),
+    };
+
+    Ok(cmd)
+}
+
 /// Finish the handshake by sending:
 /// 1. The number of remaining slots in the team.
 /// 2. The dimensions of the world.
-async fn finish_handshake(client: &mut Client, team_id: TeamId) -> ft::Result<()> {
+/// 3. The reconnection token of the player (`token`), if reconnections are enabled.
+async fn finish_handshake(
+    client: &mut Client,
+    player_id: PlayerId,
+    team_id: TeamId,
+) -> ft::Result<()> {
     let lock = state();
     let available_slots = lock.available_slots_for(team_id);
     let width = lock.world().width();
     let height = lock.world().height();
+    let token = lock.player(player_id).and_then(|p| p.reconnect_token());
     drop(lock);
 
-    client
-        .send_raw(format!("{available_slots}\n{width} {height}\n").as_bytes())
-        .await
-}
-
-/// Splits the provided slice into two parts at the first occurrence of the provided delimiter.
-fn slice_split_once(slice: &[u8], delim: u8) -> Option<(&[u8], &[u8])> {
-    slice
-        .iter()
-        .position(|&b| b == delim)
-        .map(|pos| (&slice[..pos], &slice[pos + 1..]))
+    let mut msg = format!("{available_slots}\n{width} {height}\n");
+    if let Some(token) = token {
+        let _ = writeln!(msg, "token {token:016x}");
+    }
+    client.send_raw(msg.as_bytes()).await
 }
@@ -0,0 +1,74 @@
+//! Priority classes of the traffic sent by the server.
+//!
+//! Responses to player commands are tiny and players are waiting for them, while graphics
+//! monitors may receive whole map dumps. Bulk traffic waits until the responses that are
+//! being sent have been flushed, so that a monitor asking for the map repeatedly cannot
+//! delay the players.
+
+use alloc::vec::Vec;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+
+/// The state shared by the senders of both classes.
+struct Gate {
+    /// The number of batches of responses currently being sent.
+    pending_batches: usize,
+    /// The bulk senders waiting for the responses to be flushed.
+    waiters: Vec<Waker>,
+}
+
+/// See [`Gate`].
+static GATE: ft::Mutex<Gate, ft::sync::mutex::NoBlockMutex> = ft::Mutex::new(Gate {
+    pending_batches: 0,
+    waiters: Vec::new(),
+});
+
+/// A batch of responses being sent to players.
+///
+/// Bulk traffic is held back until the batch is dropped.
+pub struct ResponseBatch(());
+
+impl ResponseBatch {
+    /// Starts sending a batch of responses.
+    pub fn begin() -> Self {
+        GATE.lock().pending_batches += 1;
+        Self(())
+    }
+}
+
+impl Drop for ResponseBatch {
+    fn drop(&mut self) {
+        let mut gate = GATE.lock();
+        gate.pending_batches -= 1;
+        if gate.pending_batches == 0 {
+            gate.waiters.drain(..).for_each(Waker::wake);
+        }
+    }
+}
+
+/// Returns a future that completes once no batch of responses is being sent.
+///
+/// Bulk senders must await it before every write.
+pub fn bulk_turn() -> BulkTurn {
+    BulkTurn
+}
+
+/// See [`bulk_turn`].
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct BulkTurn;
+
+impl Future for BulkTurn {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let mut gate = GATE.lock();
+        if gate.pending_batches == 0 {
+            return Poll::Ready(());
+        }
+        if !gate.waiters.iter().any(|w| w.will_wake(cx.waker())) {
+            gate.waiters.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
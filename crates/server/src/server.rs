@@ -1,21 +1,70 @@
 //! Provides useful functions to setup a TCP server.
 
+use alloc::vec::Vec;
+
 /// A TCP server.
 pub struct Server(ft::File);
 
 impl Server {
     /// Opens a TCP server on the provided port.
-    pub fn new(port: u16) -> ft::Result<Self> {
+    ///
+    /// Up to `backlog` connections may wait to be accepted.
+    pub fn new(port: u16, backlog: u32) -> ft::Result<Self> {
         let address = ft::net::SocketAddr::V4([0, 0, 0, 0], port);
         let socket = ft::File::socket(address.family(), ft::net::SocketType::Stream)?;
+        // Connections are only accepted once the socket is ready, but a client may give
+        // up in between: accepting must not block then.
+        socket.set_nonblocking(true)?;
         socket.bind(&address)?;
-        socket.listen(128)?;
+        socket.listen(backlog)?;
         Ok(Self(socket))
     }
 
+    /// Accepts a connection waiting on the socket, making it non-blocking.
+    ///
+    /// A client that stops reading must never block the server: writes to its connection
+    /// fail with `ft::Errno::AGAIN` instead, and what could not be written is either
+    /// awaited or queued.
+    fn accept_nonblocking(&self) -> ft::Result<(ft::File, ft::net::SocketAddr)> {
+        let (conn, address) = self.0.accept()?;
+        conn.set_nonblocking(true)?;
+        Ok((conn, address))
+    }
+
     /// Accepts a new connection.
     pub async fn accept(&self) -> ft::Result<(ft::File, ft::net::SocketAddr)> {
-        ft_async::futures::ready_for_reading(*self.0).await;
-        self.0.accept()
+        loop {
+            ft_async::futures::ready_for_reading(*self.0).await?;
+            match self.accept_nonblocking() {
+                Err(ft::Errno::AGAIN) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    /// Waits for new connections, then accepts all the ones that are waiting, up to
+    /// `max`, appending them to `accepted`.
+    ///
+    /// An error is only returned when no connection could be accepted.
+    pub async fn accept_many(
+        &self,
+        max: usize,
+        accepted: &mut Vec<(ft::File, ft::net::SocketAddr)>,
+    ) -> ft::Result<()> {
+        let start = accepted.len();
+        loop {
+            ft_async::futures::ready_for_reading(*self.0).await?;
+            while accepted.len() - start < max {
+                match self.accept_nonblocking() {
+                    Ok(conn) => accepted.push(conn),
+                    Err(ft::Errno::AGAIN) => break,
+                    Err(_) if accepted.len() != start => break,
+                    Err(err) => return Err(err),
+                }
+            }
+            if accepted.len() != start {
+                return Ok(());
+            }
+        }
     }
 }
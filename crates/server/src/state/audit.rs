@@ -0,0 +1,75 @@
+use alloc::string::String;
+use core::fmt::Write;
+
+use super::{ObjectClass, ResourceSet};
+
+/// Counts the objects that entered and left the game, to check that none of them appear
+/// or vanish by mistake.
+///
+/// Objects enter the game when the world is generated, when a player spawns with their
+/// initial inventory, when they respawn and when an admin spawns them. They leave it with the inventory of
+/// the players that are removed from the game.
+#[derive(Debug, Clone)]
+pub struct ResourceLedger {
+    /// The number of objects of each class that entered the game, indexed by
+    /// [`ObjectClass::index`].
+    spawned: [u64; 7],
+    /// The number of objects of each class that left the game, indexed by
+    /// [`ObjectClass::index`].
+    consumed: [u64; 7],
+}
+
+impl ResourceLedger {
+    /// Creates a ledger for a game whose world initially holds `initial` objects of each
+    /// class.
+    pub fn new(initial: [u64; 7]) -> Self {
+        Self {
+            spawned: initial,
+            consumed: [0; 7],
+        }
+    }
+
+    /// Records that the objects of `objects` entered the game.
+    pub fn spawn(&mut self, objects: &ResourceSet) {
+        for (total, &count) in self.spawned.iter_mut().zip(objects.counts()) {
+            *total += u64::from(count);
+        }
+    }
+
+    /// Records that the objects of `objects` left the game.
+    pub fn consume(&mut self, objects: &ResourceSet) {
+        for (total, &count) in self.consumed.iter_mut().zip(objects.counts()) {
+            *total += u64::from(count);
+        }
+    }
+
+    /// Compares the objects actually present in the game with the ones that should be.
+    ///
+    /// # Returns
+    ///
+    /// `None` if the counts match. Otherwise, a description of the classes that differ,
+    /// like `linemate 12 != 13 (-1)`.
+    pub fn check(&self, present: &[u64; 7]) -> Option<String> {
+        let mut diff = String::new();
+
+        for class in ObjectClass::ALL {
+            let i = class.index();
+            let expected = self.spawned[i] as i64 - self.consumed[i] as i64;
+            let found = present[i] as i64;
+            if found == expected {
+                continue;
+            }
+            if !diff.is_empty() {
+                diff.push_str(", ");
+            }
+            let _ = write!(
+                diff,
+                "{} {found} != {expected} ({:+})",
+                class.name(),
+                found - expected
+            );
+        }
+
+        (!diff.is_empty()).then_some(diff)
+    }
+}
@@ -0,0 +1,143 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::PlayerId;
+
+/// The number of closed connections remembered by the [`ConnectionLog`].
+const MAX_CLOSED: usize = 32;
+
+/// What a connected client turned out to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// The client did not finish its handshake yet.
+    Pending,
+    /// A player.
+    Player,
+    /// A graphics monitor.
+    Monitor,
+    /// An admin session.
+    Admin,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "pending"),
+            Self::Player => write!(f, "player"),
+            Self::Monitor => write!(f, "monitor"),
+            Self::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+/// What is known about a connection, from the moment it was accepted.
+#[derive(Debug, Clone)]
+pub struct Connection {
+    /// The ID of the client.
+    id: usize,
+    /// The address of the remote end of the connection.
+    address: Box<str>,
+    /// What the client turned out to be.
+    role: Role,
+    /// The name of the team and the ID of the player, once the client joined the game.
+    ///
+    /// The ID of the player differs from the one of the client when they reconnected.
+    player: Option<(Box<str>, PlayerId)>,
+    /// The tick during which the connection was accepted.
+    opened_at: u64,
+    /// The tick during which the connection was closed, and why.
+    closed: Option<(u64, Box<str>)>,
+}
+
+impl fmt::Display for Connection {
+    /// Formats the connection like `#3 127.0.0.1:51234 player team red #3 opened 12`,
+    /// followed by `closed 40 (<reason>)` once it is closed.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{} {} {}", self.id, self.address, self.role)?;
+        if let Some((team, player)) = &self.player {
+            write!(f, " team {team} #{player}")?;
+        }
+        write!(f, " opened {}", self.opened_at)?;
+        if let Some((tick, reason)) = &self.closed {
+            write!(f, " closed {tick} ({reason})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Keeps track of the open connections, and of the last ones that were closed, so that
+/// multi-client setups can be debugged.
+#[derive(Debug, Default)]
+pub struct ConnectionLog {
+    /// The connections, in the order in which they were accepted.
+    connections: Vec<Connection>,
+}
+
+impl ConnectionLog {
+    /// Records that the connection of client `id`, coming from `address`, was accepted
+    /// during tick `tick`.
+    pub fn open(&mut self, id: usize, address: Box<str>, tick: u64) {
+        self.connections.push(Connection {
+            id,
+            address,
+            role: Role::Pending,
+            player: None,
+            opened_at: tick,
+            closed: None,
+        });
+    }
+
+    /// Returns the connection of client `id`, unless it was closed.
+    fn get_mut(&mut self, id: usize) -> Option<&mut Connection> {
+        self.connections
+            .iter_mut()
+            .find(|c| c.id == id && c.closed.is_none())
+    }
+
+    /// Records what client `id` turned out to be.
+    pub fn set_role(&mut self, id: usize, role: Role) {
+        if let Some(connection) = self.get_mut(id) {
+            connection.role = role;
+        }
+    }
+
+    /// Records that client `id` plays as player `player` in team `team`.
+    pub fn set_player(&mut self, id: usize, team: &str, player: PlayerId) {
+        if let Some(connection) = self.get_mut(id) {
+            connection.role = Role::Player;
+            connection.player = Some((team.into(), player));
+        }
+    }
+
+    /// Records that the connection of client `id` was closed during tick `tick`.
+    ///
+    /// Only the last [`MAX_CLOSED`] closed connections are remembered.
+    ///
+    /// # Returns
+    ///
+    /// The connection, unless it was not open.
+    pub fn close(&mut self, id: usize, reason: Box<str>, tick: u64) -> Option<&Connection> {
+        let connection = self.get_mut(id)?;
+        connection.closed = Some((tick, reason));
+
+        let closed = self
+            .connections
+            .iter()
+            .filter(|c| c.closed.is_some())
+            .count();
+        if closed > MAX_CLOSED {
+            if let Some(oldest) = self.connections.iter().position(|c| c.closed.is_some()) {
+                self.connections.remove(oldest);
+            }
+        }
+
+        self.connections.iter().rev().find(|c| c.id == id)
+    }
+
+    /// Returns the open connections and the last closed ones, in the order in which they
+    /// were accepted.
+    pub fn iter(&self) -> impl Iterator<Item = &Connection> {
+        self.connections.iter()
+    }
+}
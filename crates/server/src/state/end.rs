@@ -0,0 +1,47 @@
+use core::fmt;
+
+/// The level players reach at the end of their elevation.
+pub const MAX_LEVEL: u32 = 8;
+
+/// The number of players of a team that must reach [`MAX_LEVEL`] for the team to win
+/// under [`WinCondition::MaxLevel`].
+pub const WINNING_PLAYERS: usize = 6;
+
+/// The rule deciding which team wins the game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WinCondition {
+    /// The first team with [`WINNING_PLAYERS`] players at [`MAX_LEVEL`] wins.
+    MaxLevel,
+    /// The game ends after the provided number of ticks, and the team whose players have
+    /// the highest sum of levels wins.
+    TimeLimit(u64),
+    /// Once every team had a player, the last team with players left wins.
+    LastTeamAlive,
+}
+
+impl WinCondition {
+    /// Parses a win condition from the provided argument: `level`, `time:<ticks>` or
+    /// `last-team`.
+    pub fn from_arg(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            b"level" => Some(Self::MaxLevel),
+            b"last-team" => Some(Self::LastTeamAlive),
+            _ => {
+                let ticks = bytes.strip_prefix(b"time:")?;
+                let ticks = core::str::from_utf8(ticks).ok()?.parse().ok()?;
+                Some(Self::TimeLimit(ticks))
+            }
+        }
+    }
+}
+
+impl fmt::Display for WinCondition {
+    /// Formats the win condition the way it is passed to the server.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::MaxLevel => write!(f, "level"),
+            Self::TimeLimit(ticks) => write!(f, "time:{ticks}"),
+            Self::LastTeamAlive => write!(f, "last-team"),
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use alloc::string::String;
+use core::fmt::{self, Write};
+
+use super::{EggId, PlayerId, WinCondition};
+
+/// An event of the game, written to the event log.
+pub enum Event<'a> {
+    /// A player joined a team.
+    PlayerJoined {
+        /// The ID of the player.
+        player: PlayerId,
+        /// The name of the team the player joined.
+        team: &'a str,
+    },
+    /// A player left the game.
+    PlayerLeft {
+        /// The ID of the player.
+        player: PlayerId,
+        /// The name of the team the player was in.
+        team: &'a str,
+    },
+    /// A player laid an egg.
+    EggLaid {
+        /// The ID of the egg.
+        egg: EggId,
+        /// The ID of the player that laid the egg.
+        player: PlayerId,
+        /// The name of the team the egg belongs to.
+        team: &'a str,
+    },
+    /// An egg hatched, creating a new slot in its team.
+    EggHatched {
+        /// The ID of the egg.
+        egg: EggId,
+        /// The name of the team the egg belongs to.
+        team: &'a str,
+    },
+    /// No player took the slot created by a hatched egg in time, and it was removed.
+    EggExpired {
+        /// The ID of the egg.
+        egg: EggId,
+        /// The name of the team the egg belongs to.
+        team: &'a str,
+    },
+    /// A team won the game.
+    GameEnded {
+        /// The name of the team that won.
+        team: &'a str,
+        /// The win condition the team fulfilled.
+        rule: WinCondition,
+    },
+}
+
+impl fmt::Display for Event<'_> {
+    /// Formats the event as tab-separated values, starting with the name of the event.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::PlayerJoined { player, team } => {
+                write!(f, "player_joined\t{player}\t{}", team.escape_debug())
+            }
+            Self::PlayerLeft { player, team } => {
+                write!(f, "player_left\t{player}\t{}", team.escape_debug())
+            }
+            Self::EggLaid { egg, player, team } => {
+                write!(f, "egg_laid\t{egg}\t{player}\t{}", team.escape_debug())
+            }
+            Self::EggHatched { egg, team } => {
+                write!(f, "egg_hatched\t{egg}\t{}", team.escape_debug())
+            }
+            Self::EggExpired { egg, team } => {
+                write!(f, "egg_expired\t{egg}\t{}", team.escape_debug())
+            }
+            Self::GameEnded { team, rule } => {
+                write!(f, "game_ended\t{}\t{rule}", team.escape_debug())
+            }
+        }
+    }
+}
+
+/// Writes the events of the game to a file descriptor, one line per event.
+///
+/// Each line is made of tab-separated values: the tick at which the event occurred, the
+/// name of the event and its fields.
+pub struct EventLog {
+    /// The file descriptor to write the events to.
+    ///
+    /// When `None`, events are discarded.
+    fd: Option<ft::Fd>,
+    /// A buffer used to format the events.
+    buf: String,
+}
+
+impl EventLog {
+    /// Creates a new [`EventLog`] writing to the provided file descriptor.
+    pub fn new(fd: Option<ft::Fd>) -> Self {
+        Self {
+            fd,
+            buf: String::new(),
+        }
+    }
+
+    /// Writes an event to the log.
+    ///
+    /// If writing fails, the error is reported and the log is disabled.
+    pub fn log(&mut self, tick: u64, event: Event) {
+        let Some(fd) = self.fd else {
+            return;
+        };
+
+        self.buf.clear();
+        let _ = writeln!(self.buf, "{tick}\t{event}");
+
+        let mut bytes = self.buf.as_bytes();
+        while !bytes.is_empty() {
+            match fd.write(bytes) {
+                Ok(n) => bytes = &bytes[n..],
+                Err(err) => {
+                    ft_log::error!("failed to write to the event log: {err}");
+                    self.fd = None;
+                    return;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use super::{Command, ObjectClass, PlayerId, PlayerState, Team, TeamId};
+
+/// A command executed by a player, as recorded in the [`CommandJournal`].
+struct JournalEntry {
+    /// The tick at which the command was executed.
+    tick: u64,
+    /// The ID of the player that sent the command.
+    player: PlayerId,
+    /// The ID of the team the player is in.
+    team: TeamId,
+    /// The kind of the command, as its index in [`Command::NAMES`].
+    command: u8,
+    /// The object the command was about, for `prend` and `pose`.
+    object: Option<ObjectClass>,
+    /// Whether the command was answered with `ko`.
+    failed: bool,
+    /// The number of ticks the command spent between being scheduled and being executed,
+    /// including its own duration.
+    latency: u32,
+}
+
+/// Every command executed by the players during the game, so that tournament organizers
+/// can look for protocol abuse or analyze the strategies of the teams once it is over.
+///
+/// Only the kind of the command and its object are kept, not the text of broadcasts.
+#[derive(Default)]
+pub struct CommandJournal {
+    /// The commands, in the order they were executed.
+    entries: Vec<JournalEntry>,
+}
+
+impl CommandJournal {
+    /// Records the execution of a command sent by `player`, `latency` ticks after it was
+    /// scheduled.
+    pub fn record(
+        &mut self,
+        tick: u64,
+        player: &PlayerState,
+        command: &Command,
+        latency: u64,
+        failed: bool,
+    ) {
+        let object = match *command {
+            Command::PickUpObject(class) | Command::DropObject(class) => Some(class),
+            _ => None,
+        };
+
+        self.entries.push(JournalEntry {
+            tick,
+            player: player.id(),
+            team: player.team_id(),
+            command: command.index() as u8,
+            object,
+            failed,
+            latency: u32::try_from(latency).unwrap_or(u32::MAX),
+        });
+    }
+
+    /// Returns the number of commands recorded.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Writes the commands to `buf`, one line per command, starting each line with
+    /// `prefix`.
+    ///
+    /// Only the commands of `player` are written, if provided. Each line is made of
+    /// tab-separated values: the tick, the player, their team, the command, its outcome
+    /// (`ok` or `ko`) and its latency in ticks.
+    pub fn write(&self, buf: &mut String, prefix: &str, player: Option<PlayerId>, teams: &[Team]) {
+        let entries = self
+            .entries
+            .iter()
+            .filter(|entry| player.is_none_or(|player| entry.player == player));
+
+        for entry in entries {
+            let _ = write!(
+                buf,
+                "{prefix}{}\t#{}\t{}\t{}",
+                entry.tick,
+                entry.player,
+                teams[entry.team].name.escape_debug(),
+                Command::NAMES[usize::from(entry.command)],
+            );
+            if let Some(object) = entry.object {
+                let _ = write!(buf, " {}", object.name());
+            }
+            let outcome = if entry.failed { "ko" } else { "ok" };
+            let _ = writeln!(buf, "\t{outcome}\t{}", entry.latency);
+        }
+    }
+}
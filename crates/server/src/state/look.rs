@@ -0,0 +1,77 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::{ObjectClass, WorldCell};
+
+/// Memoizes how the cells of the world are written in `voir` responses.
+///
+/// Players looking around during the same tick see overlapping regions: every cell is
+/// only formatted once, until it changes or the tick ends. The fragments are stored one
+/// after the other in a single buffer, reused from one tick to the next.
+#[derive(Debug)]
+pub struct LookCache {
+    /// The formatted fragments, one after the other.
+    text: String,
+    /// The position of the fragment of every cell in `text`, if it is cached.
+    spans: Vec<Option<(usize, usize)>>,
+    /// The indices of the cells whose fragment is cached.
+    cached: Vec<usize>,
+}
+
+impl LookCache {
+    /// Creates an empty cache for a world of `area` cells.
+    pub fn new(area: usize) -> Self {
+        Self {
+            text: String::new(),
+            spans: vec![None; area],
+            cached: Vec::new(),
+        }
+    }
+
+    /// Forgets every fragment.
+    pub fn clear(&mut self) {
+        for index in self.cached.drain(..) {
+            self.spans[index] = None;
+        }
+        self.text.clear();
+    }
+
+    /// Forgets the fragment of the cell at index `index`, because the cell changed.
+    #[inline]
+    pub fn invalidate(&mut self, index: usize) {
+        self.spans[index] = None;
+    }
+
+    /// Returns the fragment of the cell at index `index`, formatting it if needed.
+    pub fn fragment(&mut self, index: usize, cell: &WorldCell) -> &str {
+        let (start, end) = match self.spans[index] {
+            Some(span) => span,
+            None => {
+                let start = self.text.len();
+                write_cell(&mut self.text, cell);
+                let span = (start, self.text.len());
+                self.spans[index] = Some(span);
+                self.cached.push(index);
+                span
+            }
+        };
+        &self.text[start..end]
+    }
+}
+
+/// Writes the content of a cell the way `voir` shows it: `joueur` for every player
+/// standing on it, then the name of every object lying on it, separated by spaces.
+fn write_cell(buf: &mut String, cell: &WorldCell) {
+    let players = (0..cell.players()).map(|_| "joueur");
+    let objects = ObjectClass::ALL
+        .into_iter()
+        .flat_map(|class| (0..cell.objects()[class]).map(move |_| class.name()));
+
+    for (i, name) in players.chain(objects).enumerate() {
+        if i != 0 {
+            buf.push(' ');
+        }
+        buf.push_str(name);
+    }
+}
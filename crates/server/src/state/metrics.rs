@@ -0,0 +1,51 @@
+use super::Command;
+
+/// Statistics about the execution of one kind of command.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CommandStats {
+    /// The number of commands that were executed.
+    pub executed: u64,
+    /// The number of commands that were answered with `ko`.
+    pub failed: u64,
+    /// The total number of ticks the commands spent between being scheduled and being
+    /// executed, including their own duration.
+    pub queued_ticks: u64,
+}
+
+impl CommandStats {
+    /// Returns the average number of ticks the commands spent between being scheduled and
+    /// being executed.
+    pub fn average_latency(&self) -> f64 {
+        match self.executed {
+            0 => 0.0,
+            executed => self.queued_ticks as f64 / executed as f64,
+        }
+    }
+}
+
+/// Execution statistics for every kind of command, to help balancing the game.
+#[derive(Debug, Clone, Default)]
+pub struct CommandMetrics {
+    /// The statistics of each kind of command, indexed by [`Command::index`].
+    stats: [CommandStats; Command::NAMES.len()],
+}
+
+impl CommandMetrics {
+    /// Records the execution of a command, `latency` ticks after it was scheduled.
+    pub fn record(&mut self, command: &Command, latency: u64, failed: bool) {
+        let stats = &mut self.stats[command.index()];
+        stats.executed += 1;
+        stats.failed += u64::from(failed);
+        stats.queued_ticks += latency;
+    }
+
+    /// Returns the name and the statistics of every kind of command that was executed at
+    /// least once.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &CommandStats)> {
+        Command::NAMES
+            .iter()
+            .zip(&self.stats)
+            .filter(|(_, stats)| stats.executed != 0)
+            .map(|(&name, stats)| (name, stats))
+    }
+}
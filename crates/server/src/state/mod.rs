@@ -4,16 +4,40 @@ use alloc::boxed::Box;
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::fmt::Write;
+use core::task::Waker;
+use core::time::Duration;
 
 use ft::collections::ArrayVec;
 
 use crate::args::Args;
 use crate::client::Client;
+use crate::gfx_connection::messages;
 use crate::player::PlayerError;
 
+mod audit;
+mod connections;
+mod end;
+mod events;
+mod journal;
+mod look;
+mod metrics;
+mod monitor;
+mod rng;
+mod snapshot;
 mod world;
 
+pub use self::audit::*;
+pub use self::connections::*;
+pub use self::end::*;
+pub use self::events::*;
+pub use self::journal::*;
+pub use self::look::*;
+pub use self::metrics::*;
+pub use self::monitor::*;
+pub use self::rng::*;
+pub use self::snapshot::*;
 pub use self::world::*;
+pub use resources::{elevation_requirement, ObjectClass, ResourceSet};
 
 /// The ID of a team.
 pub type TeamId = usize;
@@ -46,10 +70,53 @@ pub enum Command {
     LayAnEgg,
     /// The `connect_nbr` command.
     AvailableTeamSlots,
+    /// A line that could not be parsed as a command.
+    ///
+    /// It is scheduled like other commands so that its `ko` response is sent in order.
+    Invalid,
 }
 
 impl Command {
+    /// The name of every kind of command, indexed by [`Command::index`].
+    pub const NAMES: [&'static str; 13] = [
+        "avance",
+        "gauche",
+        "droite",
+        "voir",
+        "inventaire",
+        "prend",
+        "pose",
+        "expulse",
+        "broadcast",
+        "incantation",
+        "fork",
+        "connect_nbr",
+        "invalid",
+    ];
+
+    /// Returns the index of the kind of this command in [`Command::NAMES`].
+    pub fn index(&self) -> usize {
+        match self {
+            Command::MoveForward => 0,
+            Command::TurnLeft => 1,
+            Command::TurnRight => 2,
+            Command::LookAround => 3,
+            Command::Inventory => 4,
+            Command::PickUpObject(_) => 5,
+            Command::DropObject(_) => 6,
+            Command::KnockPlayer => 7,
+            Command::Broadcast(_) => 8,
+            Command::Evolve => 9,
+            Command::LayAnEgg => 10,
+            Command::AvailableTeamSlots => 11,
+            Command::Invalid => 12,
+        }
+    }
+
     /// Returns the number of ticks that this command takes to execute.
+    ///
+    /// The duration of [`Command::LayAnEgg`] may be changed with [`EggTiming`], this is
+    /// only its default value.
     pub fn ticks(&self) -> u32 {
         match self {
             Command::MoveForward => 7,
@@ -64,19 +131,7 @@ impl Command {
             Command::Evolve => 300,
             Command::LayAnEgg => 42,
             Command::AvailableTeamSlots => 0,
-        }
-    }
-
-    /// Executes the command, returning the response that must be sent back to the player.
-    #[allow(dead_code)]
-    pub fn execute(&self, player: &PlayerState, _world: &World, teams: &[Team]) -> Response {
-        match self {
-            Command::AvailableTeamSlots => {
-                let team_id = player.team_id;
-                let count = teams[team_id].available_slots;
-                Response::ConnectNbr(count)
-            }
-            _ => Response::Ok,
+            Command::Invalid => 0,
         }
     }
 }
@@ -85,8 +140,23 @@ impl Command {
 pub enum Response {
     /// The string `"ok"`.
     Ok,
+    /// The string `"ko"`.
+    Ko,
     /// The number of available slots in the team.
     ConnectNbr(u32),
+    /// The player was pushed by another one, coming from the provided direction.
+    Displaced(u32),
+    /// The string `"mort"`: the player starved to death or was killed by an admin.
+    Dead,
+    /// The content of the cells seen by the player, as written by [`World::look`].
+    Seen(String),
+    /// The objects held by the player, food counted in whole units.
+    Inventory(ResourceSet),
+    /// The string `"elevation en cours"`: the player takes part in an elevation that just
+    /// started.
+    Elevating,
+    /// The level of the player at the end of an elevation.
+    Elevated(u32),
 }
 
 impl Response {
@@ -94,8 +164,31 @@ impl Response {
     pub async fn send_to(&self, fd: ft::Fd, buf: &mut String) -> ft::Result<()> {
         match self {
             Response::Ok => ft_async::futures::write_all(fd, b"ok\n").await?,
+            Response::Ko => ft_async::futures::write_all(fd, b"ko\n").await?,
             Response::ConnectNbr(nbr) => {
-                writeln!(buf, "{}", nbr).unwrap();
+                let _ = writeln!(buf, "{}", nbr);
+                ft_async::futures::write_all(fd, buf.as_bytes()).await?
+            }
+            Response::Displaced(direction) => {
+                let _ = writeln!(buf, "deplacement {}", direction);
+                ft_async::futures::write_all(fd, buf.as_bytes()).await?
+            }
+            Response::Dead => ft_async::futures::write_all(fd, b"mort\n").await?,
+            Response::Seen(text) => ft_async::futures::write_all(fd, text.as_bytes()).await?,
+            Response::Inventory(inventory) => {
+                buf.push('{');
+                for (i, (class, count)) in inventory.iter().enumerate() {
+                    let separator = if i == 0 { "" } else { ", " };
+                    let _ = write!(buf, "{separator}{} {count}", class.name());
+                }
+                buf.push_str("}\n");
+                ft_async::futures::write_all(fd, buf.as_bytes()).await?
+            }
+            Response::Elevating => {
+                ft_async::futures::write_all(fd, b"elevation en cours\n").await?
+            }
+            Response::Elevated(level) => {
+                let _ = writeln!(buf, "niveau actuel : {}", level);
                 ft_async::futures::write_all(fd, buf.as_bytes()).await?
             }
         }
@@ -111,6 +204,10 @@ pub struct ScheduledCommand {
     pub command: Command,
     /// The number of ticks remaining before the command is executed.
     pub remaining_ticks: u32,
+    /// The tick at which the command was scheduled.
+    pub scheduled_at: u64,
+    /// Whether the command reached the front of the queue and started.
+    pub started: bool,
 }
 
 /// Information about the state of a team.
@@ -119,11 +216,161 @@ pub struct Team {
     name: Box<str>,
     /// The number of available slots in the team.
     available_slots: u32,
+    /// The center of the region in which the players of the team spawn, if spawns are
+    /// clustered.
+    spawn_anchor: Option<(u32, u32)>,
+    /// Whether a player ever joined the team.
+    joined: bool,
+    /// The objects the players of the team hold when they spawn.
+    starting_inventory: ResourceSet,
+}
+
+/// The ID of an egg.
+pub type EggId = usize;
+
+/// An egg laid by a player, which will create a new slot in its team once hatched.
+pub struct Egg {
+    /// The ID of the egg.
+    id: EggId,
+    /// The ID of the team the egg belongs to.
+    team_id: TeamId,
+    /// The ID of the player that laid the egg.
+    parent: PlayerId,
+    /// The horizontal position at which the egg was laid.
+    x: u32,
+    /// The vertical position at which the egg was laid.
+    y: u32,
+    /// The number of ticks remaining before the egg hatches or, once it has hatched,
+    /// before its slot expires.
+    remaining_ticks: u32,
+}
+
+impl Egg {
+    /// Returns the ID of the egg.
+    #[inline]
+    pub fn id(&self) -> EggId {
+        self.id
+    }
+
+    /// Returns the ID of the player that laid the egg.
+    #[inline]
+    pub fn parent(&self) -> PlayerId {
+        self.parent
+    }
+
+    /// Returns the position at which the egg was laid, as `(x, y)`.
+    #[inline]
+    pub fn position(&self) -> (u32, u32) {
+        (self.x, self.y)
+    }
+}
+
+/// An elevation in progress, started by an `incantation` command.
+struct Elevation {
+    /// The player that started the elevation.
+    initiator: PlayerId,
+    /// The position of the cell on which the elevation takes place.
+    position: (u32, u32),
+    /// The level of the players taking part in the elevation.
+    level: u32,
+    /// The players taking part in the elevation, including the initiator.
+    participants: Vec<PlayerId>,
+}
+
+/// How long eggs take to be laid and to hatch, and how long the slot they create stays
+/// available.
+#[derive(Debug, Clone, Copy)]
+pub struct EggTiming {
+    /// The number of ticks the `fork` command takes.
+    pub fork_ticks: u32,
+    /// The number of ticks it takes for an egg to hatch.
+    pub hatch_ticks: u32,
+    /// The number of ticks during which the slot created by a hatched egg stays
+    /// available, or `0` if it never expires.
+    pub slot_ticks: u32,
+}
+
+/// Statistics about a team, as reported to graphics monitors.
+pub struct TeamStats {
+    /// The number of players currently in the team.
+    pub players: u32,
+    /// The number of eggs laid by the team that have not hatched yet.
+    pub eggs: u32,
+    /// The sum of the levels of the players in the team.
+    pub levels: u32,
+    /// The number of available slots in the team.
+    pub available_slots: u32,
+}
+
+/// Statistics about the server, as reported by the `dbg` command.
+pub struct DebugStats {
+    /// The number of ticks that have passed since the beginning of the game.
+    pub tick: u64,
+    /// How late the last tick was simulated compared to when it was scheduled.
+    pub tick_lateness: Duration,
+    /// The number of ticks that were not simulated because the server fell behind.
+    pub skipped_ticks: u64,
+    /// The number of players currently connected.
+    pub players: usize,
+    /// The number of commands waiting to be executed, across all players.
+    pub queued_commands: usize,
+    /// The number of eggs that have not hatched yet.
+    pub eggs: usize,
+    /// The number of graphics monitors currently connected.
+    pub monitors: usize,
+    /// The number of messages dropped because graphics monitors did not read them fast
+    /// enough, across the monitors currently connected.
+    pub dropped_gfx_messages: u64,
 }
 
 /// The ID of a player.
 pub type PlayerId = usize;
 
+/// The number of food units a player holds when they spawn.
+pub const INITIAL_FOOD: u32 = 10;
+
+/// The objects a player holds when they spawn, unless their team was given a different
+/// starting inventory.
+pub const DEFAULT_STARTING_INVENTORY: ResourceSet =
+    ResourceSet::from_counts([INITIAL_FOOD, 0, 0, 0, 0, 0, 0]);
+
+/// The minimum number of ticks between two `pin` messages pushed to the graphics monitors
+/// for the same player.
+///
+/// Inventory changes happening in between are coalesced into a single message.
+const PIN_PUSH_PERIOD: u64 = 7;
+
+/// The number of ticks a unit of food keeps a player alive.
+pub const TICKS_PER_FOOD: u32 = 126;
+
+/// The lowest tick frequency accepted by the server, in ticks per second.
+pub const MIN_TICK_FREQUENCY: f32 = 0.01;
+
+/// The highest tick frequency accepted by the server, in ticks per second.
+pub const MAX_TICK_FREQUENCY: f32 = 10_000.0;
+
+/// Returns whether ticks may be simulated at `freq` ticks per second.
+///
+/// Every tick frequency set by the command-line, the configuration file, the graphics
+/// monitors, the admins or a saved game must be checked with this function.
+pub fn is_valid_tick_frequency(freq: f32) -> bool {
+    (MIN_TICK_FREQUENCY..=MAX_TICK_FREQUENCY).contains(&freq)
+}
+
+/// What happened to the food of a player during a tick.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Digestion {
+    /// The player was still digesting the last unit of food they ate.
+    Digesting,
+    /// The player ate a unit of food from their inventory.
+    Ate,
+    /// The player had nothing left to eat.
+    Starved,
+}
+
+/// The maximum number of commands a player may have waiting to be executed.
+pub const MAX_QUEUED_COMMANDS: usize = 10;
+
 /// The state of a player.
 pub struct PlayerState {
     /// The ID of the player.
@@ -133,25 +380,221 @@ pub struct PlayerState {
     /// The connection that was open with the player.
     conn: ft::Fd,
     /// The commands that have been buffered for the player.
-    commands: ArrayVec<ScheduledCommand, 10>,
+    commands: ArrayVec<ScheduledCommand, MAX_QUEUED_COMMANDS>,
+    /// The current level of the player.
+    level: u32,
+    /// The horizontal position of the player.
+    x: u32,
+    /// The vertical position of the player.
+    y: u32,
+    /// The direction the player is facing.
+    orientation: Orientation,
+    /// The objects held by the player.
+    inventory: ResourceSet,
+    /// Whether the inventory changed since it was last pushed to the graphics monitors.
+    inventory_changed: bool,
+    /// The tick at which the inventory was last pushed to the graphics monitors.
+    last_inventory_push: u64,
+    /// The number of queued commands last pushed to the graphics monitors that follow
+    /// command queues.
+    pushed_queue_len: usize,
+    /// The number of ticks before the player eats a unit of food from their inventory.
+    food_ticks: u32,
+    /// The token with which the player may resume after losing their connection, if
+    /// reconnections are enabled.
+    reconnect_token: Option<u64>,
+    /// The waker of the task handling the connection of the player, woken once they
+    /// leave the game so that the connection gets closed.
+    waker: Option<Waker>,
 }
 
 impl PlayerState {
-    /// Schedules a command for this player.
+    /// Creates a new level 1 player at the provided position, holding `inventory` and
+    /// facing a random direction.
+    pub fn new(
+        client: &Client,
+        team_id: TeamId,
+        (x, y): (u32, u32),
+        inventory: ResourceSet,
+        rng: &mut Rng,
+    ) -> Self {
+        Self {
+            player_id: client.id(),
+            team_id,
+            conn: client.fd(),
+            commands: ArrayVec::new(),
+            level: 1,
+            x,
+            y,
+            orientation: Orientation::ALL[rng.below(4) as usize],
+            inventory,
+            inventory_changed: false,
+            last_inventory_push: 0,
+            pushed_queue_len: 0,
+            // The first unit of food is eaten during the first tick.
+            food_ticks: 0,
+            reconnect_token: None,
+            waker: None,
+        }
+    }
+
+    /// Returns the number of units of food held by the player.
+    ///
+    /// This is what the players and the graphics monitors are told, in the response to
+    /// `inventaire` and in `pin`. The ticks left before the unit being digested runs out
+    /// are only part of [`PlayerState::time_to_live`].
+    #[inline]
+    pub fn food_units(&self) -> u32 {
+        self.inventory[ObjectClass::Food]
+    }
+
+    /// Returns the number of ticks the player will stay alive without eating anything
+    /// new.
+    ///
+    /// A player with a time to live of zero starves during the next tick.
+    pub fn time_to_live(&self) -> u64 {
+        u64::from(self.food_units()) * u64::from(TICKS_PER_FOOD) + u64::from(self.food_ticks)
+    }
+
+    /// Makes a tick pass for the player, who eats a unit of food from their inventory
+    /// once the previous one is digested.
+    ///
+    /// The time to live of a player that did not starve is one less afterwards.
+    fn consume_tick(&mut self) -> Digestion {
+        let mut digestion = Digestion::Digesting;
+        if self.food_ticks == 0 {
+            if !self.inventory.remove(ObjectClass::Food) {
+                return Digestion::Starved;
+            }
+            self.food_ticks = TICKS_PER_FOOD;
+            self.inventory_changed = true;
+            digestion = Digestion::Ate;
+        }
+        self.food_ticks -= 1;
+        digestion
+    }
+
+    /// Returns the ID of the player.
+    #[inline]
+    pub fn id(&self) -> PlayerId {
+        self.player_id
+    }
+
+    /// Returns the ID of the team the player is in.
+    #[inline]
+    pub fn team_id(&self) -> TeamId {
+        self.team_id
+    }
+
+    /// Returns the current level of the player.
+    #[inline]
+    pub fn level(&self) -> u32 {
+        self.level
+    }
+
+    /// Returns the position of the player, as `(x, y)`.
+    #[inline]
+    pub fn position(&self) -> (u32, u32) {
+        (self.x, self.y)
+    }
+
+    /// Returns the direction the player is facing.
+    #[inline]
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Returns the objects held by the player.
+    #[inline]
+    pub fn inventory(&self) -> &ResourceSet {
+        &self.inventory
+    }
+
+    /// Returns the number of commands waiting to be executed for the player.
+    #[inline]
+    pub fn queued_commands(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns the token with which the player may resume after losing their connection,
+    /// if reconnections are enabled.
+    #[inline]
+    pub fn reconnect_token(&self) -> Option<u64> {
+        self.reconnect_token
+    }
+
+    /// Schedules a command for this player at tick `now`, completing after `ticks` ticks.
     ///
     /// # Returns
     ///
     /// `true` if the command has been scheduled, `false` if the buffer is full.
-    pub fn schedule_command(&mut self, command: Command) -> bool {
+    pub fn schedule_command(&mut self, command: Command, ticks: u32, now: u64) -> bool {
         self.commands
             .try_push(ScheduledCommand {
-                remaining_ticks: command.ticks(),
+                remaining_ticks: ticks,
+                scheduled_at: now,
+                started: false,
                 command,
             })
             .is_ok()
     }
 }
 
+/// A player whose connection was lost, waiting to reconnect.
+struct DetachedPlayer {
+    /// The state of the player, as it was when the connection was lost.
+    player: Box<PlayerState>,
+    /// The tick at which the player is removed from the game if they did not reconnect.
+    expires_at: u64,
+}
+
+/// The number of writes to a graphics monitor that may fail in a row before the monitor
+/// is dropped.
+const MAX_FAILED_WRITES: u32 = 3;
+
+/// The state of a graphics monitor.
+pub struct GfxMonitor {
+    /// The ID of the client.
+    id: usize,
+    /// The connection that was open with the monitor.
+    conn: ft::Fd,
+    /// The number of writes to the monitor that failed in a row.
+    failed_writes: u32,
+    /// Whether the monitor receives its messages as JSON lines.
+    json: bool,
+    /// Whether the monitor receives the `pqc` messages, telling how many commands every
+    /// player has queued.
+    follows_queues: bool,
+    /// The messages that have been pushed to the monitor but not written yet.
+    pending: Vec<u8>,
+    /// An empty buffer that takes the place of `pending` while its messages are being
+    /// written, so that neither has to be allocated again.
+    spare: Vec<u8>,
+    /// Whether the first message of `pending` has been partly written.
+    partial_line: bool,
+    /// Whether the monitor is still receiving the initial state of the game.
+    ///
+    /// Its queue is not written meanwhile, and only keeps the messages the initial state
+    /// may have missed, see [`State::finish_gfx_monitor_loading`].
+    loading: bool,
+    /// The number of messages dropped because the monitor did not read them fast enough.
+    dropped_messages: u64,
+}
+
+impl GfxMonitor {
+    /// Returns whether the monitor receives its messages as JSON lines.
+    #[inline]
+    pub fn json(&self) -> bool {
+        self.json
+    }
+
+    /// Returns whether the monitor receives the `pqc` messages.
+    #[inline]
+    pub fn follows_queues(&self) -> bool {
+        self.follows_queues
+    }
+}
+
 /// The global state of the server, responsible for managing the clients and the game.
 #[allow(clippy::vec_box)] // `PlayerState` is a huge struct, copying it around is not a good idea.
 pub struct State {
@@ -159,28 +602,167 @@ pub struct State {
     teams: Box<[Team]>,
     /// The list of players currently connected to the server.
     players: Vec<Box<PlayerState>>,
+    /// The players whose connection was lost and that may still reconnect.
+    detached: Vec<DetachedPlayer>,
+    /// How long the state of a disconnected player is kept, zero disabling reconnections.
+    reconnect_grace: Duration,
+    /// The generator of reconnection tokens.
+    ///
+    /// It is kept apart from the generator of the game so that tokens do not change how
+    /// games unfold, and cannot be guessed from the seed of the game.
+    token_rng: Rng,
+    /// When the state was created.
+    started_at: ft::Instant,
+    /// The connections accepted by the server.
+    connections: ConnectionLog,
+    /// The list of graphics monitors currently connected to the server.
+    gfx_monitors: Vec<GfxMonitor>,
+    /// The maximum number of bytes waiting to be written to a graphics monitor.
+    gfx_buffer_cap: usize,
+    /// What happens when a graphics monitor exceeds `gfx_buffer_cap`.
+    gfx_overflow: OverflowPolicy,
+    /// Whether graphics monitors may connect to the server.
+    gfx_enabled: bool,
+    /// The connections of the players killed since the last tick, which must receive
+    /// `mort`.
+    deaths: Vec<ft::Fd>,
+    /// The wakers of the tasks handling the players that left the game, woken once the
+    /// responses of the tick are sent.
+    removed_wakers: Vec<Waker>,
+    /// The eggs that have been laid and have not hatched yet.
+    eggs: Vec<Egg>,
+    /// The eggs that have hatched and whose slot has not been taken yet, from the oldest
+    /// to the youngest.
+    ///
+    /// Only eggs whose slot may expire are tracked.
+    hatched_eggs: Vec<Egg>,
+    /// How long eggs take to be laid and to hatch.
+    egg_timing: EggTiming,
+    /// The average number of objects of each class per cell kept by respawns, indexed by
+    /// [`ObjectClass::index`].
+    densities: [f32; 7],
+    /// The number of ticks between two respawns, `0` disabling them.
+    respawn_ticks: u32,
+    /// The ID of the next egg to be laid.
+    next_egg_id: EggId,
+    /// The elevations in progress.
+    elevations: Vec<Elevation>,
     /// The current state of the world.
     world: World,
+    /// The number of ticks simulated per second.
+    tick_frequency: f32,
+    /// The number of ticks that have passed since the beginning of the game.
+    tick: u64,
+    /// Whether the game is paused.
+    paused: bool,
+    /// The rule deciding which team wins the game.
+    win_condition: WinCondition,
+    /// The team that won the game, once it is over.
+    winner: Option<TeamId>,
+    /// How far from the center of their team's region players spawn, if spawns are
+    /// clustered.
+    spawn_radius: u32,
+    /// Execution statistics for every kind of command.
+    command_metrics: CommandMetrics,
+    /// Every command executed by the players, if the journal is enabled.
+    journal: Option<CommandJournal>,
+    /// The objects that entered and left the game.
+    ledger: ResourceLedger,
+    /// The number of ticks between two checks of the ledger, `0` disabling them.
+    audit_ticks: u32,
+    /// Whether the number of players on every cell is checked after every command.
+    check_cells: bool,
+    /// How late the last tick was simulated compared to when it was scheduled.
+    tick_lateness: Duration,
+    /// The number of ticks that were not simulated because the server fell behind.
+    skipped_ticks: u64,
+    /// The log to which the events of the game are written.
+    event_log: EventLog,
+    /// The random number generator used by the game.
+    rng: Rng,
+    /// The maximum delay randomly added to the completion of each command.
+    jitter: Duration,
+    /// The number of invalid commands a player may send in a row before being
+    /// disconnected.
+    max_violations: u32,
+    /// The messages that must be pushed to every graphics monitor.
+    ///
+    /// This buffer is flushed at the end of every tick.
+    gfx_outbox: String,
+    /// The `pqc` messages that must be pushed to the graphics monitors that follow
+    /// command queues, flushed at the end of every tick.
+    gfx_queue_outbox: String,
 }
 
 impl State {
     /// Creates a new instance of [`State`] from the arguments passed to the server.
     pub fn from_args(args: &Args) -> Self {
+        let mut rng = Rng::new(args.seed);
+        let world = World::new(args.width, args.height, &mut rng);
+
+        let anchors = match args.spawn_radius {
+            0 => Vec::new(),
+            _ => world.spread_anchors(args.teams.len(), &mut rng),
+        };
         let teams = args
             .teams
             .iter()
-            .map(|&team| Team {
+            .enumerate()
+            .map(|(i, &team)| Team {
                 name: team.into(),
                 available_slots: args.initial_slot_count,
+                spawn_anchor: anchors.get(i).copied(),
+                joined: false,
+                starting_inventory: DEFAULT_STARTING_INVENTORY,
             })
             .collect();
-
-        let world = World::new(args.width, args.height);
+        let ledger = ResourceLedger::new(world.object_totals());
 
         Self {
             teams,
             players: Vec::new(),
+            detached: Vec::new(),
+            reconnect_grace: Duration::from_secs(args.reconnect_grace_secs.into()),
+            token_rng: Rng::new(!args.seed),
+            started_at: ft::Clock::MONOTONIC.get(),
+            connections: ConnectionLog::default(),
+            gfx_monitors: Vec::new(),
+            gfx_buffer_cap: args.gfx_buffer_cap,
+            gfx_overflow: args.gfx_overflow,
+            gfx_enabled: args.graphic,
+            deaths: Vec::new(),
+            removed_wakers: Vec::new(),
+            eggs: Vec::new(),
+            hatched_eggs: Vec::new(),
+            egg_timing: EggTiming {
+                fork_ticks: args.fork_ticks,
+                hatch_ticks: args.hatch_ticks,
+                slot_ticks: args.hatched_slot_ticks,
+            },
+            densities: ObjectClass::ALL.map(default_density),
+            respawn_ticks: 0,
+            next_egg_id: 0,
+            elevations: Vec::new(),
             world,
+            tick_frequency: args.tick_frequency,
+            tick: 0,
+            paused: false,
+            win_condition: args.win_condition,
+            winner: None,
+            spawn_radius: args.spawn_radius,
+            command_metrics: CommandMetrics::default(),
+            journal: args.journal_file.map(|_| CommandJournal::default()),
+            ledger,
+            audit_ticks: args.audit_ticks,
+            check_cells: args.check_cells,
+            tick_lateness: Duration::ZERO,
+            skipped_ticks: 0,
+            event_log: EventLog::new(args.event_log_fd.map(ft::Fd::from_raw)),
+            rng,
+            jitter: Duration::from_millis(args.jitter_ms.into()),
+            max_violations: args.max_violations,
+            gfx_outbox: String::new(),
+            gfx_queue_outbox: String::new(),
         }
     }
 
@@ -212,40 +794,348 @@ impl State {
         }
 
         team.available_slots -= 1;
+        team.joined = true;
 
-        self.players.push(Box::new(PlayerState {
-            player_id: client.id(),
+        // The slot of the oldest hatched egg is taken first, it is the closest to expiring.
+        if let Some(index) = self.hatched_eggs.iter().position(|e| e.team_id == team_id) {
+            self.hatched_eggs.remove(index);
+        }
+        self.debug_check_slots(team_id);
+        let team = &self.teams[team_id];
+
+        let position = match team.spawn_anchor {
+            Some(anchor) => self
+                .world
+                .random_near(anchor, self.spawn_radius, &mut self.rng),
+            None => (
+                self.rng.below(self.world.width()),
+                self.rng.below(self.world.height()),
+            ),
+        };
+        let mut player = PlayerState::new(
+            client,
             team_id,
-            conn: client.fd(),
-            commands: ArrayVec::new(),
-        }));
+            position,
+            team.starting_inventory,
+            &mut self.rng,
+        );
+        player.last_inventory_push = self.tick;
+        if !self.reconnect_grace.is_zero() {
+            // The timing of connections is hard to predict, which makes the tokens hard to
+            // guess.
+            let elapsed = ft::Clock::MONOTONIC.get().saturating_sub(self.started_at);
+            self.token_rng.mix(elapsed.as_nanos() as u64);
+            player.reconnect_token = Some(self.token_rng.next_u64());
+        }
+        self.ledger.spawn(&player.inventory);
+
+        if !self.gfx_monitors.is_empty() {
+            messages::player_new(&mut self.gfx_outbox, &player, &team.name);
+            messages::player_inventory(&mut self.gfx_outbox, &player);
+            messages::player_time_to_live(&mut self.gfx_outbox, &player);
+        }
+
+        self.world.enter(player.position());
+        self.players.push(Box::new(player));
+        self.connections
+            .set_player(client.id(), &team.name, client.id());
+
+        self.event_log.log(
+            self.tick,
+            Event::PlayerJoined {
+                player: client.id(),
+                team: &team.name,
+            },
+        );
 
         Ok(client.id())
     }
 
+    /// Checks that every hatched egg of a team holds one of its available slots.
+    fn debug_check_slots(&self, team_id: TeamId) {
+        let held = self.hatched_eggs.iter().filter(|e| e.team_id == team_id);
+        debug_assert!(
+            held.count() <= self.teams[team_id].available_slots as usize,
+            "team #{team_id} has more hatched eggs than available slots",
+        );
+    }
+
     /// Returns the index of a player in the list of players.
     #[inline]
     fn player_index_by_id(&self, player: PlayerId) -> Option<usize> {
         self.players.iter().position(|p| p.player_id == player)
     }
 
-    /// Returns the state of the player with the provided ID.
-    pub fn player_mut(&mut self, player: PlayerId) -> &mut PlayerState {
-        self.player_index_by_id(player)
-            .and_then(|i| self.players.get_mut(i))
-            .expect("no player with the provided ID")
+    /// Returns the state of the player with the provided ID, if they are connected.
+    pub fn player(&self, player: PlayerId) -> Option<&PlayerState> {
+        self.player_index_by_id(player).map(|i| &*self.players[i])
+    }
+
+    /// Returns the players currently connected to the server.
+    pub fn players(&self) -> impl Iterator<Item = &PlayerState> {
+        self.players.iter().map(|p| &**p)
+    }
+
+    /// Schedules a command for the player with the provided ID.
+    ///
+    /// When jitter is enabled, the completion of the command is delayed by a random
+    /// number of ticks fitting in the jitter window.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the command has been scheduled, `false` if the buffer is full or if the
+    /// player is no longer in the game.
+    pub fn schedule_command(&mut self, player: PlayerId, command: Command) -> bool {
+        let window = (self.jitter.as_secs_f32() * self.tick_frequency) as u32;
+        let extra_ticks = match window {
+            0 => 0,
+            _ => self.rng.below(window + 1),
+        };
+
+        let ticks = match command {
+            Command::LayAnEgg => self.egg_timing.fork_ticks,
+            _ => command.ticks(),
+        };
+
+        let Some(index) = self.player_index_by_id(player) else {
+            return false;
+        };
+        self.players[index].schedule_command(command, ticks + extra_ticks, self.tick)
     }
 
     /// Removes a player from the server.
+    ///
+    /// The slot used by the player is not released: new slots are only created by
+    /// hatching eggs.
+    ///
+    /// Nothing happens if the player has already been removed (for instance, because
+    /// they were killed).
     pub fn leave(&mut self, player: PlayerId) {
+        if let Some(index) = self.player_index_by_id(player) {
+            self.remove_player(index);
+        }
+    }
+
+    /// Keeps the state of a player whose connection was lost, so that they can reconnect
+    /// during the grace period.
+    ///
+    /// The player is removed from the game right away if reconnections are disabled.
+    /// Their pending commands are dropped, but they keep eating their food and may starve
+    /// before reconnecting. Graphics monitors receive `pdi` right away, and `pnw` once the
+    /// player reconnects.
+    pub fn detach(&mut self, player: PlayerId) {
+        if self.reconnect_grace.is_zero() {
+            self.leave(player);
+            return;
+        }
+        let Some(index) = self.player_index_by_id(player) else {
+            return;
+        };
+
+        let mut player = self.players.remove(index);
+        player.commands.clear();
+        player.waker = None;
+        self.world.leave(player.position());
+        self.cancel_elevation(player.player_id);
+        // The slot of a detached player stays taken until they leave the game for good.
+        self.debug_check_slots(player.team_id);
+        // The player is no longer in the world: graphics monitors stop drawing them until
+        // they reconnect.
+        if !self.gfx_monitors.is_empty() {
+            messages::player_death(&mut self.gfx_outbox, &player);
+        }
+
+        let grace_ticks = (self.reconnect_grace.as_secs_f32() * self.tick_frequency) as u64;
+        ft_log::info!(
+            "player #{} lost their connection, waiting {} ticks for them to reconnect",
+            player.player_id,
+            grace_ticks,
+        );
+        self.detached.push(DetachedPlayer {
+            player,
+            expires_at: self.tick + grace_ticks,
+        });
+    }
+
+    /// Disconnects the player using `conn`, whose connection failed.
+    ///
+    /// The player is detached as if they had closed the connection, and the task handling
+    /// it is woken by [`State::wake_removed_players`] so that it closes it.
+    ///
+    /// # Returns
+    ///
+    /// The ID of the player, or `None` if no player uses `conn`.
+    pub fn disconnect(&mut self, conn: ft::Fd) -> Option<PlayerId> {
+        let player = self.players.iter_mut().find(|p| p.conn == conn)?;
+        let player_id = player.player_id;
+        self.removed_wakers.extend(player.waker.take());
+        self.detach(player_id);
+        Some(player_id)
+    }
+
+    /// Gives the state of a disconnected player to a new connection.
+    ///
+    /// # Returns
+    ///
+    /// The ID of the player and of their team.
+    pub fn reattach(
+        &mut self,
+        client: &Client,
+        token: u64,
+    ) -> Result<(PlayerId, TeamId), PlayerError> {
         let index = self
-            .players
+            .detached
             .iter()
-            .position(|p| p.player_id == player)
-            .expect("no player with the provided ID found");
+            .position(|d| d.player.reconnect_token == Some(token))
+            .ok_or(PlayerError::UnknownReconnectToken)?;
+
+        let mut player = self.detached.remove(index).player;
+        player.conn = client.fd();
+        let ids = (player.player_id, player.team_id);
+        self.world.enter(player.position());
+        if !self.gfx_monitors.is_empty() {
+            messages::player_new(&mut self.gfx_outbox, &player, &self.teams[ids.1].name);
+            messages::player_inventory(&mut self.gfx_outbox, &player);
+            messages::player_time_to_live(&mut self.gfx_outbox, &player);
+        }
+        self.players.push(player);
+        self.connections
+            .set_player(client.id(), &self.teams[ids.1].name, ids.0);
+
+        ft_log::info!("player #{} reconnected as client #{}", ids.0, client.id());
+        Ok(ids)
+    }
+
+    /// Kills a player, removing them from the game.
+    ///
+    /// They receive `mort` with the responses of the next tick, after which their
+    /// connection is closed.
+    ///
+    /// # Returns
+    ///
+    /// `false` if no such player is connected.
+    pub fn kill(&mut self, player: PlayerId) -> bool {
+        let Some(index) = self.player_index_by_id(player) else {
+            return false;
+        };
+        let player = self.remove_player(index);
+        self.deaths.push(player.conn);
+        true
+    }
+
+    /// Records the waker of the task handling the connection of a player, which is woken
+    /// once the player leaves the game.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the player is no longer in the game.
+    pub fn watch_player(&mut self, player: PlayerId, waker: &Waker) -> bool {
+        let Some(index) = self.player_index_by_id(player) else {
+            return false;
+        };
+        match &mut self.players[index].waker {
+            Some(current) if current.will_wake(waker) => (),
+            slot => *slot = Some(waker.clone()),
+        }
+        true
+    }
+
+    /// Wakes the tasks handling the players that left the game, so that they close the
+    /// connections.
+    ///
+    /// This must be called once the responses of the tick are sent, `mort` included.
+    pub fn wake_removed_players(&mut self) {
+        for waker in self.removed_wakers.drain(..) {
+            waker.wake();
+        }
+    }
+
+    /// Removes the player at index `index` in the list of players, notifying the graphics
+    /// monitors and the event log.
+    fn remove_player(&mut self, index: usize) -> Box<PlayerState> {
+        let mut player = self.players.remove(index);
+        self.removed_wakers.extend(player.waker.take());
+        self.world.leave(player.position());
+        self.cancel_elevation(player.player_id);
+        if !self.gfx_monitors.is_empty() {
+            messages::player_death(&mut self.gfx_outbox, &player);
+        }
+        self.forget_player(&player);
+        player
+    }
+
+    /// Notifies the event log that a player left the game, along with their inventory.
+    ///
+    /// The graphics monitors are notified when the player leaves the world, which happens
+    /// earlier for detached players.
+    fn forget_player(&mut self, player: &PlayerState) {
+        // The inventory of the player leaves the game with them.
+        self.ledger.consume(&player.inventory);
+
+        self.event_log.log(
+            self.tick,
+            Event::PlayerLeft {
+                player: player.player_id,
+                team: &self.teams[player.team_id].name,
+            },
+        );
+    }
+
+    /// Moves a player to the provided position.
+    ///
+    /// # Returns
+    ///
+    /// `false` if no such player is connected or if the position is outside of the
+    /// world.
+    pub fn teleport(&mut self, player: PlayerId, x: u32, y: u32) -> bool {
+        if x >= self.world.width() || y >= self.world.height() {
+            return false;
+        }
+        let Some(index) = self.player_index_by_id(player) else {
+            return false;
+        };
+
+        let player = &mut self.players[index];
+        self.world.move_player(player.position(), (x, y));
+        (player.x, player.y) = (x, y);
+        if !self.gfx_monitors.is_empty() {
+            messages::player_position(&mut self.gfx_outbox, player);
+        }
+        true
+    }
+
+    /// Adds an object to the cell at the provided position, pushing the new content of
+    /// the cell to the graphics monitors right away.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the position is outside of the world.
+    pub fn spawn_object(&mut self, x: u32, y: u32, class: ObjectClass) -> bool {
+        if x >= self.world.width() || y >= self.world.height() {
+            return false;
+        }
+        self.world.add_object(x, y, class);
+        let mut spawned = ResourceSet::default();
+        spawned.add(class);
+        self.ledger.spawn(&spawned);
+        // The `bct` message is not held back until the end of the tick, like the `ppo`
+        // message of a teleported player.
+        self.push_cell_updates();
+        true
+    }
+
+    /// Returns whether the game is paused.
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
 
-        let player = self.players.remove(index);
-        self.teams[player.team_id].available_slots += 1;
+    /// Pauses or resumes the game.
+    ///
+    /// While the game is paused, ticks are not simulated: commands, eggs and the tick
+    /// counter stay where they are.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
     }
 
     /// Returns the number of available slots in the specified team.
@@ -254,56 +1144,1155 @@ impl State {
         self.teams[team].available_slots
     }
 
-    /// Returns the current state of the world.
+    /// Returns the name of the specified team.
     #[inline]
-    pub fn world(&self) -> &World {
-        &self.world
+    pub fn team_name(&self, team: TeamId) -> &str {
+        &self.teams[team].name
     }
 
-    /// Notifies the state that a whole tick has passed.
-    ///
-    /// # Arguments
-    ///
-    /// - `responses` - a list of responses that must be sent to their associated file
-    ///   descriptiors.
-    #[allow(clippy::unwrap_used)]
-    pub fn tick(&mut self, responses: &mut Vec<(ft::Fd, Response)>) {
-        for player in &mut self.players {
-            let Some(command) = player.commands.first_mut() else {
-                continue;
-            };
+    /// Returns the eggs that have not hatched yet.
+    pub fn eggs(&self) -> impl Iterator<Item = &Egg> {
+        self.eggs.iter()
+    }
 
-            if command.remaining_ticks > 0 {
-                command.remaining_ticks -= 1;
-                continue;
-            }
+    /// Computes statistics about the specified team.
+    pub fn team_stats(&self, team: TeamId) -> TeamStats {
+        let mut stats = TeamStats {
+            players: 0,
+            eggs: self.eggs.iter().filter(|e| e.team_id == team).count() as u32,
+            levels: 0,
+            available_slots: self.teams[team].available_slots,
+        };
 
-            // This unwrap can ever fail because the case where there is no
-            // first element is handled above.
-            // Also, we can't optimize this with a swap_remove because the
-            // order in which commands are inserted matters. Maybe we can use
-            // a VecDeque instead, but that would be vastly overkill for those
-            // 10 poor elements.
-            let cmd = player.commands.remove(0).unwrap();
+        for player in self.players.iter().filter(|p| p.team_id == team) {
+            stats.players += 1;
+            stats.levels += player.level;
+        }
 
-            // Execute the command.
-            ft_log::trace!(
-                "executing command for #{}: {:?}",
-                player.player_id,
-                cmd.command,
-            );
+        stats
+    }
 
-            let response = cmd.command.execute(player, &self.world, &self.teams);
-            responses.push((player.conn, response));
-        }
+    /// Returns the names of the teams, in the order of their IDs.
+    pub fn team_names(&self) -> impl Iterator<Item = &str> {
+        self.teams.iter().map(|team| &*team.name)
     }
-}
 
-/// The global state of the server.
-static STATE: ft::Mutex<Option<State>, ft::sync::mutex::NoBlockMutex> = ft::Mutex::new(None);
+    /// Returns the teams whose players spawn with another inventory than
+    /// [`DEFAULT_STARTING_INVENTORY`], with their starting inventory.
+    pub fn team_handicaps(&self) -> impl Iterator<Item = (&str, &ResourceSet)> {
+        self.teams
+            .iter()
+            .filter(|team| team.starting_inventory != DEFAULT_STARTING_INVENTORY)
+            .map(|team| (&*team.name, &team.starting_inventory))
+    }
 
-/// Initializes the global [`State`].
-///
+    /// Changes the objects the players of a team hold when they spawn.
+    ///
+    /// Players already in the game keep their inventory.
+    pub fn set_starting_inventory(&mut self, team_id: TeamId, inventory: ResourceSet) {
+        let team = &mut self.teams[team_id];
+        team.starting_inventory = inventory;
+
+        if !self.gfx_monitors.is_empty() {
+            messages::team_starting_inventory(&mut self.gfx_outbox, &team.name, &inventory);
+        }
+    }
+
+    /// Returns the current state of the world.
+    #[inline]
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Returns the number of invalid commands a player may send in a row before being
+    /// disconnected.
+    #[inline]
+    pub fn max_violations(&self) -> u32 {
+        self.max_violations
+    }
+
+    /// Returns the number of ticks simulated per second.
+    #[inline]
+    pub fn tick_frequency(&self) -> f32 {
+        self.tick_frequency
+    }
+
+    /// Sets the number of ticks simulated per second.
+    ///
+    /// The remaining ticks of scheduled commands and eggs are scaled so that they keep
+    /// completing after the same amount of time, in seconds. Food is counted in ticks and
+    /// is not scaled. Graphics monitors are notified of the new time unit.
+    pub fn set_tick_frequency(&mut self, freq: f32) {
+        debug_assert!(is_valid_tick_frequency(freq));
+
+        let ratio = freq / self.tick_frequency;
+        // `f32::round` is not available without `std`. The values are never negative.
+        let rescale = |ticks: u32| (ticks as f32 * ratio + 0.5) as u32;
+
+        for player in &mut self.players {
+            for command in player.commands.iter_mut() {
+                command.remaining_ticks = rescale(command.remaining_ticks);
+            }
+        }
+        for egg in self.eggs.iter_mut().chain(&mut self.hatched_eggs) {
+            egg.remaining_ticks = rescale(egg.remaining_ticks);
+        }
+
+        self.tick_frequency = freq;
+
+        if !self.gfx_monitors.is_empty() {
+            let _ = writeln!(self.gfx_outbox, "sgt {freq}");
+        }
+    }
+
+    /// Returns how long eggs take to be laid and to hatch.
+    #[inline]
+    pub fn egg_timing(&self) -> EggTiming {
+        self.egg_timing
+    }
+
+    /// Sets how long eggs take to be laid and to hatch.
+    ///
+    /// Eggs that are already laid or hatched keep their current timing, as do `fork`
+    /// commands that are already scheduled.
+    pub fn set_egg_timing(&mut self, timing: EggTiming) {
+        self.egg_timing = timing;
+    }
+
+    /// Sets the average number of objects of a class per cell, which the next respawns
+    /// restore.
+    pub fn set_density(&mut self, class: ObjectClass, density: f32) {
+        self.densities[class.index()] = density;
+    }
+
+    /// Sets the number of ticks between two respawns, `0` disabling them.
+    pub fn set_respawn_ticks(&mut self, ticks: u32) {
+        self.respawn_ticks = ticks;
+    }
+
+    /// Records how late the current tick is simulated compared to when it was scheduled.
+    #[inline]
+    pub fn set_tick_lateness(&mut self, lateness: Duration) {
+        self.tick_lateness = lateness;
+    }
+
+    /// Records that `count` ticks were not simulated because the server fell behind.
+    #[inline]
+    pub fn record_skipped_ticks(&mut self, count: u64) {
+        self.skipped_ticks += count;
+    }
+
+    /// Computes statistics about the server.
+    pub fn debug_stats(&self) -> DebugStats {
+        DebugStats {
+            tick: self.tick,
+            tick_lateness: self.tick_lateness,
+            skipped_ticks: self.skipped_ticks,
+            players: self.players.len(),
+            queued_commands: self.players.iter().map(|p| p.commands.len()).sum(),
+            eggs: self.eggs.len(),
+            monitors: self.gfx_monitors.len(),
+            dropped_gfx_messages: self.gfx_monitors.iter().map(|m| m.dropped_messages).sum(),
+        }
+    }
+
+    /// Records that a connection coming from `address` was accepted for client `id`.
+    pub fn open_connection(&mut self, id: usize, address: Box<str>) {
+        self.connections.open(id, address, self.tick);
+    }
+
+    /// Records that client `id` is an admin session.
+    pub fn set_admin_connection(&mut self, id: usize) {
+        self.connections.set_role(id, Role::Admin);
+    }
+
+    /// Records that the connection of client `id` was closed, logging what is known
+    /// about it.
+    pub fn close_connection(&mut self, id: usize, reason: Box<str>) {
+        if let Some(connection) = self.connections.close(id, reason, self.tick) {
+            ft_log::info!("connection {connection}");
+        }
+    }
+
+    /// Returns the open connections and the last closed ones, in the order in which they
+    /// were accepted.
+    pub fn connections(&self) -> impl Iterator<Item = &Connection> {
+        self.connections.iter()
+    }
+
+    /// Returns execution statistics for every kind of command.
+    #[inline]
+    pub fn command_metrics(&self) -> &CommandMetrics {
+        &self.command_metrics
+    }
+
+    /// Writes the commands recorded in the journal to `buf`, as described by
+    /// [`CommandJournal::write`].
+    ///
+    /// # Returns
+    ///
+    /// `false` if the journal is disabled.
+    pub fn write_journal(&self, buf: &mut String, prefix: &str, player: Option<PlayerId>) -> bool {
+        let Some(journal) = &self.journal else {
+            return false;
+        };
+        journal.write(buf, prefix, player, &self.teams);
+        true
+    }
+
+    /// Returns the number of commands recorded in the journal, if it is enabled.
+    pub fn journal_len(&self) -> Option<usize> {
+        self.journal.as_ref().map(CommandJournal::len)
+    }
+
+    /// Registers a graphics monitor to the server, before its initial state is sent.
+    ///
+    /// Nothing is written to the monitor until [`State::finish_gfx_monitor_loading`] is
+    /// called: its task sends the first parts of the initial state itself meanwhile.
+    pub fn add_gfx_monitor(&mut self, client: &Client) {
+        self.gfx_monitors.push(GfxMonitor {
+            id: client.id(),
+            conn: client.fd(),
+            failed_writes: 0,
+            json: false,
+            follows_queues: false,
+            pending: Vec::new(),
+            spare: Vec::new(),
+            partial_line: false,
+            loading: true,
+            dropped_messages: 0,
+        });
+        self.connections.set_role(client.id(), Role::Monitor);
+
+        ft_log::info!(
+            "graphics monitor #{} connected ({} connected)",
+            client.id(),
+            self.gfx_monitors.len(),
+        );
+    }
+
+    /// Removes a graphics monitor from the server.
+    ///
+    /// Nothing happens if the monitor has already been removed.
+    pub fn remove_gfx_monitor(&mut self, id: usize) {
+        let Some(index) = self.gfx_monitors.iter().position(|m| m.id == id) else {
+            return;
+        };
+
+        self.gfx_monitors.swap_remove(index);
+
+        ft_log::info!(
+            "graphics monitor #{id} disconnected ({} connected)",
+            self.gfx_monitors.len(),
+        );
+    }
+
+    /// Moves the messages that must be pushed to every graphics monitor into `buf`, which
+    /// is expected to be empty.
+    ///
+    /// The previous content of `buf` is kept to be re-used as the next outbox.
+    pub fn take_gfx_outbox(&mut self, buf: &mut String) {
+        core::mem::swap(&mut self.gfx_outbox, buf);
+        self.gfx_outbox.clear();
+    }
+
+    /// Moves the `pqc` messages that must be pushed to the graphics monitors that follow
+    /// command queues into `buf`, which is expected to be empty.
+    pub fn take_gfx_queue_outbox(&mut self, buf: &mut String) {
+        core::mem::swap(&mut self.gfx_queue_outbox, buf);
+        self.gfx_queue_outbox.clear();
+    }
+
+    /// Returns whether graphics monitors may connect to the server.
+    #[inline]
+    pub fn gfx_enabled(&self) -> bool {
+        self.gfx_enabled
+    }
+
+    /// Returns the graphics monitors currently connected to the server.
+    #[inline]
+    pub fn gfx_monitors(&self) -> &[GfxMonitor] {
+        &self.gfx_monitors
+    }
+
+    /// Sets whether a graphics monitor receives its messages as JSON lines.
+    pub fn set_gfx_monitor_json(&mut self, id: usize, json: bool) {
+        if let Some(monitor) = self.gfx_monitors.iter_mut().find(|m| m.id == id) {
+            monitor.json = json;
+        }
+    }
+
+    /// Queues the last part of the initial state of a graphics monitor, after which it
+    /// receives every message pushed to the monitors.
+    ///
+    /// The changes to the cells and to the time unit pushed while the first parts were
+    /// being sent are written before it. The other messages were dropped: the players
+    /// and the eggs are only listed in the last part.
+    ///
+    /// Returns `false` if the monitor has been removed.
+    pub fn finish_gfx_monitor_loading(&mut self, id: usize, last: &[u8]) -> bool {
+        let Some(monitor) = self.gfx_monitors.iter_mut().find(|m| m.id == id) else {
+            return false;
+        };
+        monitor.pending.extend_from_slice(last);
+        monitor.loading = false;
+        true
+    }
+
+    /// Queues a reply to a command of a graphics monitor after the messages pushed to it
+    /// so far.
+    ///
+    /// Replies are not subject to the cap of the queue: the task of the monitor waits for
+    /// them to be written before reading the next command, see
+    /// [`State::gfx_monitor_has_pending`].
+    ///
+    /// Returns `false` if the monitor has been removed.
+    pub fn queue_gfx_reply(&mut self, id: usize, reply: &[u8]) -> bool {
+        let Some(monitor) = self.gfx_monitors.iter_mut().find(|m| m.id == id) else {
+            return false;
+        };
+        monitor.pending.extend_from_slice(reply);
+        true
+    }
+
+    /// Returns whether messages are waiting to be written to a graphics monitor, or
+    /// `None` if it has been removed.
+    pub fn gfx_monitor_has_pending(&self, id: usize) -> Option<bool> {
+        let monitor = self.gfx_monitors.iter().find(|m| m.id == id)?;
+        Some(!monitor.pending.is_empty())
+    }
+
+    /// Sets whether a graphics monitor receives the `pqc` messages.
+    pub fn set_gfx_monitor_follows_queues(&mut self, id: usize, follows: bool) {
+        if let Some(monitor) = self.gfx_monitors.iter_mut().find(|m| m.id == id) {
+            monitor.follows_queues = follows;
+        }
+    }
+
+    /// Pushes a message to every graphics monitor, `json` being the same message encoded
+    /// as JSON lines for the monitors that asked for it.
+    ///
+    /// The message is queued after the ones the monitor has not read yet, to be written
+    /// with [`State::take_gfx_writes`] once the message is built. Monitors whose queue
+    /// grows past the cap lose their oldest messages or are dropped, depending on the
+    /// overflow policy.
+    pub fn push_to_gfx_monitors(&mut self, msg: &str, json: &str) {
+        self.push_to_some_gfx_monitors(msg, json, |_| true);
+    }
+
+    /// Pushes `pqc` messages to the graphics monitors that follow command queues, see
+    /// [`State::push_to_gfx_monitors`].
+    pub fn push_to_gfx_queue_followers(&mut self, msg: &str, json: &str) {
+        self.push_to_some_gfx_monitors(msg, json, GfxMonitor::follows_queues);
+    }
+
+    /// Pushes a message to the graphics monitors for which `filter` returns `true`.
+    fn push_to_some_gfx_monitors(
+        &mut self,
+        msg: &str,
+        json: &str,
+        filter: impl Fn(&GfxMonitor) -> bool,
+    ) {
+        let mut index = 0;
+        while index < self.gfx_monitors.len() {
+            let monitor = &mut self.gfx_monitors[index];
+            if !filter(monitor) {
+                index += 1;
+                continue;
+            }
+            let id = monitor.id;
+            let kept;
+            let bytes = if monitor.loading {
+                kept = kept_while_loading(msg);
+                &kept
+            } else if monitor.json {
+                json.as_bytes()
+            } else {
+                msg.as_bytes()
+            };
+
+            let Some(dropped) = monitor.queue(bytes, self.gfx_buffer_cap, self.gfx_overflow) else {
+                ft_log::warning!(
+                    "dropping graphics monitor #{id}, which does not read its messages"
+                );
+                self.gfx_monitors.swap_remove(index);
+                continue;
+            };
+            if dropped != 0 {
+                ft_log::trace!("dropped {dropped} messages for graphics monitor #{id}");
+            }
+            index += 1;
+        }
+    }
+
+    /// Moves the messages queued for the graphics monitors into `writes`, so that they
+    /// can be written with [`GfxWrite::write`] once the lock on the state is released.
+    ///
+    /// [`State::finish_gfx_writes`] must be called right after, before anything else is
+    /// pushed to the monitors.
+    pub fn take_gfx_writes(&mut self, writes: &mut Vec<GfxWrite>) {
+        writes.extend(
+            self.gfx_monitors
+                .iter_mut()
+                .filter_map(GfxMonitor::take_pending),
+        );
+    }
+
+    /// Puts the messages of `writes` that could not be written back in the queues of
+    /// their monitors, emptying `writes`, and records whether the writes succeeded.
+    ///
+    /// A monitor that stops reading never blocks the server: what it does not accept
+    /// stays queued.
+    pub fn finish_gfx_writes(&mut self, writes: &mut Vec<GfxWrite>) {
+        for write in writes.drain(..) {
+            let id = write.id();
+            // The monitor may have been removed while the lock was released.
+            let Some(monitor) = self.gfx_monitors.iter_mut().find(|m| m.id == id) else {
+                continue;
+            };
+            let result = monitor.restore_pending(write);
+            if let Err(err) = result {
+                ft_log::trace!("failed to write to graphics monitor #{id}: {err}");
+            }
+            self.report_gfx_write(id, result.is_ok());
+        }
+    }
+
+    /// Records whether a write to a graphics monitor succeeded.
+    ///
+    /// Monitors that fail too many writes in a row are removed from the server and won't
+    /// receive any more messages.
+    pub fn report_gfx_write(&mut self, id: usize, success: bool) {
+        let Some(monitor) = self.gfx_monitors.iter_mut().find(|m| m.id == id) else {
+            return;
+        };
+
+        if success {
+            monitor.failed_writes = 0;
+            return;
+        }
+
+        monitor.failed_writes += 1;
+        if monitor.failed_writes >= MAX_FAILED_WRITES {
+            ft_log::warning!(
+                "dropping graphics monitor #{id} after {} failed writes",
+                monitor.failed_writes,
+            );
+            self.remove_gfx_monitor(id);
+        }
+    }
+
+    /// Notifies the state that a whole tick has passed.
+    ///
+    /// # Arguments
+    ///
+    /// - `responses` - a list of responses that must be sent to their associated file
+    ///   descriptiors.
+    #[allow(clippy::unwrap_used)]
+    pub fn tick(&mut self, responses: &mut Vec<(ft::Fd, Response)>) {
+        // Admins may kill players while the game is paused.
+        responses.extend(self.deaths.drain(..).map(|conn| (conn, Response::Dead)));
+
+        if self.paused {
+            // Admins may still change the world while the game is paused.
+            self.push_cell_updates();
+            return;
+        }
+
+        self.tick += 1;
+
+        if self.check_cells {
+            self.check_occupancy(format_args!(
+                "players joined, left or were moved by an admin"
+            ));
+        }
+
+        self.hatch_eggs();
+        if self.respawn_ticks != 0 && self.tick.is_multiple_of(u64::from(self.respawn_ticks)) {
+            self.respawn_resources();
+        }
+        self.expire_detached_players();
+        self.feed_players(responses);
+
+        for index in 0..self.players.len() {
+            let player = &mut self.players[index];
+            let Some(command) = player.commands.first_mut() else {
+                continue;
+            };
+
+            if !command.started {
+                command.started = true;
+                if let Command::Evolve = command.command {
+                    if !self.start_elevation(index, responses) {
+                        // The requirements are not met: the command fails right away.
+                        self.players[index].commands[0].remaining_ticks = 0;
+                    }
+                }
+            }
+
+            let command = &mut self.players[index].commands[0];
+            if command.remaining_ticks > 0 {
+                command.remaining_ticks -= 1;
+                continue;
+            }
+
+            let player = &mut self.players[index];
+            // This unwrap can ever fail because the case where there is no
+            // first element is handled above.
+            // Also, we can't optimize this with a swap_remove because the
+            // order in which commands are inserted matters. Maybe we can use
+            // a VecDeque instead, but that would be vastly overkill for those
+            // 10 poor elements.
+            let cmd = player.commands.remove(0).unwrap();
+
+            // Execute the command.
+            ft_log::trace!(
+                "executing command for #{}: {:?}",
+                player.player_id,
+                cmd.command,
+            );
+
+            let response = self.execute_command(index, &cmd.command, responses);
+            if self.check_cells {
+                let player_id = self.players[index].player_id;
+                self.check_occupancy(format_args!(
+                    "player #{player_id} executed {:?}",
+                    cmd.command
+                ));
+            }
+            let latency = self.tick - cmd.scheduled_at;
+            let failed = matches!(response, Response::Ko);
+            self.command_metrics.record(&cmd.command, latency, failed);
+            if let Some(journal) = &mut self.journal {
+                let player = &self.players[index];
+                journal.record(self.tick, player, &cmd.command, latency, failed);
+            }
+            responses.push((self.players[index].conn, response));
+        }
+
+        self.push_inventory_updates();
+        self.push_queue_updates();
+        self.push_cell_updates();
+        self.world.clear_look_cache();
+
+        if self.audit_ticks != 0 && self.tick.is_multiple_of(u64::from(self.audit_ticks)) {
+            self.audit_resources();
+        }
+
+        self.check_end_of_game();
+    }
+
+    /// Ends the game if a team fulfilled the win condition: the graphics monitors receive
+    /// `seg <team> <rule>` and the game is paused.
+    fn check_end_of_game(&mut self) {
+        if self.winner.is_some() {
+            return;
+        }
+        let Some(team) = self.find_winner() else {
+            return;
+        };
+
+        self.winner = Some(team);
+        self.paused = true;
+
+        let name = &self.teams[team].name;
+        let rule = self.win_condition;
+        ft_log::info!("team `{name}` won the game at tick {} ({rule})", self.tick);
+        if !self.gfx_monitors.is_empty() {
+            let _ = writeln!(self.gfx_outbox, "seg {name} {rule}");
+        }
+        self.event_log
+            .log(self.tick, Event::GameEnded { team: name, rule });
+
+        if self.journal.is_some() {
+            // The journal is written by the main loop, which knows where to.
+            crate::JOURNAL_REQUESTED.store(true, core::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the team that fulfilled the win condition, if any.
+    fn find_winner(&self) -> Option<TeamId> {
+        let mut teams = 0..self.teams.len();
+        match self.win_condition {
+            WinCondition::MaxLevel => teams.find(|&team| {
+                self.players
+                    .iter()
+                    .filter(|p| p.team_id == team && p.level >= MAX_LEVEL)
+                    .count()
+                    >= WINNING_PLAYERS
+            }),
+            WinCondition::TimeLimit(ticks) => {
+                if self.tick < ticks {
+                    return None;
+                }
+                // Ties go to the team that comes first.
+                teams.rev().max_by_key(|&team| self.team_stats(team).levels)
+            }
+            WinCondition::LastTeamAlive => {
+                if self.teams.len() < 2 || !self.teams.iter().all(|t| t.joined) {
+                    return None;
+                }
+                // Disconnected players that may still come back keep their team alive.
+                let mut alive = teams.filter(|&team| {
+                    self.players.iter().any(|p| p.team_id == team)
+                        || self.detached.iter().any(|d| d.player.team_id == team)
+                });
+                match (alive.next(), alive.next()) {
+                    (Some(team), None) => Some(team),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Makes the players eat a unit of food when the previous one is digested.
+    ///
+    /// Players that have nothing left to eat starve to death: they are removed from the
+    /// game and receive `mort`. Disconnected players starve the same way, without being
+    /// told.
+    fn feed_players(&mut self, responses: &mut Vec<(ft::Fd, Response)>) {
+        let mut index = 0;
+        while index < self.players.len() {
+            match self.players[index].consume_tick() {
+                Digestion::Digesting => (),
+                Digestion::Ate => {
+                    let mut eaten = ResourceSet::default();
+                    eaten.add(ObjectClass::Food);
+                    self.ledger.consume(&eaten);
+                }
+                Digestion::Starved => {
+                    let player = self.remove_player(index);
+                    ft_log::info!("player #{} starved to death", player.player_id);
+                    responses.push((player.conn, Response::Dead));
+                    continue;
+                }
+            }
+            index += 1;
+        }
+
+        let mut index = 0;
+        while index < self.detached.len() {
+            match self.detached[index].player.consume_tick() {
+                Digestion::Digesting => (),
+                Digestion::Ate => {
+                    let mut eaten = ResourceSet::default();
+                    eaten.add(ObjectClass::Food);
+                    self.ledger.consume(&eaten);
+                }
+                Digestion::Starved => {
+                    let detached = self.detached.swap_remove(index);
+                    ft_log::info!(
+                        "player #{} starved to death before reconnecting",
+                        detached.player.player_id
+                    );
+                    self.forget_player(&detached.player);
+                    continue;
+                }
+            }
+            index += 1;
+        }
+    }
+
+    /// Spawns the objects missing from the world for it to match the densities.
+    fn respawn_resources(&mut self) {
+        let spawned = self.world.replenish(&self.densities, &mut self.rng);
+        if !spawned.is_empty() {
+            ft_log::trace!("respawned {spawned}");
+            self.ledger.spawn(&spawned);
+        }
+    }
+
+    /// Removes the disconnected players whose grace period is over from the game.
+    fn expire_detached_players(&mut self) {
+        let mut index = 0;
+        while index < self.detached.len() {
+            if self.detached[index].expires_at > self.tick {
+                index += 1;
+                continue;
+            }
+            let detached = self.detached.swap_remove(index);
+            ft_log::info!(
+                "player #{} did not reconnect in time",
+                detached.player.player_id
+            );
+            self.forget_player(&detached.player);
+        }
+    }
+
+    /// Checks that the objects lying on the world and held by the players match the
+    /// ones that entered and left the game, logging an error otherwise.
+    ///
+    /// Commands moving objects are executed atomically within a tick, so no object is
+    /// ever in flight when this runs.
+    fn audit_resources(&self) {
+        let mut present = self.world.object_totals();
+        let detached = self.detached.iter().map(|d| &d.player);
+        for player in self.players.iter().chain(detached) {
+            for (total, &count) in present.iter_mut().zip(player.inventory.counts()) {
+                *total += u64::from(count);
+            }
+        }
+
+        if let Some(diff) = self.ledger.check(&present) {
+            ft_log::error!("resource conservation broken at tick {}: {diff}", self.tick);
+        }
+    }
+
+    /// Counts the players standing on every cell and compares it with the counts kept by
+    /// the world, logging an error for every cell that differs.
+    ///
+    /// `cause` describes what happened since the last check. The counts are fixed, so
+    /// that every mismatch is only reported once.
+    fn check_occupancy(&mut self, cause: core::fmt::Arguments) {
+        let tick = self.tick;
+        let positions = self.players.iter().map(|player| player.position());
+        self.world
+            .recount_players(positions, |x, y, counted, actual| {
+                ft_log::error!(
+                    "cell ({x}, {y}) out of sync at tick {tick} after {cause}: \
+                 {counted} players counted, {actual} found"
+                );
+            });
+    }
+
+    /// Executes a command on behalf of the player at index `index` in the list of
+    /// players, returning the response that must be sent back to the player.
+    ///
+    /// Messages that other players must receive are pushed to `notifications`.
+    fn execute_command(
+        &mut self,
+        index: usize,
+        command: &Command,
+        notifications: &mut Vec<(ft::Fd, Response)>,
+    ) -> Response {
+        let player = &mut self.players[index];
+        let notify_gfx = !self.gfx_monitors.is_empty();
+
+        match command {
+            Command::MoveForward => {
+                let from = player.position();
+                (player.x, player.y) = self.world.step(player.x, player.y, player.orientation);
+                self.world.move_player(from, player.position());
+                if notify_gfx {
+                    messages::player_position(&mut self.gfx_outbox, player);
+                }
+                Response::Ok
+            }
+            Command::TurnLeft => {
+                player.orientation = player.orientation.left();
+                if notify_gfx {
+                    messages::player_position(&mut self.gfx_outbox, player);
+                }
+                Response::Ok
+            }
+            Command::TurnRight => {
+                player.orientation = player.orientation.right();
+                if notify_gfx {
+                    messages::player_position(&mut self.gfx_outbox, player);
+                }
+                Response::Ok
+            }
+            Command::PickUpObject(class) => {
+                if !self.world.remove_object(player.x, player.y, *class) {
+                    return Response::Ko;
+                }
+                player.inventory.add(*class);
+                if notify_gfx {
+                    messages::player_pickup(&mut self.gfx_outbox, player, *class);
+                }
+                Self::push_transfer_inventory(&mut self.gfx_outbox, player, self.tick, notify_gfx);
+                Response::Ok
+            }
+            Command::DropObject(class) => {
+                if !player.inventory.remove(*class) {
+                    return Response::Ko;
+                }
+                self.world.add_object(player.x, player.y, *class);
+                if notify_gfx {
+                    messages::player_drop(&mut self.gfx_outbox, player, *class);
+                }
+                Self::push_transfer_inventory(&mut self.gfx_outbox, player, self.tick, notify_gfx);
+                Response::Ok
+            }
+            Command::LookAround => {
+                let mut text = String::new();
+                self.world.look(
+                    player.position(),
+                    player.orientation,
+                    player.level,
+                    &mut text,
+                );
+                Response::Seen(text)
+            }
+            Command::Inventory => Response::Inventory(player.inventory),
+            Command::KnockPlayer => self.knock_players(index, notifications),
+            Command::AvailableTeamSlots => {
+                Response::ConnectNbr(self.teams[player.team_id].available_slots)
+            }
+            Command::Invalid => Response::Ko,
+            Command::LayAnEgg => {
+                let egg = Egg {
+                    id: self.next_egg_id,
+                    team_id: player.team_id,
+                    parent: player.player_id,
+                    x: player.x,
+                    y: player.y,
+                    remaining_ticks: self.egg_timing.hatch_ticks,
+                };
+                self.next_egg_id += 1;
+
+                if notify_gfx {
+                    messages::egg_new(&mut self.gfx_outbox, &egg);
+                }
+
+                self.event_log.log(
+                    self.tick,
+                    Event::EggLaid {
+                        egg: egg.id,
+                        player: player.player_id,
+                        team: &self.teams[egg.team_id].name,
+                    },
+                );
+
+                self.eggs.push(egg);
+                Response::Ok
+            }
+            Command::Evolve => self.finish_elevation(index, notifications),
+            Command::Broadcast(_) => Response::Ok,
+        }
+    }
+
+    /// Starts an elevation on the cell of the player at index `index`, with every player
+    /// of their level standing there.
+    ///
+    /// The participants receive `elevation en cours` and the graphics monitors `pic`.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the requirements of the elevation are not met, in which case it does
+    /// not start.
+    fn start_elevation(
+        &mut self,
+        index: usize,
+        notifications: &mut Vec<(ft::Fd, Response)>,
+    ) -> bool {
+        let initiator = &self.players[index];
+        let (position, level) = (initiator.position(), initiator.level);
+        let Some(requirement) = elevation_requirement(level) else {
+            return false;
+        };
+
+        // A player cannot take part in two elevations at once.
+        let busy = |id| self.elevations.iter().any(|e| e.participants.contains(&id));
+        if busy(initiator.player_id) {
+            return false;
+        }
+        let participants: Vec<PlayerId> = self
+            .players
+            .iter()
+            .filter(|p| p.position() == position && p.level == level && !busy(p.player_id))
+            .map(|p| p.player_id)
+            .collect();
+
+        if participants.len() < usize::from(requirement.players)
+            || !self.objects_at(position).contains(&requirement.stones)
+        {
+            return false;
+        }
+
+        for player in &self.players {
+            if participants.contains(&player.player_id) {
+                notifications.push((player.conn, Response::Elevating));
+            }
+        }
+        if !self.gfx_monitors.is_empty() {
+            messages::incantation_start(&mut self.gfx_outbox, position, level, &participants);
+        }
+
+        self.elevations.push(Elevation {
+            initiator: self.players[index].player_id,
+            position,
+            level,
+            participants,
+        });
+        true
+    }
+
+    /// Ends the elevation started by the player at index `index`, returning the response
+    /// that must be sent back to them.
+    ///
+    /// The requirements are checked again, with the participants still standing on the
+    /// cell at their level. If they are met, the stones are consumed and every participant
+    /// levels up: the other ones receive `niveau actuel : <level>`, and the graphics
+    /// monitors `pie` followed by `plv`.
+    fn finish_elevation(
+        &mut self,
+        index: usize,
+        notifications: &mut Vec<(ft::Fd, Response)>,
+    ) -> Response {
+        let initiator = self.players[index].player_id;
+        let Some(i) = self
+            .elevations
+            .iter()
+            .position(|e| e.initiator == initiator)
+        else {
+            // The elevation did not start.
+            return Response::Ko;
+        };
+        let Elevation {
+            position,
+            level,
+            mut participants,
+            ..
+        } = self.elevations.swap_remove(i);
+        let Some(requirement) = elevation_requirement(level) else {
+            return Response::Ko;
+        };
+
+        participants.retain(|&id| {
+            self.player(id)
+                .is_some_and(|p| p.position() == position && p.level == level)
+        });
+        let success = participants.contains(&initiator)
+            && participants.len() >= usize::from(requirement.players)
+            && self.objects_at(position).contains(&requirement.stones);
+
+        let notify_gfx = !self.gfx_monitors.is_empty();
+        if notify_gfx {
+            messages::incantation_end(&mut self.gfx_outbox, position, success);
+        }
+        if !success {
+            return Response::Ko;
+        }
+
+        let (x, y) = position;
+        for (class, count) in requirement.stones.iter() {
+            for _ in 0..count {
+                self.world.remove_object(x, y, class);
+            }
+        }
+        self.ledger.consume(&requirement.stones);
+
+        for player in &mut self.players {
+            if !participants.contains(&player.player_id) {
+                continue;
+            }
+            player.level += 1;
+            if notify_gfx {
+                messages::player_level(&mut self.gfx_outbox, player);
+            }
+            if player.player_id != initiator {
+                notifications.push((player.conn, Response::Elevated(player.level)));
+            }
+        }
+        ft_log::info!(
+            "{} players reached level {} at ({x}, {y})",
+            participants.len(),
+            level + 1,
+        );
+
+        Response::Elevated(level + 1)
+    }
+
+    /// Cancels the elevation started by a player who left the game, telling the graphics
+    /// monitors that it failed.
+    fn cancel_elevation(&mut self, player: PlayerId) {
+        let Some(i) = self.elevations.iter().position(|e| e.initiator == player) else {
+            return;
+        };
+        let elevation = self.elevations.swap_remove(i);
+        if !self.gfx_monitors.is_empty() {
+            messages::incantation_end(&mut self.gfx_outbox, elevation.position, false);
+        }
+    }
+
+    /// Returns the objects lying on the cell at the provided position.
+    #[inline]
+    fn objects_at(&self, (x, y): (u32, u32)) -> &ResourceSet {
+        self.world.cell(x, y).objects()
+    }
+
+    /// Pushes the inventory of a player that just picked up or dropped an object right
+    /// after the `pgt` or `pdr` message, without waiting for [`PIN_PUSH_PERIOD`].
+    ///
+    /// The `bct` message of their cell follows at the end of the tick, with the other
+    /// cells that changed.
+    fn push_transfer_inventory(
+        outbox: &mut String,
+        player: &mut PlayerState,
+        tick: u64,
+        notify_gfx: bool,
+    ) {
+        player.inventory_changed = false;
+        player.last_inventory_push = tick;
+        if notify_gfx {
+            messages::player_inventory(outbox, player);
+            messages::player_time_to_live(outbox, player);
+        }
+    }
+
+    /// Pushes the inventory of the players whose inventory changed to the graphics
+    /// monitors, at most once every [`PIN_PUSH_PERIOD`] ticks per player.
+    fn push_inventory_updates(&mut self) {
+        for player in &mut self.players {
+            if !player.inventory_changed || self.tick - player.last_inventory_push < PIN_PUSH_PERIOD
+            {
+                continue;
+            }
+
+            player.inventory_changed = false;
+            player.last_inventory_push = self.tick;
+
+            if !self.gfx_monitors.is_empty() {
+                messages::player_inventory(&mut self.gfx_outbox, player);
+                messages::player_time_to_live(&mut self.gfx_outbox, player);
+            }
+        }
+    }
+
+    /// Pushes the number of queued commands of the players whose queue changed during the
+    /// tick to the graphics monitors that follow command queues.
+    fn push_queue_updates(&mut self) {
+        if !self.gfx_monitors.iter().any(GfxMonitor::follows_queues) {
+            return;
+        }
+
+        for player in &mut self.players {
+            if player.commands.len() == player.pushed_queue_len {
+                continue;
+            }
+
+            player.pushed_queue_len = player.commands.len();
+            messages::player_queue(&mut self.gfx_queue_outbox, player);
+        }
+    }
+
+    /// Pushes the content of the cells that changed during the tick to the graphics
+    /// monitors, as a single batch of `bct` messages.
+    fn push_cell_updates(&mut self) {
+        let notify_gfx = !self.gfx_monitors.is_empty();
+        let outbox = &mut self.gfx_outbox;
+
+        self.world.drain_dirty_cells(|x, y, cell| {
+            if notify_gfx {
+                messages::tile_content(outbox, x, y, cell);
+            }
+        });
+    }
+
+    /// Pushes every other player standing on the tile of the player at index `index` one
+    /// tile away, in the direction that player is facing.
+    ///
+    /// Pushed players are notified with `deplacement`.
+    fn knock_players(
+        &mut self,
+        index: usize,
+        notifications: &mut Vec<(ft::Fd, Response)>,
+    ) -> Response {
+        let (x, y) = self.players[index].position();
+        let push = self.players[index].orientation;
+        let notify_gfx = !self.gfx_monitors.is_empty();
+
+        if self.world.cell(x, y).players() < 2 {
+            return Response::Ko;
+        }
+        if notify_gfx {
+            messages::player_expulsion(&mut self.gfx_outbox, &self.players[index]);
+        }
+
+        for (i, player) in self.players.iter_mut().enumerate() {
+            if i == index || player.position() != (x, y) {
+                continue;
+            }
+
+            (player.x, player.y) = self.world.step(x, y, push);
+            self.world.move_player((x, y), player.position());
+            notifications.push((
+                player.conn,
+                Response::Displaced(player.orientation.push_origin(push)),
+            ));
+            if notify_gfx {
+                messages::player_position(&mut self.gfx_outbox, player);
+            }
+        }
+
+        Response::Ok
+    }
+
+    /// Advances the eggs by one tick, creating a new slot in their team for every egg
+    /// that hatches.
+    fn hatch_eggs(&mut self) {
+        let mut i = 0;
+        while let Some(egg) = self.eggs.get_mut(i) {
+            if egg.remaining_ticks > 0 {
+                egg.remaining_ticks -= 1;
+                i += 1;
+                continue;
+            }
+
+            let mut egg = self.eggs.swap_remove(i);
+            let team = &mut self.teams[egg.team_id];
+            team.available_slots += 1;
+
+            if !self.gfx_monitors.is_empty() {
+                messages::egg_hatched(&mut self.gfx_outbox, egg.id);
+            }
+
+            self.event_log.log(
+                self.tick,
+                Event::EggHatched {
+                    egg: egg.id,
+                    team: &team.name,
+                },
+            );
+
+            if self.egg_timing.slot_ticks != 0 {
+                egg.remaining_ticks = self.egg_timing.slot_ticks;
+                self.hatched_eggs.push(egg);
+            }
+        }
+
+        self.expire_hatched_eggs();
+    }
+
+    /// Advances the hatched eggs by one tick, removing the slot of the eggs that waited
+    /// too long for a player.
+    fn expire_hatched_eggs(&mut self) {
+        // Eggs are kept in the order in which they hatched, so `retain` is used instead
+        // of `swap_remove`.
+        self.hatched_eggs.retain_mut(|egg| {
+            if egg.remaining_ticks > 0 {
+                egg.remaining_ticks -= 1;
+                return true;
+            }
+
+            // Every hatched egg holds one of the slots of its team.
+            let team = &mut self.teams[egg.team_id];
+            debug_assert!(team.available_slots > 0, "a hatched egg lost its slot");
+            team.available_slots -= 1;
+
+            if !self.gfx_monitors.is_empty() {
+                messages::egg_death(&mut self.gfx_outbox, egg.id);
+            }
+
+            self.event_log.log(
+                self.tick,
+                Event::EggExpired {
+                    egg: egg.id,
+                    team: &team.name,
+                },
+            );
+
+            false
+        });
+    }
+}
+
+/// The global state of the server.
+static STATE: ft::Mutex<Option<State>, ft::sync::mutex::NoBlockMutex> = ft::Mutex::new(None);
+
+/// Initializes the global [`State`].
+///
 /// # Panics
 ///
 /// This function panics if the global state is already initialized.
@@ -342,3 +2331,386 @@ extern "C" fn setup_clear_state() {
     ft::at_exit(clear_state);
 }
 ft::ctor!(setup_clear_state);
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// Returns a level 1 player that just spawned at the origin, holding `food` units of
+    /// food and nothing else.
+    fn player_with_food(food: u32) -> PlayerState {
+        PlayerState {
+            player_id: 0,
+            team_id: 0,
+            conn: ft::Fd::from_raw(-1),
+            commands: ArrayVec::new(),
+            level: 1,
+            x: 0,
+            y: 0,
+            orientation: Orientation::North,
+            inventory: ResourceSet::from_counts([food, 0, 0, 0, 0, 0, 0]),
+            inventory_changed: false,
+            last_inventory_push: 0,
+            pushed_queue_len: 0,
+            food_ticks: 0,
+            reconnect_token: None,
+            waker: None,
+        }
+    }
+
+    #[test]
+    fn first_unit_of_food_is_eaten_during_first_tick() {
+        let mut player = player_with_food(INITIAL_FOOD);
+        assert_eq!(player.food_units(), INITIAL_FOOD);
+        assert_eq!(
+            player.time_to_live(),
+            u64::from(INITIAL_FOOD * TICKS_PER_FOOD)
+        );
+
+        assert_eq!(player.consume_tick(), Digestion::Ate);
+        assert_eq!(player.food_units(), INITIAL_FOOD - 1);
+        assert!(player.inventory_changed);
+    }
+
+    #[test]
+    fn food_units_only_count_whole_units() {
+        let mut player = player_with_food(2);
+        assert_eq!(player.consume_tick(), Digestion::Ate);
+        for _ in 1..TICKS_PER_FOOD {
+            assert_eq!(player.consume_tick(), Digestion::Digesting);
+            assert_eq!(player.food_units(), 1);
+        }
+        assert_eq!(player.consume_tick(), Digestion::Ate);
+        assert_eq!(player.food_units(), 0);
+    }
+
+    #[test]
+    fn time_to_live_decreases_by_one_every_tick() {
+        let mut player = player_with_food(3);
+        let mut time_to_live = player.time_to_live();
+        while time_to_live > 0 {
+            assert_ne!(player.consume_tick(), Digestion::Starved);
+            time_to_live -= 1;
+            assert_eq!(player.time_to_live(), time_to_live);
+        }
+        assert_eq!(player.consume_tick(), Digestion::Starved);
+    }
+
+    #[test]
+    fn player_without_food_starves_during_next_tick() {
+        let mut player = player_with_food(0);
+        assert_eq!(player.time_to_live(), 0);
+        assert_eq!(player.consume_tick(), Digestion::Starved);
+        assert_eq!(player.food_units(), 0);
+    }
+
+    /// Returns a client backed by a socket that is never connected.
+    fn client() -> Client {
+        let family = ft::net::SocketAddr::V4([127, 0, 0, 1], 0).family();
+        Client::new(ft::File::socket(family, ft::net::SocketType::Stream).unwrap())
+    }
+
+    /// Returns a new state in which each team has `slots` slots, and players may
+    /// reconnect for `grace_secs` seconds (10 ticks per second).
+    fn new_state(slots: u32, grace_secs: u32) -> State {
+        State::from_args(&Args {
+            initial_slot_count: slots,
+            reconnect_grace_secs: grace_secs,
+            ..Args::default()
+        })
+    }
+
+    /// Simulates `count` ticks, returning the messages pushed to the graphics monitors.
+    fn run_ticks(state: &mut State, count: usize) -> String {
+        let mut responses = Vec::new();
+        let mut outbox = String::new();
+        let mut buf = String::new();
+        for _ in 0..count {
+            state.tick(&mut responses);
+            state.take_gfx_outbox(&mut buf);
+            outbox.push_str(&buf);
+            buf.clear();
+        }
+        outbox
+    }
+
+    #[test]
+    fn joining_a_team_consumes_a_slot() {
+        let mut state = new_state(1, 0);
+        let team = state.team_id_by_name("Blue").unwrap();
+
+        assert!(state.try_join_team(&client(), team).is_ok());
+        assert_eq!(state.available_slots_for(team), 0);
+        assert!(matches!(
+            state.try_join_team(&client(), team),
+            Err(PlayerError::TeamFull { .. })
+        ));
+    }
+
+    #[test]
+    fn death_does_not_free_a_slot() {
+        let mut state = new_state(1, 0);
+        let player = state.try_join_team(&client(), 0).unwrap();
+
+        assert!(state.kill(player));
+        assert!(state.player(player).is_none());
+        assert_eq!(state.available_slots_for(0), 0);
+        assert!(state.try_join_team(&client(), 0).is_err());
+    }
+
+    #[test]
+    fn hatched_egg_adds_a_slot_until_it_expires() {
+        let mut state = new_state(0, 0);
+        state.add_gfx_monitor(&client());
+        state.set_egg_timing(EggTiming {
+            fork_ticks: 1,
+            hatch_ticks: 0,
+            slot_ticks: 2,
+        });
+        state.eggs.push(Egg {
+            id: 7,
+            team_id: 0,
+            parent: 0,
+            x: 0,
+            y: 0,
+            remaining_ticks: 0,
+        });
+
+        let outbox = run_ticks(&mut state, 1);
+        assert!(outbox.contains("eht #7\n"));
+        assert_eq!(state.available_slots_for(0), 1);
+
+        let outbox = run_ticks(&mut state, 1);
+        assert!(!outbox.contains("edi #7\n"));
+        assert_eq!(state.available_slots_for(0), 1);
+
+        let outbox = run_ticks(&mut state, 1);
+        assert!(outbox.contains("edi #7\n"));
+        assert_eq!(state.available_slots_for(0), 0);
+        assert!(state.hatched_eggs.is_empty());
+    }
+
+    #[test]
+    fn joining_takes_the_slot_of_a_hatched_egg() {
+        let mut state = new_state(0, 0);
+        state.set_egg_timing(EggTiming {
+            fork_ticks: 1,
+            hatch_ticks: 0,
+            slot_ticks: 100,
+        });
+        state.eggs.push(Egg {
+            id: 0,
+            team_id: 0,
+            parent: 0,
+            x: 0,
+            y: 0,
+            remaining_ticks: 0,
+        });
+        run_ticks(&mut state, 1);
+
+        assert!(state.try_join_team(&client(), 0).is_ok());
+        assert_eq!(state.available_slots_for(0), 0);
+        assert!(state.hatched_eggs.is_empty());
+    }
+
+    #[test]
+    fn player_reattaches_within_grace_period() {
+        let mut state = new_state(1, 1);
+        let player = state.try_join_team(&client(), 0).unwrap();
+        let token = state.player(player).unwrap().reconnect_token.unwrap();
+
+        state.detach(player);
+        assert!(state.player(player).is_none());
+        run_ticks(&mut state, 9);
+
+        let reattached = state.reattach(&client(), token);
+        assert!(matches!(reattached, Ok((id, 0)) if id == player));
+        assert!(state.player(player).is_some());
+        assert_eq!(state.available_slots_for(0), 0);
+    }
+
+    #[test]
+    fn player_cannot_reattach_after_grace_period() {
+        let mut state = new_state(1, 1);
+        let player = state.try_join_team(&client(), 0).unwrap();
+        let token = state.player(player).unwrap().reconnect_token.unwrap();
+
+        state.detach(player);
+        run_ticks(&mut state, 10);
+
+        assert!(matches!(
+            state.reattach(&client(), token),
+            Err(PlayerError::UnknownReconnectToken)
+        ));
+        // The slot of the player is not given back.
+        assert_eq!(state.available_slots_for(0), 0);
+        assert!(state.try_join_team(&client(), 0).is_err());
+    }
+
+    #[test]
+    fn monitors_see_detached_players_leave_and_come_back() {
+        let mut state = new_state(1, 1);
+        state.add_gfx_monitor(&client());
+        let player = state.try_join_team(&client(), 0).unwrap();
+        let token = state.player(player).unwrap().reconnect_token.unwrap();
+        let mut outbox = String::new();
+        state.take_gfx_outbox(&mut outbox);
+
+        outbox.clear();
+        state.detach(player);
+        state.take_gfx_outbox(&mut outbox);
+        assert_eq!(outbox, format!("pdi #{player}\n"));
+
+        outbox.clear();
+        state.reattach(&client(), token).unwrap();
+        state.take_gfx_outbox(&mut outbox);
+        assert!(outbox.starts_with(&format!("pnw #{player} ")));
+        assert!(outbox.contains(&format!("pin #{player} ")));
+    }
+
+    #[test]
+    fn respawns_restore_the_densities() {
+        let mut state = new_state(1, 0);
+        let area = u64::from(state.world.width() * state.world.height());
+        state.set_density(ObjectClass::Thystame, 2.0);
+
+        run_ticks(&mut state, 1);
+        assert!(state.world.object_totals()[ObjectClass::Thystame.index()] < area);
+
+        state.set_respawn_ticks(2);
+        run_ticks(&mut state, 1);
+        assert_eq!(
+            state.world.object_totals()[ObjectClass::Thystame.index()],
+            2 * area
+        );
+        assert!(state.ledger.check(&state.world.object_totals()).is_none());
+    }
+
+    /// Joins `count` players of level `level` to the first team, all standing at the
+    /// origin on a cell that holds exactly `stones`.
+    fn players_on_origin(state: &mut State, count: usize, level: u32, stones: &ResourceSet) {
+        for _ in 0..count {
+            let player = state.try_join_team(&client(), 0).unwrap();
+            let index = state.player_index_by_id(player).unwrap();
+            let from = state.players[index].position();
+            state.world.move_player(from, (0, 0));
+            let player = &mut state.players[index];
+            (player.x, player.y, player.level) = (0, 0, level);
+        }
+
+        let objects = *state.objects_at((0, 0));
+        for (class, count) in objects.iter() {
+            for _ in 0..count {
+                state.world.remove_object(0, 0, class);
+            }
+        }
+        state.ledger.consume(&objects);
+        for (class, count) in stones.iter() {
+            for _ in 0..count {
+                state.world.add_object(0, 0, class);
+            }
+        }
+        state.ledger.spawn(stones);
+    }
+
+    #[test]
+    fn elevation_requires_its_stones() {
+        let mut state = new_state(1, 0);
+        players_on_origin(&mut state, 1, 1, &ResourceSet::default());
+        let mut notifications = Vec::new();
+
+        assert!(!state.start_elevation(0, &mut notifications));
+        assert!(notifications.is_empty());
+        assert!(state.elevations.is_empty());
+        assert!(matches!(
+            state.finish_elevation(0, &mut notifications),
+            Response::Ko
+        ));
+        assert_eq!(state.players[0].level, 1);
+    }
+
+    #[test]
+    fn elevation_requires_enough_players() {
+        let mut state = new_state(2, 0);
+        let requirement = elevation_requirement(2).unwrap();
+        players_on_origin(&mut state, 1, 2, &requirement.stones);
+        let mut notifications = Vec::new();
+
+        assert!(!state.start_elevation(0, &mut notifications));
+        assert!(state.objects_at((0, 0)).contains(&requirement.stones));
+        assert_eq!(state.players[0].level, 2);
+    }
+
+    #[test]
+    fn elevation_consumes_its_stones_and_levels_players_up() {
+        let mut state = new_state(2, 0);
+        state.add_gfx_monitor(&client());
+        let requirement = elevation_requirement(2).unwrap();
+        let mut stones = requirement.stones;
+        stones.add(ObjectClass::Linemate);
+        players_on_origin(&mut state, 2, 2, &stones);
+        let mut outbox = String::new();
+        state.take_gfx_outbox(&mut outbox);
+        let mut notifications = Vec::new();
+
+        assert!(state.start_elevation(0, &mut notifications));
+        assert_eq!(notifications.len(), 2);
+        assert!(notifications
+            .iter()
+            .all(|(_, response)| matches!(response, Response::Elevating)));
+
+        notifications.clear();
+        let totals = state.world.object_totals();
+        assert!(matches!(
+            state.finish_elevation(0, &mut notifications),
+            Response::Elevated(3)
+        ));
+        assert!(matches!(notifications[..], [(_, Response::Elevated(3))]));
+        assert!(state.players.iter().all(|p| p.level == 3));
+        assert_eq!(
+            *state.objects_at((0, 0)),
+            ResourceSet::from_counts([0, 1, 0, 0, 0, 0, 0])
+        );
+        let mut consumed = ResourceLedger::new(totals);
+        consumed.consume(&requirement.stones);
+        assert!(consumed.check(&state.world.object_totals()).is_none());
+
+        outbox.clear();
+        state.take_gfx_outbox(&mut outbox);
+        assert!(outbox.starts_with("pic 0 0 2 "));
+        assert!(outbox.contains("pie 0 0 1\n"));
+        assert_eq!(outbox.matches("plv ").count(), 2);
+    }
+
+    #[test]
+    fn elevation_fails_if_its_stones_are_taken() {
+        let mut state = new_state(1, 0);
+        let requirement = elevation_requirement(1).unwrap();
+        players_on_origin(&mut state, 1, 1, &requirement.stones);
+        let mut notifications = Vec::new();
+
+        assert!(state.start_elevation(0, &mut notifications));
+        state.world.remove_object(0, 0, ObjectClass::Linemate);
+        assert!(matches!(
+            state.finish_elevation(0, &mut notifications),
+            Response::Ko
+        ));
+        assert_eq!(state.players[0].level, 1);
+        assert!(state.elevations.is_empty());
+    }
+
+    #[test]
+    fn detached_player_cannot_reattach_twice() {
+        let mut state = new_state(1, 1);
+        let player = state.try_join_team(&client(), 0).unwrap();
+        let token = state.player(player).unwrap().reconnect_token.unwrap();
+
+        state.detach(player);
+        assert!(state.reattach(&client(), token).is_ok());
+        assert!(matches!(
+            state.reattach(&client(), token),
+            Err(PlayerError::UnknownReconnectToken)
+        ));
+    }
+}
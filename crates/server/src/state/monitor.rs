@@ -0,0 +1,173 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::GfxMonitor;
+
+/// What happens when a graphics monitor does not read its messages fast enough, and the
+/// messages waiting to be written to it exceed the cap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// The oldest messages waiting to be written are dropped.
+    DropOldest,
+    /// The monitor is dropped.
+    Disconnect,
+}
+
+impl OverflowPolicy {
+    /// Parses an overflow policy from the provided argument: `drop` or `disconnect`.
+    pub fn from_arg(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            b"drop" => Some(Self::DropOldest),
+            b"disconnect" => Some(Self::Disconnect),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for OverflowPolicy {
+    /// Formats the policy the way it is passed to the server.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DropOldest => write!(f, "drop"),
+            Self::Disconnect => write!(f, "disconnect"),
+        }
+    }
+}
+
+/// The tags of the messages kept for a monitor that is still receiving the initial state
+/// of the game: the changes to the cells and to the time unit, which the parts already
+/// sent may have missed.
+const KEPT_WHILE_LOADING: [&str; 2] = ["bct ", "sgt "];
+
+/// Returns the lines of `msg` that a monitor still receiving the initial state of the
+/// game must be sent, see [`KEPT_WHILE_LOADING`].
+pub(super) fn kept_while_loading(msg: &str) -> Vec<u8> {
+    let mut kept = Vec::new();
+    for line in msg.split_inclusive('\n') {
+        if KEPT_WHILE_LOADING.iter().any(|tag| line.starts_with(tag)) {
+            kept.extend_from_slice(line.as_bytes());
+        }
+    }
+    kept
+}
+
+/// Messages taken out of the queue of a graphics monitor, to be written to it without
+/// holding the lock on the state, see [`State::take_gfx_writes`](super::State::take_gfx_writes).
+pub struct GfxWrite {
+    /// The ID of the monitor.
+    id: usize,
+    /// The connection that is open with the monitor.
+    conn: ft::Fd,
+    /// The messages to write.
+    bytes: Vec<u8>,
+    /// The number of bytes of `bytes` written so far.
+    written: usize,
+    /// The outcome of the last write.
+    result: ft::Result<()>,
+}
+
+impl GfxWrite {
+    /// Returns the ID of the monitor the messages are for.
+    #[inline]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// Writes as much of the messages as the connection accepts without blocking.
+    pub fn write(&mut self) {
+        while self.written < self.bytes.len() {
+            match self.conn.write(&self.bytes[self.written..]) {
+                Ok(0) | Err(ft::Errno::AGAIN) => break,
+                Ok(n) => self.written += n,
+                Err(err) => {
+                    self.result = Err(err);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+impl GfxMonitor {
+    /// Queues a message after the ones that have not been written to the monitor yet.
+    ///
+    /// When more than `cap` bytes are waiting, whole messages are dropped from the front
+    /// of the queue with [`OverflowPolicy::DropOldest`].
+    ///
+    /// # Returns
+    ///
+    /// The number of messages dropped, or `None` if the monitor must be disconnected.
+    pub(super) fn queue(&mut self, msg: &[u8], cap: usize, policy: OverflowPolicy) -> Option<u64> {
+        self.pending.extend_from_slice(msg);
+        if self.pending.len() <= cap {
+            return Some(0);
+        }
+        if policy == OverflowPolicy::Disconnect {
+            return None;
+        }
+
+        // A message that has been partly written must be finished, or the monitor would
+        // receive a corrupted line.
+        let start = if self.partial_line {
+            match self.pending.iter().position(|&b| b == b'\n') {
+                Some(end) => end + 1,
+                None => return Some(0),
+            }
+        } else {
+            0
+        };
+
+        let mut dropped = 0;
+        while self.pending.len() > cap {
+            let Some(end) = self.pending[start..].iter().position(|&b| b == b'\n') else {
+                break;
+            };
+            self.pending.drain(start..=start + end);
+            dropped += 1;
+        }
+        self.dropped_messages += dropped;
+        Some(dropped)
+    }
+
+    /// Takes the queued messages out of the monitor so that they can be written to it,
+    /// leaving the spare buffer in their place.
+    ///
+    /// Returns `None` if there is nothing to write, or if the monitor is still receiving
+    /// the initial state of the game.
+    pub(super) fn take_pending(&mut self) -> Option<GfxWrite> {
+        if self.loading || self.pending.is_empty() {
+            return None;
+        }
+        let bytes = core::mem::replace(&mut self.pending, core::mem::take(&mut self.spare));
+        Some(GfxWrite {
+            id: self.id,
+            conn: self.conn,
+            bytes,
+            written: 0,
+            result: Ok(()),
+        })
+    }
+
+    /// Puts the messages of `write` that could not be written back in front of the queue,
+    /// keeping the buffer they were in as the next spare one.
+    ///
+    /// # Returns
+    ///
+    /// The outcome of the write.
+    pub(super) fn restore_pending(&mut self, write: GfxWrite) -> ft::Result<()> {
+        let GfxWrite {
+            mut bytes,
+            written,
+            result,
+            ..
+        } = write;
+        if written != 0 {
+            self.partial_line = bytes[written - 1] != b'\n';
+        }
+        bytes.drain(..written);
+        bytes.extend_from_slice(&self.pending);
+        self.pending.clear();
+        self.spare = core::mem::replace(&mut self.pending, bytes);
+        result
+    }
+}
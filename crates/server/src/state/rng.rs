@@ -0,0 +1,51 @@
+/// A small pseudo-random number generator (xorshift64*).
+///
+/// The generator is seeded once at startup, which makes games reproducible when the same
+/// seed is used.
+pub struct Rng {
+    /// The internal state of the generator. Never zero.
+    state: u64,
+}
+
+impl Rng {
+    /// Creates a new [`Rng`] from the provided seed.
+    pub fn new(seed: u64) -> Self {
+        // A zero state would only ever produce zeros.
+        Self {
+            state: (seed ^ 0x9E37_79B9_7F4A_7C15).max(1),
+        }
+    }
+
+    /// Restores a generator from the state returned by [`Rng::state`].
+    pub fn from_state(state: u64) -> Self {
+        Self {
+            state: state.max(1),
+        }
+    }
+
+    /// Returns the internal state of the generator, to save it.
+    #[inline]
+    pub fn state(&self) -> u64 {
+        self.state
+    }
+
+    /// Mixes some entropy into the state of the generator.
+    pub fn mix(&mut self, entropy: u64) {
+        self.state = (self.state ^ entropy).max(1);
+    }
+
+    /// Returns the next pseudo-random 64-bit number.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a pseudo-random number in the range `0..bound`.
+    ///
+    /// `bound` must not be zero.
+    pub fn below(&mut self, bound: u32) -> u32 {
+        (((self.next_u64() >> 32) * u64::from(bound)) >> 32) as u32
+    }
+}
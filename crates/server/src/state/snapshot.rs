@@ -0,0 +1,458 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use ft::collections::{ArrayVec, ReadBuffer};
+use ft::CharStar;
+
+use super::{
+    is_valid_tick_frequency, DetachedPlayer, Egg, Orientation, PlayerState, ResourceLedger,
+    ResourceSet, Rng, State, Team, World, DEFAULT_STARTING_INVENTORY,
+};
+use crate::client::Client;
+
+/// The bytes every snapshot starts with.
+const MAGIC: &[u8; 4] = b"ZPSV";
+
+/// The version of the snapshot format, bumped whenever it changes.
+const VERSION: u64 = 2;
+
+/// An error that can occur while saving or restoring a game.
+pub enum SnapshotError {
+    /// The snapshot file could not be read or written.
+    Io(ft::Errno),
+    /// The file is not a snapshot, or was written by an incompatible version of the
+    /// server.
+    UnknownFormat,
+    /// The snapshot ends unexpectedly.
+    Truncated,
+    /// The snapshot holds an inconsistent value.
+    Corrupted(&'static str),
+}
+
+impl From<ft::Errno> for SnapshotError {
+    #[inline]
+    fn from(value: ft::Errno) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Self::Io(err) => write!(f, "failed to access the snapshot file: {err}"),
+            Self::UnknownFormat => f.write_str("not a snapshot of this version of the server"),
+            Self::Truncated => f.write_str("the snapshot is truncated"),
+            Self::Corrupted(what) => write!(f, "the snapshot is corrupted: {what}"),
+        }
+    }
+}
+
+/// Reads the snapshot stored in the file at `path`.
+pub fn read_snapshot(path: &CharStar) -> Result<Vec<u8>, SnapshotError> {
+    let file = ft::File::open(path)?;
+    let mut buf = ReadBuffer::new();
+
+    loop {
+        buf.reserve(4096).map_err(ft::Errno::from)?;
+        if buf.fill_with_fd(*file)? == 0 {
+            break;
+        }
+    }
+
+    Ok(buf.pending().to_vec())
+}
+
+/// Writes a snapshot to the file at `path`, replacing its previous content.
+pub fn write_snapshot(path: &CharStar, snapshot: &[u8]) -> Result<(), SnapshotError> {
+    let file = ft::File::create(path)?;
+    file.write_all(snapshot)?;
+    Ok(())
+}
+
+impl State {
+    /// Serializes the game, so that it can be resumed later with [`State::restore`].
+    ///
+    /// Every number is written as a LEB128 varint. Connected players are saved as if they
+    /// had lost their connection, and the commands they queued are dropped.
+    pub fn snapshot(&self) -> Vec<u8> {
+        let mut w = Writer(Vec::new());
+        w.0.extend_from_slice(MAGIC);
+        w.u64(VERSION);
+
+        w.u64(self.tick);
+        w.u64(self.tick_frequency.to_bits().into());
+        w.bool(self.paused);
+        w.option(self.winner, |w, team| w.usize(team));
+        w.u64(self.rng.state());
+        w.u64(self.token_rng.state());
+        w.usize(self.next_egg_id);
+
+        w.u64(self.world.width().into());
+        w.u64(self.world.height().into());
+        for y in 0..self.world.height() {
+            for x in 0..self.world.width() {
+                w.objects(self.world.cell(x, y).objects());
+            }
+        }
+
+        w.usize(self.teams.len());
+        for team in &*self.teams {
+            w.bytes(team.name.as_bytes());
+            w.u64(team.available_slots.into());
+            w.option(team.spawn_anchor, |w, (x, y)| {
+                w.u64(x.into());
+                w.u64(y.into());
+            });
+            w.bool(team.joined);
+        }
+
+        let detached = self.detached.iter().map(|d| &d.player);
+        w.usize(self.players.len() + self.detached.len());
+        for player in self.players.iter().chain(detached) {
+            w.usize(player.player_id);
+            w.usize(player.team_id);
+            w.u64(player.level.into());
+            w.u64(player.x.into());
+            w.u64(player.y.into());
+            w.usize(player.orientation as usize);
+            w.objects(&player.inventory);
+            w.u64(player.food_ticks.into());
+            w.option(player.reconnect_token, Writer::u64);
+        }
+
+        for eggs in [&self.eggs, &self.hatched_eggs] {
+            w.usize(eggs.len());
+            for egg in eggs {
+                w.usize(egg.id);
+                w.usize(egg.team_id);
+                w.usize(egg.parent);
+                w.u64(egg.x.into());
+                w.u64(egg.y.into());
+                w.u64(egg.remaining_ticks.into());
+            }
+        }
+
+        w.0
+    }
+
+    /// Replaces the game with the one saved in `snapshot`.
+    ///
+    /// Players are restored as if they had just lost their connection: they may resume
+    /// with their reconnection token during the grace period. When reconnections are
+    /// disabled, they are dropped. Settings that are not part of the game itself, such
+    /// as the win condition or the timing of eggs, are kept.
+    ///
+    /// Nothing changes when the snapshot is invalid.
+    pub fn restore(&mut self, snapshot: &[u8]) -> Result<(), SnapshotError> {
+        let mut r = Reader(snapshot);
+        if r.take(MAGIC.len())? != MAGIC || r.u64()? != VERSION {
+            return Err(SnapshotError::UnknownFormat);
+        }
+
+        let tick = r.u64()?;
+        let tick_frequency = f32::from_bits(r.u32()?);
+        if !is_valid_tick_frequency(tick_frequency) {
+            return Err(SnapshotError::Corrupted("invalid tick frequency"));
+        }
+        let paused = r.bool()?;
+        let winner = r.option(Reader::usize)?;
+        let rng = Rng::from_state(r.u64()?);
+        let token_rng = Rng::from_state(r.u64()?);
+        let next_egg_id = r.usize()?;
+
+        let width = r.u32()?;
+        let height = r.u32()?;
+        if width == 0 || height == 0 {
+            return Err(SnapshotError::Corrupted("empty world"));
+        }
+        let area = width as usize * height as usize;
+        // Every cell takes at least 7 bytes, which bounds the allocation below.
+        if area > r.0.len() / 7 {
+            return Err(SnapshotError::Truncated);
+        }
+        let mut cells = Vec::with_capacity(area);
+        for _ in 0..area {
+            cells.push(r.objects()?);
+        }
+        let world = World::from_objects(width, height, cells);
+
+        let team_count = r.usize()?;
+        let mut teams = Vec::new();
+        for _ in 0..team_count {
+            let name = core::str::from_utf8(r.bytes()?)
+                .map_err(|_| SnapshotError::Corrupted("invalid team name"))?;
+            // The starting inventory is a setting: teams keep the one they were given.
+            let starting_inventory = self
+                .team_id_by_name(name)
+                .map_or(DEFAULT_STARTING_INVENTORY, |id| {
+                    self.teams[id].starting_inventory
+                });
+            teams.push(Team {
+                name: name.into(),
+                available_slots: r.u32()?,
+                spawn_anchor: r.option(|r| Ok((r.u32()?, r.u32()?)))?,
+                joined: r.bool()?,
+                starting_inventory,
+            });
+        }
+        let teams: Box<[Team]> = teams.into();
+        if teams.is_empty() || winner.is_some_and(|team| team >= teams.len()) {
+            return Err(SnapshotError::Corrupted("invalid team"));
+        }
+
+        let player_count = r.usize()?;
+        let mut players = Vec::new();
+        for _ in 0..player_count {
+            let player = Box::new(PlayerState {
+                player_id: r.usize()?,
+                team_id: r.usize()?,
+                // The player reconnects with a new connection, which replaces this one.
+                conn: ft::Fd::from_raw(-1),
+                commands: ArrayVec::new(),
+                level: r.u32()?,
+                x: r.u32()?,
+                y: r.u32()?,
+                orientation: *Orientation::ALL
+                    .get(r.usize()?)
+                    .ok_or(SnapshotError::Corrupted("invalid orientation"))?,
+                inventory: r.objects()?,
+                inventory_changed: false,
+                last_inventory_push: 0,
+                pushed_queue_len: 0,
+                food_ticks: r.u32()?,
+                reconnect_token: r.option(Reader::u64)?,
+                waker: None,
+            });
+            if player.team_id >= teams.len()
+                || player.level == 0
+                || player.x >= width
+                || player.y >= height
+            {
+                return Err(SnapshotError::Corrupted("invalid player"));
+            }
+            players.push(player);
+        }
+
+        let mut eggs = [Vec::new(), Vec::new()];
+        for eggs in &mut eggs {
+            let egg_count = r.usize()?;
+            for _ in 0..egg_count {
+                let egg = Egg {
+                    id: r.usize()?,
+                    team_id: r.usize()?,
+                    parent: r.usize()?,
+                    x: r.u32()?,
+                    y: r.u32()?,
+                    remaining_ticks: r.u32()?,
+                };
+                if egg.team_id >= teams.len() || egg.x >= width || egg.y >= height {
+                    return Err(SnapshotError::Corrupted("invalid egg"));
+                }
+                eggs.push(egg);
+            }
+        }
+        let [eggs, hatched_eggs] = eggs;
+        // Every hatched egg holds one of the slots of its team.
+        for (team_id, team) in teams.iter().enumerate() {
+            let held = hatched_eggs.iter().filter(|e| e.team_id == team_id).count();
+            if (team.available_slots as usize) < held {
+                return Err(SnapshotError::Corrupted("more hatched eggs than slots"));
+            }
+        }
+
+        if !r.0.is_empty() {
+            return Err(SnapshotError::Corrupted("unexpected trailing data"));
+        }
+
+        // The snapshot is valid, the game can be replaced.
+        if teams.len() != self.teams.len()
+            || teams
+                .iter()
+                .zip(&*self.teams)
+                .any(|(a, b)| a.name != b.name)
+        {
+            ft_log::warning!("the restored game replaces the teams passed on the command-line");
+        }
+
+        let next_player_id = players
+            .iter()
+            .map(|p| p.player_id.saturating_add(1))
+            .max()
+            .unwrap_or(0);
+        Client::reserve_ids(next_player_id);
+
+        let (kept, dropped): (Vec<_>, Vec<_>) = players
+            .into_iter()
+            .partition(|p| !self.reconnect_grace.is_zero() && p.reconnect_token.is_some());
+        if !dropped.is_empty() {
+            ft_log::warning!(
+                "{} restored players cannot reconnect and were dropped",
+                dropped.len(),
+            );
+        }
+
+        let mut present = world.object_totals();
+        for player in &kept {
+            for (total, &count) in present.iter_mut().zip(player.inventory.counts()) {
+                *total += u64::from(count);
+            }
+        }
+
+        let grace_ticks = (self.reconnect_grace.as_secs_f32() * tick_frequency) as u64;
+        ft_log::info!(
+            "restored tick {tick}: {} players waiting {grace_ticks} ticks to reconnect",
+            kept.len(),
+        );
+
+        self.teams = teams;
+        self.players.clear();
+        self.elevations.clear();
+        self.detached = kept
+            .into_iter()
+            .map(|player| DetachedPlayer {
+                player,
+                expires_at: tick + grace_ticks,
+            })
+            .collect();
+        self.token_rng = token_rng;
+        self.eggs = eggs;
+        self.hatched_eggs = hatched_eggs;
+        self.next_egg_id = next_egg_id;
+        self.world = world;
+        self.tick_frequency = tick_frequency;
+        self.tick = tick;
+        self.paused = paused;
+        self.winner = winner;
+        self.ledger = ResourceLedger::new(present);
+        self.rng = rng;
+
+        Ok(())
+    }
+}
+
+/// Appends the values of a snapshot to a buffer.
+struct Writer(Vec<u8>);
+
+impl Writer {
+    /// Writes an unsigned number, 7 bits per byte.
+    fn u64(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.0.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        self.0.push(value as u8);
+    }
+
+    /// Writes an index or a count.
+    #[inline]
+    fn usize(&mut self, value: usize) {
+        self.u64(value as u64);
+    }
+
+    /// Writes a boolean.
+    #[inline]
+    fn bool(&mut self, value: bool) {
+        self.u64(value.into());
+    }
+
+    /// Writes an optional value, with `f` when it is present.
+    fn option<T>(&mut self, value: Option<T>, f: impl FnOnce(&mut Self, T)) {
+        self.bool(value.is_some());
+        if let Some(value) = value {
+            f(self, value);
+        }
+    }
+
+    /// Writes a length-prefixed byte string.
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.usize(bytes.len());
+        self.0.extend_from_slice(bytes);
+    }
+
+    /// Writes the number of objects of each class.
+    fn objects(&mut self, objects: &ResourceSet) {
+        for &count in objects.counts() {
+            self.u64(count.into());
+        }
+    }
+}
+
+/// Reads the values of a snapshot, in the order they were written by [`Writer`].
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    /// Reads the next `len` bytes.
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.0.len() < len {
+            return Err(SnapshotError::Truncated);
+        }
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(bytes)
+    }
+
+    /// Reads an unsigned number.
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.take(1)?[0];
+            let bits = u64::from(byte & 0x7F);
+            if shift >= 64 || (bits << shift) >> shift != bits {
+                return Err(SnapshotError::Corrupted("number too large"));
+            }
+            value |= bits << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Reads an unsigned number that must fit in 32 bits.
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        u32::try_from(self.u64()?).map_err(|_| SnapshotError::Corrupted("number too large"))
+    }
+
+    /// Reads an index or a count.
+    fn usize(&mut self) -> Result<usize, SnapshotError> {
+        usize::try_from(self.u64()?).map_err(|_| SnapshotError::Corrupted("number too large"))
+    }
+
+    /// Reads a boolean.
+    fn bool(&mut self) -> Result<bool, SnapshotError> {
+        match self.u64()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SnapshotError::Corrupted("invalid boolean")),
+        }
+    }
+
+    /// Reads an optional value, with `f` when it is present.
+    fn option<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, SnapshotError>,
+    ) -> Result<Option<T>, SnapshotError> {
+        if self.bool()? {
+            f(self).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Reads a length-prefixed byte string.
+    fn bytes(&mut self) -> Result<&'a [u8], SnapshotError> {
+        let len = self.usize()?;
+        self.take(len)
+    }
+
+    /// Reads the number of objects of each class.
+    fn objects(&mut self) -> Result<ResourceSet, SnapshotError> {
+        let mut counts = [0; 7];
+        for count in &mut counts {
+            *count = self.u32()?;
+        }
+        Ok(ResourceSet::from_counts(counts))
+    }
+}
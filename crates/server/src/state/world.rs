@@ -1,48 +1,192 @@
-/// The class of an object.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub enum ObjectClass {
-    /// Food.
-    Food,
-    /// Linemate.
-    Linemate,
-    /// Deraumere.
-    Deraumere,
-    /// Sibur.
-    Sibur,
-    /// Mendiane.
-    Mendiane,
-    /// Phiras.
-    Phiras,
-    /// Thystame.
-    Thystame,
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use resources::{ObjectClass, ResourceSet};
+
+use super::{LookCache, Rng};
+
+/// Returns the average number of objects of the provided class per cell when the world
+/// is generated, and the default one kept by respawns.
+pub fn default_density(class: ObjectClass) -> f32 {
+    match class {
+        ObjectClass::Food => 0.5,
+        ObjectClass::Linemate => 0.3,
+        ObjectClass::Deraumere => 0.15,
+        ObjectClass::Sibur => 0.1,
+        ObjectClass::Mendiane => 0.1,
+        ObjectClass::Phiras => 0.08,
+        ObjectClass::Thystame => 0.05,
+    }
+}
+
+/// The direction a player is facing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    /// Towards the top of the map (decreasing `y`).
+    North,
+    /// Towards the right of the map (increasing `x`).
+    East,
+    /// Towards the bottom of the map (increasing `y`).
+    South,
+    /// Towards the left of the map (decreasing `x`).
+    West,
 }
 
-impl ObjectClass {
-    /// Parses an object class from the provided argument.
-    pub fn from_arg(bytes: &[u8]) -> Option<Self> {
-        match bytes {
-            b"nourriture" => Some(Self::Food),
-            b"linemate" => Some(Self::Linemate),
-            b"deraumere" => Some(Self::Deraumere),
-            b"sibur" => Some(Self::Sibur),
-            b"mendiane" => Some(Self::Mendiane),
-            b"phiras" => Some(Self::Phiras),
-            b"thystame" => Some(Self::Thystame),
-            _ => None,
+impl Orientation {
+    /// All orientations, in clockwise order starting from [`Orientation::North`].
+    pub const ALL: [Self; 4] = [Self::North, Self::East, Self::South, Self::West];
+
+    /// Returns the cell offset of a step in this orientation, `y` growing towards the
+    /// south.
+    pub fn vector(self) -> (i64, i64) {
+        match self {
+            Self::North => (0, -1),
+            Self::East => (1, 0),
+            Self::South => (0, 1),
+            Self::West => (-1, 0),
         }
     }
+
+    /// Returns the orientation obtained by turning right.
+    pub fn right(self) -> Self {
+        Self::ALL[(self as usize + 1) % 4]
+    }
+
+    /// Returns the orientation obtained by turning left.
+    pub fn left(self) -> Self {
+        Self::ALL[(self as usize + 3) % 4]
+    }
+
+    /// Returns the direction a push towards `push` comes from, for a player facing this
+    /// orientation.
+    ///
+    /// Directions are numbered counterclockwise like in `broadcast`: `1` is the front of
+    /// the player, `3` its left, `5` its back and `7` its right.
+    pub fn push_origin(self, push: Self) -> u32 {
+        match (push as usize + 4 - self as usize) % 4 {
+            0 => 5,
+            1 => 3,
+            2 => 1,
+            _ => 7,
+        }
+    }
+
+    /// Returns the number used to represent the orientation in the graphics protocol.
+    #[inline]
+    pub fn to_gfx(self) -> u32 {
+        self as u32 + 1
+    }
+}
+
+/// The content of a cell of the world.
+#[derive(Debug, Clone, Default)]
+pub struct WorldCell {
+    /// The objects lying on the cell.
+    objects: ResourceSet,
+    /// The number of players standing on the cell.
+    players: u32,
+    /// Whether the cell changed during the current tick.
+    dirty: bool,
+}
+
+impl WorldCell {
+    /// Returns the objects lying on the cell.
+    #[inline]
+    pub fn objects(&self) -> &ResourceSet {
+        &self.objects
+    }
+
+    /// Returns the number of players standing on the cell.
+    #[inline]
+    pub fn players(&self) -> u32 {
+        self.players
+    }
 }
 
 /// The world state.
 pub struct World {
     width: u32,
     height: u32,
+    /// The cells of the world, stored row by row.
+    cells: Vec<WorldCell>,
+    /// The indices of the cells that changed during the current tick.
+    dirty_cells: Vec<usize>,
+    /// How the cells are written in `voir` responses during the current tick.
+    look_cache: LookCache,
 }
 
 impl World {
-    /// Creates a new [`World`] with the specified dimensions.
-    pub fn new(width: u32, height: u32) -> Self {
-        Self { width, height }
+    /// Creates a new [`World`] with the specified dimensions, spreading resources randomly
+    /// according to their density.
+    pub fn new(width: u32, height: u32, rng: &mut Rng) -> Self {
+        let area = width as usize * height as usize;
+
+        let mut world = Self {
+            width,
+            height,
+            cells: vec![WorldCell::default(); area],
+            dirty_cells: Vec::new(),
+            look_cache: LookCache::new(area),
+        };
+
+        for class in ObjectClass::ALL {
+            let count = (area as f32 * default_density(class)) as usize;
+            for _ in 0..count {
+                let x = rng.below(width);
+                let y = rng.below(height);
+                let index = world.index(x, y);
+                world.cells[index].objects.add(class);
+            }
+        }
+
+        world
+    }
+
+    /// Spreads objects randomly until the world holds, for every class, at least its
+    /// density (indexed by [`ObjectClass::index`]) times the number of cells.
+    ///
+    /// # Returns
+    ///
+    /// The objects that were added.
+    pub fn replenish(&mut self, densities: &[f32; 7], rng: &mut Rng) -> ResourceSet {
+        let area = self.cells.len() as f32;
+        let totals = self.object_totals();
+
+        let mut added = ResourceSet::default();
+        for class in ObjectClass::ALL {
+            let target = (area * densities[class.index()]) as u64;
+            for _ in totals[class.index()]..target {
+                let x = rng.below(self.width);
+                let y = rng.below(self.height);
+                self.add_object(x, y, class);
+                added.add(class);
+            }
+        }
+        added
+    }
+
+    /// Creates a new [`World`] with the specified dimensions, from the objects lying on
+    /// every cell, stored row by row.
+    ///
+    /// `objects` must hold exactly `width * height` sets.
+    pub fn from_objects(width: u32, height: u32, objects: Vec<ResourceSet>) -> Self {
+        let area = objects.len();
+        debug_assert_eq!(area, width as usize * height as usize);
+
+        Self {
+            width,
+            height,
+            cells: objects
+                .into_iter()
+                .map(|objects| WorldCell {
+                    objects,
+                    ..WorldCell::default()
+                })
+                .collect(),
+            dirty_cells: Vec::new(),
+            look_cache: LookCache::new(area),
+        }
     }
 
     /// Returns the width of the world.
@@ -56,4 +200,222 @@ impl World {
     pub fn height(&self) -> u32 {
         self.height
     }
+
+    /// Returns the index of the cell at the provided position in `cells`.
+    #[inline]
+    fn index(&self, x: u32, y: u32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+
+    /// Returns the cell at the provided position.
+    #[inline]
+    pub fn cell(&self, x: u32, y: u32) -> &WorldCell {
+        &self.cells[self.index(x, y)]
+    }
+
+    /// Adds an object to the cell at the provided position.
+    pub fn add_object(&mut self, x: u32, y: u32, class: ObjectClass) {
+        let index = self.index(x, y);
+        self.cells[index].objects.add(class);
+        self.mark_dirty(index);
+    }
+
+    /// Removes an object from the cell at the provided position.
+    ///
+    /// # Returns
+    ///
+    /// `false` if there was no object of this class on the cell.
+    pub fn remove_object(&mut self, x: u32, y: u32, class: ObjectClass) -> bool {
+        let index = self.index(x, y);
+        if !self.cells[index].objects.remove(class) {
+            return false;
+        }
+        self.mark_dirty(index);
+        true
+    }
+
+    /// Records that a player arrived on the cell at the provided position.
+    pub fn enter(&mut self, (x, y): (u32, u32)) {
+        let index = self.index(x, y);
+        self.cells[index].players += 1;
+        self.look_cache.invalidate(index);
+    }
+
+    /// Records that a player left the cell at the provided position.
+    pub fn leave(&mut self, (x, y): (u32, u32)) {
+        let index = self.index(x, y);
+        let players = &mut self.cells[index].players;
+        *players = players.saturating_sub(1);
+        self.look_cache.invalidate(index);
+    }
+
+    /// Records that a player moved from a cell to another.
+    pub fn move_player(&mut self, from: (u32, u32), to: (u32, u32)) {
+        self.leave(from);
+        self.enter(to);
+    }
+
+    /// Counts the players standing on every cell from their `positions`, and fixes the
+    /// cells whose count is wrong.
+    ///
+    /// `f` is called with the position, the wrong count and the actual number of players
+    /// of every such cell.
+    pub fn recount_players(
+        &mut self,
+        positions: impl Iterator<Item = (u32, u32)>,
+        mut f: impl FnMut(u32, u32, u32, u32),
+    ) {
+        let mut counts = vec![0; self.cells.len()];
+        for (x, y) in positions {
+            counts[self.index(x, y)] += 1;
+        }
+
+        let width = self.width as usize;
+        for (index, (cell, count)) in self.cells.iter_mut().zip(counts).enumerate() {
+            if cell.players != count {
+                f(
+                    (index % width) as u32,
+                    (index / width) as u32,
+                    cell.players,
+                    count,
+                );
+                cell.players = count;
+                self.look_cache.invalidate(index);
+            }
+        }
+    }
+
+    /// Writes the `voir` response of a player standing at `(x, y)`, facing `orientation`
+    /// and seeing `range` rows ahead, to `buf`.
+    ///
+    /// Row `r` holds `2r + 1` cells, from the left of the player to their right.
+    pub fn look(
+        &mut self,
+        (x, y): (u32, u32),
+        orientation: Orientation,
+        range: u32,
+        buf: &mut String,
+    ) {
+        let (dx, dy) = orientation.vector();
+        let (width, height) = (i64::from(self.width), i64::from(self.height));
+
+        buf.push('{');
+        for row in 0..=i64::from(range) {
+            for column in -row..=row {
+                if row != 0 {
+                    buf.push_str(", ");
+                }
+                let cx = (i64::from(x) + row * dx - column * dy).rem_euclid(width);
+                let cy = (i64::from(y) + row * dy + column * dx).rem_euclid(height);
+                let index = self.index(cx as u32, cy as u32);
+                buf.push_str(self.look_cache.fragment(index, &self.cells[index]));
+            }
+        }
+        buf.push_str("}\n");
+    }
+
+    /// Forgets how the cells were written in `voir` responses, at the end of a tick.
+    #[inline]
+    pub fn clear_look_cache(&mut self) {
+        self.look_cache.clear();
+    }
+
+    /// Records that the cell at index `index` changed during the current tick.
+    fn mark_dirty(&mut self, index: usize) {
+        self.look_cache.invalidate(index);
+        let cell = &mut self.cells[index];
+        if !cell.dirty {
+            cell.dirty = true;
+            self.dirty_cells.push(index);
+        }
+    }
+
+    /// Returns the number of objects of each class lying on the world, indexed by
+    /// [`ObjectClass::index`].
+    pub fn object_totals(&self) -> [u64; 7] {
+        let mut totals = [0; 7];
+        for cell in &self.cells {
+            for (total, &count) in totals.iter_mut().zip(cell.objects.counts()) {
+                *total += u64::from(count);
+            }
+        }
+        totals
+    }
+
+    /// Calls `f` with the position and content of every cell that changed since the last
+    /// call, then forgets about those changes.
+    pub fn drain_dirty_cells(&mut self, mut f: impl FnMut(u32, u32, &WorldCell)) {
+        let width = self.width as usize;
+
+        for index in self.dirty_cells.drain(..) {
+            let cell = &mut self.cells[index];
+            cell.dirty = false;
+            f((index % width) as u32, (index / width) as u32, cell);
+        }
+    }
+
+    /// Picks `count` positions as far as possible from each other, taking the wrapping
+    /// of the world into account.
+    ///
+    /// The first position is random, every following one is the cell farthest from the
+    /// ones already picked.
+    pub fn spread_anchors(&self, count: usize, rng: &mut Rng) -> Vec<(u32, u32)> {
+        let mut anchors = Vec::with_capacity(count);
+        if count == 0 {
+            return anchors;
+        }
+        anchors.push((rng.below(self.width), rng.below(self.height)));
+
+        while anchors.len() < count {
+            let mut best = (0, (0, 0));
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let distance = anchors
+                        .iter()
+                        .map(|&anchor| self.distance_squared(anchor, (x, y)))
+                        .min()
+                        .unwrap_or(0);
+                    if distance > best.0 {
+                        best = (distance, (x, y));
+                    }
+                }
+            }
+            anchors.push(best.1);
+        }
+
+        anchors
+    }
+
+    /// Returns the squared distance between two positions, the world wrapping around
+    /// its edges.
+    fn distance_squared(&self, (ax, ay): (u32, u32), (bx, by): (u32, u32)) -> u64 {
+        let dx = ax.abs_diff(bx).min(self.width - ax.abs_diff(bx));
+        let dy = ay.abs_diff(by).min(self.height - ay.abs_diff(by));
+        u64::from(dx) * u64::from(dx) + u64::from(dy) * u64::from(dy)
+    }
+
+    /// Returns a random position at most `radius` cells away from `center` along each
+    /// axis, the world wrapping around its edges.
+    pub fn random_near(&self, (x, y): (u32, u32), radius: u32, rng: &mut Rng) -> (u32, u32) {
+        let offset = |rng: &mut Rng, size: u32| {
+            let span = radius.min(size / 2);
+            (rng.below(2 * span + 1) + size - span) % size
+        };
+        let dx = offset(rng, self.width);
+        let dy = offset(rng, self.height);
+        ((x + dx) % self.width, (y + dy) % self.height)
+    }
+
+    /// Returns the position reached by moving one cell from `(x, y)` in the provided
+    /// direction.
+    ///
+    /// The world wraps around its edges.
+    pub fn step(&self, x: u32, y: u32, orientation: Orientation) -> (u32, u32) {
+        match orientation {
+            Orientation::North => (x, y.checked_sub(1).unwrap_or(self.height - 1)),
+            Orientation::East => ((x + 1) % self.width, y),
+            Orientation::South => (x, (y + 1) % self.height),
+            Orientation::West => (x.checked_sub(1).unwrap_or(self.width - 1), y),
+        }
+    }
 }